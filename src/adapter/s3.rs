@@ -3,6 +3,8 @@ use aws_sdk_s3 as s3;
 use std::error::Error;
 use bytes::Bytes;
 
+use crate::error::MindiaError;
+
 pub struct S3Object {
     pub body: Bytes,
     pub content_type: Option<String>,
@@ -39,7 +41,9 @@ impl S3 {
             .send()
             .await?;
 
-        let upload_id = create_multipart_upload_resp.upload_id.unwrap();
+        let upload_id = create_multipart_upload_resp
+            .upload_id
+            .ok_or_else(|| MindiaError::upstream("S3 did not return an upload id"))?;
 
         for chunk in file_bytes.chunks(4096) {
             let upload_part_resp = self
@@ -53,7 +57,10 @@ impl S3 {
                 .send()
                 .await?;
 
-            completed_parts.insert(part_number, upload_part_resp.e_tag.unwrap());
+            let e_tag = upload_part_resp
+                .e_tag
+                .ok_or_else(|| MindiaError::upstream("S3 did not return an ETag"))?;
+            completed_parts.insert(part_number, e_tag);
             part_number += 1;
         }
 
@@ -82,6 +89,54 @@ impl S3 {
         })
     }
 
+    pub async fn download_object_range(
+        &self,
+        key: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<S3Object, Box<dyn Error>> {
+        let resp = self
+            .client
+            .get_object()
+            .bucket(self.bucket_name.as_str())
+            .key(key)
+            .range(format!("bytes={}-{}", start, end))
+            .send()
+            .await?;
+
+        let body = resp.body.collect().await?.into_bytes();
+        let content_type = resp.content_type;
+        let content_length = resp.content_length;
+
+        Ok(S3Object {
+            body,
+            content_type,
+            content_length,
+        })
+    }
+
+    pub async fn head_object(&self, key: &str) -> Result<Option<i64>, Box<dyn Error>> {
+        let result = self
+            .client
+            .head_object()
+            .bucket(self.bucket_name.as_str())
+            .key(key)
+            .send()
+            .await;
+
+        match result {
+            Ok(resp) => Ok(resp.content_length),
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_not_found() {
+                    Ok(None)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
     pub async fn move_object(&self, src_key: &str, dst_key: &str) -> Result<(), Box<dyn Error>> {
         self.copy_object(src_key, dst_key).await?;
         self.delete_object(src_key).await?;
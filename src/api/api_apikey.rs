@@ -1,49 +1,171 @@
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
-use axum::Json;
+use axum::{Extension, Json};
 use axum::response::IntoResponse;
-use serde::Deserialize;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::api::app_state::AppState;
-use crate::apikey::ApiKey;
+use crate::api::middleware_apikey::Caller;
+use crate::api::project_extractor::CurrentProject;
+use crate::apikey::{is_in_folder, validate_name, ApiKey, Scope};
+use crate::error::MindiaError;
+use crate::handler::ProjectContext;
+use crate::media::path::normalize_folder;
 use crate::utils::generate_apikey;
 
-pub(crate) async fn get_apikeys(State(state): State<AppState>) -> impl IntoResponse {
-    match state.apikey_storage.get_all() {
-        Ok(api_keys) => {
-            let api_keys: Vec<ApiKey> = api_keys.into_iter().map(|(_, v)| v).collect();
-            (StatusCode::OK, Json(api_keys)).into_response()
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
-    }
+pub(crate) async fn get_apikeys(
+    State(state): State<AppState>,
+    CurrentProject(project): CurrentProject,
+) -> Result<impl IntoResponse, MindiaError> {
+    let api_keys = state.apikey_storage.get_all()?;
+    let api_keys: Vec<ApiKey> = api_keys
+        .into_values()
+        .filter(|apikey| apikey.project == project.name)
+        .collect();
+
+    Ok((StatusCode::OK, Json(api_keys)))
 }
 
 #[derive(Deserialize)]
 pub struct SaveApiKeyBody {
     name: String,
+    scopes: Vec<Scope>,
+    #[serde(default)]
+    folders: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// The only response carrying the key in clear, which is never stored.
+#[derive(Serialize)]
+struct IssuedApiKey {
+    #[serde(flatten)]
+    apikey: ApiKey,
+    key: String,
 }
 
 pub(crate) async fn save_apikey(
     State(data): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
     Json(new_apikey): Json<SaveApiKeyBody>,
-) -> impl IntoResponse {
-    let apikey = ApiKey {
-        name: new_apikey.name.clone(),
-        key: generate_apikey(),
-    };
-
-    match data.apikey_storage.save(apikey) {
-        Ok(()) => (StatusCode::OK, format!("API key {} saved", new_apikey.name)),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+) -> Result<impl IntoResponse, MindiaError> {
+    validate_name(&new_apikey.name).map_err(MindiaError::validation)?;
+
+    if new_apikey.scopes.is_empty() {
+        return Err(MindiaError::validation("At least one scope is required"));
+    }
+
+    if new_apikey.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(MindiaError::validation("expires_at must be in the future"));
+    }
+
+    let folders = new_apikey
+        .folders
+        .iter()
+        .map(|folder| normalize_folder(folder))
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(MindiaError::validation)?;
+
+    check_can_grant(&caller, &new_apikey.scopes, &folders)?;
+
+    // Names are unique across projects. A key saved with spaces in its name, before
+    // they were rejected, holds the name spelled with `_`.
+    let taken = data
+        .apikey_storage
+        .get_by_name(&new_apikey.name)?
+        .is_some_and(|apikey| apikey.project != project.name || apikey.name != new_apikey.name);
+    if taken {
+        return Err(MindiaError::conflict("API key name is already taken"));
     }
+
+    let key = generate_apikey();
+    let apikey = ApiKey::new(
+        new_apikey.name,
+        &key,
+        new_apikey.scopes,
+        folders,
+        new_apikey.expires_at,
+    )
+    .with_project(project.name.clone());
+
+    data.apikey_storage.save(apikey.clone())?;
+
+    Ok((StatusCode::OK, Json(IssuedApiKey { apikey, key })))
 }
 
-pub(crate) async fn delete_apikey(
+pub(crate) async fn rotate_apikey(
     State(data): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
     Path(name): Path<String>,
-) -> impl IntoResponse {
-    match data.apikey_storage.delete(&name) {
-        Ok(()) => (StatusCode::OK, format!("API key {} deleted", name)),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+) -> Result<impl IntoResponse, MindiaError> {
+    let mut apikey = get_project_apikey(&data, &project, &name)?;
+
+    check_can_grant(&caller, &apikey.scopes, &apikey.folders)?;
+
+    let key = generate_apikey();
+    apikey.rotate(
+        &key,
+        Duration::seconds(data.config.apikey.rotation_grace_period),
+        Utc::now(),
+    );
+
+    data.apikey_storage.save(apikey.clone())?;
+
+    Ok((StatusCode::OK, Json(IssuedApiKey { apikey, key })))
+}
+
+/// Keys of other projects are answered as missing.
+pub(crate) fn get_project_apikey(
+    state: &AppState,
+    project: &ProjectContext,
+    name: &str,
+) -> Result<ApiKey, MindiaError> {
+    state
+        .apikey_storage
+        .get_by_name(name)?
+        .filter(|apikey| apikey.project == project.name)
+        .ok_or_else(|| MindiaError::not_found("API key not found"))
+}
+
+/// A key can only hand out what it holds itself.
+fn check_can_grant(caller: &Caller, scopes: &[Scope], folders: &[String]) -> Result<(), MindiaError> {
+    for scope in scopes {
+        caller.require_scope(*scope)?;
+    }
+
+    if let Caller::ApiKey(caller_apikey) = caller {
+        let within_caller_folders = caller_apikey.folders.is_empty()
+            || (!folders.is_empty()
+                && folders.iter().all(|folder| {
+                    caller_apikey
+                        .folders
+                        .iter()
+                        .any(|caller_folder| is_in_folder(folder, caller_folder))
+                }));
+        if !within_caller_folders {
+            return Err(MindiaError::forbidden(
+                "API key cannot grant access outside its folders",
+            ));
+        }
     }
+
+    Ok(())
+}
+
+pub(crate) async fn delete_apikey(
+    State(data): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let apikey = get_project_apikey(&data, &project, &name)?;
+
+    // Deleting a key takes as much as creating it.
+    check_can_grant(&caller, &apikey.scopes, &apikey.folders)?;
+
+    data.apikey_storage.delete(&name)?;
+
+    Ok((StatusCode::OK, format!("API key {} deleted", name)))
 }
@@ -1,23 +1,49 @@
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use chrono::Utc;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::json;
 
 use crate::api::app_state::AppState;
+use crate::api::project_extractor::CurrentProject;
+use crate::error::MindiaError;
 use crate::scheduler::{Details, Task, TaskKind};
 
-pub(crate) async fn clear_cache(State(state): State<AppState>) -> impl IntoResponse {
+#[derive(Deserialize, Default)]
+pub(crate) struct ClearCacheBody {
+    /// Keeps the derivatives created less than this many seconds ago.
+    #[serde(default)]
+    older_than: i64,
+    /// Delays the task until then.
+    run_at: Option<DateTime<Utc>>,
+}
+
+pub(crate) async fn clear_cache(
+    State(state): State<AppState>,
+    CurrentProject(project): CurrentProject,
+    body: Option<Json<ClearCacheBody>>,
+) -> Result<impl IntoResponse, MindiaError> {
     let task_scheduler = state.task_scheduler.clone();
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+
+    if body.older_than < 0 {
+        return Err(MindiaError::validation("older_than must not be negative"));
+    }
 
     let task = Task::new(
+        project.name.clone(),
         TaskKind::ClearCache,
         Details::ClearCache {
-            before_date: Utc::now(),
+            before_date: body.run_at.unwrap_or_else(Utc::now) - Duration::seconds(body.older_than),
         },
-    );
+    )
+    .with_run_at(body.run_at);
 
-    match task_scheduler.push(task) {
-        Ok(_) => (StatusCode::OK, "Cache task pushed to scheduler").into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
-    }
+    let task_id = task.id.clone();
+
+    task_scheduler.push(task)?;
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "task_id": task_id }))))
 }
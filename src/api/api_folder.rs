@@ -0,0 +1,58 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde::Deserialize;
+
+use crate::api::app_state::AppState;
+use crate::api::middleware_apikey::Caller;
+use crate::api::project_extractor::CurrentProject;
+use crate::api::rate_limit::{check_rate_limit, ClientIdentity};
+use crate::error::MindiaError;
+use crate::media::path::normalize_folder;
+use crate::ratelimit::RateLimitBudget;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Deserialize)]
+pub(crate) struct ListFolderQuery {
+    cursor: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    recursive: bool,
+}
+
+pub(crate) async fn list_folder(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    identity: ClientIdentity,
+    path: Option<Path<String>>,
+    Query(query): Query<ListFolderQuery>,
+) -> Result<Response, MindiaError> {
+    check_rate_limit(&state, &identity, RateLimitBudget::Metadata)?;
+
+    let folder = match path {
+        Some(Path(path)) => normalize_folder(&path).map_err(MindiaError::validation)?,
+        None => "/".to_string(),
+    };
+
+    caller.require_access(&folder)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(MindiaError::validation(format!(
+            "limit must be between 1 and {}",
+            MAX_LIMIT
+        )));
+    }
+
+    let page = project
+        .media_handler
+        .list_folder(&folder, query.cursor.as_deref(), limit, query.recursive)
+        .await
+        .map_err(MindiaError::from)?;
+
+    Ok((StatusCode::OK, Json(page)).into_response())
+}
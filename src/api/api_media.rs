@@ -1,172 +1,420 @@
-use std::error::Error;
-
-use axum::extract::{Multipart, State};
-use axum::http::StatusCode;
+use axum::extract::multipart::{Field, MultipartError};
+use axum::extract::{Multipart, Path, State};
+use axum::Extension;
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
 use axum::Json;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum_macros::debug_handler;
-use bytes::BytesMut;
-use futures::StreamExt;
-use log::error;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
 
 use crate::api::app_state::AppState;
+use crate::api::conditional_request::Validators;
+use crate::api::middleware_apikey::Caller;
 use crate::api::path_extractor::PathExtractor;
+use crate::api::project_extractor::CurrentProject;
+use crate::api::rate_limit::{check_rate_limit, ClientIdentity};
+use crate::api::request_access::RequestAccess;
 use crate::api::transformation_chain_extractor::TransformationChainExtractor;
-use crate::extractor::TransformationsExtractor;
-use crate::media::path::generate_path;
-use crate::transform::TransformationDescriptorChain;
+use crate::api::utils::{extract_transformation_chains, parse_transformation_list};
+use crate::apikey::Scope;
+use crate::error::MindiaError;
+use crate::media::path::{generate_path, normalize_folder};
+use crate::media::{Path as MediaPath, TempFile};
+use crate::ratelimit::RateLimitBudget;
+use crate::scheduler::{BulkOperation, Details, Task, TaskKind};
+use crate::transform::{FormatPreference, TransformationDescriptorChain};
+use crate::types::ByteRange;
+use crate::utils::sha256_hex;
 
 pub(crate) async fn read_media(
     State(state): State<AppState>,
+    _: RequestAccess,
+    CurrentProject(project): CurrentProject,
+    identity: ClientIdentity,
     PathExtractor(path): PathExtractor,
-) -> impl IntoResponse {
-    match state.media_handler.read(path).await {
-        Ok(Some(metadata)) => (
-            StatusCode::OK,
-            serde_json::to_string_pretty(&metadata).unwrap(),
-        ),
-        Ok(None) => (StatusCode::NOT_FOUND, "Not found".to_string()),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    headers: HeaderMap,
+) -> Result<Response, MindiaError> {
+    check_rate_limit(&state, &identity, RateLimitBudget::Metadata)?;
+
+    let metadata = project
+        .media_handler
+        .read(path)
+        .await
+        .map_err(MindiaError::from)?
+        .ok_or_else(|| MindiaError::not_found("Media not found"))?;
+
+    let body = serde_json::to_string_pretty(&metadata)?;
+
+    let validators = Validators::new(Some(&sha256_hex(body.as_bytes())), metadata.last_modified());
+
+    let mut response_headers = HeaderMap::new();
+    validators.write_headers(&mut response_headers);
+    insert_cache_control(&mut response_headers, &state.config.cache_control.metadata);
+
+    if validators.is_not_modified(&headers) {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
     }
+
+    Ok((StatusCode::OK, response_headers, body).into_response())
 }
 
 pub(crate) async fn download_media(
     State(state): State<AppState>,
+    _: RequestAccess,
+    CurrentProject(project): CurrentProject,
+    identity: ClientIdentity,
     transformation_chain_extractor: TransformationChainExtractor,
     PathExtractor(path): PathExtractor,
-) -> impl IntoResponse {
-    let result = state
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response, MindiaError> {
+    let cache_control = match transformation_chain_extractor.transformation_chain {
+        Some(_) => state.config.cache_control.derived.clone(),
+        None => state.config.cache_control.original.clone(),
+    };
+
+    let vary_accept = transformation_chain_extractor
+        .transformation_chain
+        .as_ref()
+        .is_some_and(|chain| chain.format() == Some(FormatPreference::Auto));
+
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok());
+
+    let media_source = project
         .media_handler
-        .download(path, transformation_chain_extractor.transformation_chain)
-        .await;
-
-    match result {
-        Ok(Some(body)) => (StatusCode::OK, body).into_response(),
-        Ok(None) => (StatusCode::NOT_FOUND, "Not found".to_string()).into_response(),
-        Err(e) => {
-            error!("Error: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        .download(
+            path,
+            transformation_chain_extractor.transformation_chain,
+            accept,
+            || check_rate_limit(&state, &identity, RateLimitBudget::Transformation),
+        )
+        .await
+        .map_err(MindiaError::from)?
+        .ok_or_else(|| MindiaError::not_found("Media not found"))?;
+
+    let content_type = media_source
+        .metadata
+        .content_type
+        .clone()
+        .unwrap_or_else(|| mime::APPLICATION_OCTET_STREAM.to_string());
+
+    let validators = Validators::new(
+        media_source.metadata.content_hash.as_deref(),
+        media_source.metadata.last_modified(),
+    );
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(content_type) = HeaderValue::from_str(&content_type) {
+        response_headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    validators.write_headers(&mut response_headers);
+    insert_cache_control(&mut response_headers, &cache_control);
+    if vary_accept {
+        response_headers.insert(header::VARY, HeaderValue::from_static("accept"));
+    }
+
+    if validators.is_not_modified(&headers) {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    if method == Method::HEAD {
+        response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(media_source.size));
+        return Ok((StatusCode::OK, response_headers).into_response());
+    }
+
+    let range = if validators.if_range_matches(&headers) {
+        headers
+            .get(header::RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(ByteRange::parse)
+    } else {
+        None
+    };
+
+    let (status, body) = match range.map(|range| range.resolve(media_source.size)) {
+        None => (StatusCode::OK, media_source.read().await),
+        Some(Some((start, end))) => {
+            let content_range = format!("bytes {}-{}/{}", start, end, media_source.size);
+            if let Ok(content_range) = HeaderValue::from_str(&content_range) {
+                response_headers.insert(header::CONTENT_RANGE, content_range);
+            }
+
+            (
+                StatusCode::PARTIAL_CONTENT,
+                media_source.read_range(start, end).await,
+            )
         }
+        Some(None) => {
+            let content_range = format!("bytes */{}", media_source.size);
+            if let Ok(content_range) = HeaderValue::from_str(&content_range) {
+                response_headers.insert(header::CONTENT_RANGE, content_range);
+            }
+
+            return Ok((StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response());
+        }
+    };
+
+    let body = body.map_err(MindiaError::from)?;
+
+    project
+        .media_handler
+        .record_served(&media_source, body.len() as u64);
+
+    Ok((status, response_headers, body).into_response())
+}
+
+fn insert_cache_control(response_headers: &mut HeaderMap, cache_control: &str) {
+    if let Ok(cache_control) = HeaderValue::from_str(cache_control) {
+        response_headers.insert(header::CACHE_CONTROL, cache_control);
     }
 }
 
 #[debug_handler]
 pub(crate) async fn upload_media(
     State(state): State<AppState>,
-    PathExtractor(path): PathExtractor,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    identity: ClientIdentity,
+    Path(folder): Path<String>,
     mut multipart: Multipart,
-) -> impl IntoResponse {
-    let named_transformation_storage = state.named_transformation_storage.clone();
-    let transformation_template_registry = state.transformation_template_registry.clone();
+) -> Result<Response, MindiaError> {
+    check_rate_limit(&state, &identity, RateLimitBudget::Upload)?;
+    let folder = normalize_folder(&folder).map_err(MindiaError::validation)?;
+    caller.require_access(&folder)?;
+
+    let max_field_size = state.config.upload.max_field_size;
 
-    let mut filename = String::new();
-    let mut filedata = BytesMut::new();
+    let mut original_filename: Option<String> = None;
+    let mut temp_file: Option<TempFile> = None;
     let mut transformation_chains: Vec<TransformationDescriptorChain> = Vec::new();
 
-    while let Some(mut field) = multipart.next_field().await.unwrap() {
-        let field_name = field.name().unwrap().to_string();
-        let field_data = field.bytes().await.unwrap();
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        let field_name = field.name().unwrap_or_default().to_string();
+        let mut received = 0;
 
         if field_name == "file" {
-            filename = field_name;
-            filedata.extend_from_slice(&field_data);
+            original_filename = field
+                .file_name()
+                .map(sanitize_filename::sanitize)
+                .filter(|filename| !filename.is_empty());
+
+            let file = TempFile::new(state.config.upload.tmp_dir.as_deref())?;
+            let mut writer = tokio::fs::File::create(file.path()).await?;
+
+            while let Some(chunk) = next_chunk(&mut field, &mut received, max_field_size).await? {
+                writer.write_all(&chunk).await?;
+            }
+
+            writer.flush().await?;
+
+            temp_file = Some(file);
         } else if field_name == "transformations" {
-            let mut transformations_str = String::new();
-
-            transformations_str.push_str(std::str::from_utf8(&field_data).unwrap());
-
-            let transformations_json: Value = match serde_json::from_str(&transformations_str) {
-                Ok(json) => json,
-                Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
-            };
-
-            transformation_chains = if let Some(array) = transformations_json.as_array() {
-                let str_array: Vec<&str> = array
-                    .iter()
-                    .map(|v| v.as_str().unwrap_or_default())
-                    .collect();
-                TransformationsExtractor::new(
-                    named_transformation_storage.clone(),
-                    transformation_template_registry.clone(),
-                )
-                .extract(str_array)
-                .unwrap()
-            } else {
-                return (StatusCode::BAD_REQUEST, "Invalid transformations").into_response();
-            };
+            let mut field_data = BytesMut::new();
+
+            while let Some(chunk) = next_chunk(&mut field, &mut received, max_field_size).await? {
+                field_data.extend_from_slice(&chunk);
+            }
+
+            let transformations = parse_transformation_list(&field_data)?;
+            transformation_chains =
+                extract_transformation_chains(&state, &project, &transformations)?;
         }
     }
 
-    let path = match generate_path(format!("{}/{}", path.as_str(), filename).as_str()) {
-        Ok(path) => path,
-        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
-    };
+    let temp_file = temp_file.ok_or_else(|| MindiaError::validation("Missing file field"))?;
 
-    let metadata = match state
+    let filename = original_filename.clone().unwrap_or_else(|| "file".to_string());
+
+    let path = generate_path(format!("{}/{}", folder, filename).as_str())
+        .map_err(MindiaError::validation)?;
+
+    let metadata = project
         .media_handler
-        .upload(path, transformation_chains, filedata)
+        .upload(
+            path,
+            original_filename,
+            transformation_chains,
+            &temp_file,
+            Some(caller.account().to_string()),
+        )
         .await
-    {
-        Ok(metadata) => Some(metadata),
-        Err(e) => None,
-    };
+        .map_err(MindiaError::from)?;
 
-    let json_response = match serde_json::to_string_pretty(&metadata) {
-        Ok(json_response) => json_response,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    Ok((StatusCode::CREATED, Json(metadata)).into_response())
+}
+
+async fn next_chunk(
+    field: &mut Field<'_>,
+    received: &mut usize,
+    max_field_size: usize,
+) -> Result<Option<Bytes>, MindiaError> {
+    let chunk = match field.chunk().await.map_err(multipart_error)? {
+        Some(chunk) => chunk,
+        None => return Ok(None),
     };
 
-    (StatusCode::OK, json_response).into_response()
+    *received += chunk.len();
+
+    if *received > max_field_size {
+        return Err(MindiaError::payload_too_large(format!(
+            "Field {} exceeds the maximum size of {} bytes",
+            field.name().unwrap_or_default(),
+            max_field_size
+        )));
+    }
+
+    Ok(Some(chunk))
+}
+
+fn multipart_error(e: MultipartError) -> MindiaError {
+    match e.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => MindiaError::payload_too_large(e.body_text()),
+        _ => MindiaError::validation(e.body_text()),
+    }
 }
 
 #[derive(Deserialize)]
-struct MoveMediaBody {
+pub(crate) struct RelocateMediaBody {
     src: String,
     dst: String,
 }
 
+impl RelocateMediaBody {
+    fn paths(&self, caller: &Caller) -> Result<(MediaPath, MediaPath), MindiaError> {
+        let src = MediaPath::new(&self.src)
+            .map_err(|e| MindiaError::validation(format!("src: {}", e)))?;
+        let dst = MediaPath::new(&self.dst)
+            .map_err(|e| MindiaError::validation(format!("dst: {}", e)))?;
+
+        caller.require_access(src.as_str())?;
+        caller.require_access(dst.as_str())?;
+
+        Ok((src, dst))
+    }
+}
+
 pub(crate) async fn move_media(
-    State(state): State<AppState>,
-    body: Json<MoveMediaBody>,
-) -> impl IntoResponse {
-    match state
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Json(body): Json<RelocateMediaBody>,
+) -> Result<Response, MindiaError> {
+    let (src, dst) = body.paths(&caller)?;
+
+    let metadata = project
         .media_handler
-        .move_(body.src.clone().into(), body.dst.clone().into())
+        .move_(src, dst)
         .await
-    {
-        Ok(()) => (StatusCode::OK, "Moved").into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
-    }
-}
+        .map_err(MindiaError::from)?;
 
-#[derive(Deserialize)]
-struct CopyMediaBody {
-    src: String,
-    dst: String,
+    Ok((StatusCode::OK, Json(metadata)).into_response())
 }
 
 pub(crate) async fn copy_media(
-    State(state): State<AppState>,
-    body: Json<CopyMediaBody>,
-) -> impl IntoResponse {
-    match state
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Json(body): Json<RelocateMediaBody>,
+) -> Result<Response, MindiaError> {
+    let (src, dst) = body.paths(&caller)?;
+
+    let metadata = project
         .media_handler
-        .copy(body.src.clone().into(), body.dst.clone().into())
+        .copy(src, dst)
         .await
-    {
-        Ok(()) => (StatusCode::OK, "Copied").into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        .map_err(MindiaError::from)?;
+
+    Ok((StatusCode::CREATED, Json(metadata)).into_response())
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BulkMediaBody {
+    operation: BulkOperation,
+    #[serde(default)]
+    paths: Vec<String>,
+    prefix: Option<String>,
+    destination: Option<String>,
+    /// Delays the task until then.
+    run_at: Option<DateTime<Utc>>,
+}
+
+pub(crate) async fn bulk_media(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Json(body): Json<BulkMediaBody>,
+) -> Result<Response, MindiaError> {
+    if body.paths.is_empty() == body.prefix.is_none() {
+        return Err(MindiaError::validation(
+            "Either paths or prefix must be provided",
+        ));
+    }
+
+    if body.operation != BulkOperation::Delete && body.destination.is_none() {
+        return Err(MindiaError::validation(
+            "A destination is required to move or copy media",
+        ));
+    }
+
+    caller.require_scope(match body.operation {
+        BulkOperation::Delete => Scope::MediaDelete,
+        BulkOperation::Move | BulkOperation::Copy => Scope::MediaWrite,
+    })?;
+
+    for path in &body.paths {
+        MediaPath::new(path).map_err(|e| MindiaError::validation(format!("{}: {}", path, e)))?;
+    }
+    let prefix = normalize_optional_folder(body.prefix.as_deref())?;
+    let destination = normalize_optional_folder(body.destination.as_deref())?;
+
+    let targets = body.paths.iter().chain(prefix.iter()).chain(destination.iter());
+    for target in targets {
+        caller.require_access(target)?;
     }
+
+    let task = Task::new(
+        project.name.clone(),
+        TaskKind::BulkMedia,
+        Details::BulkMedia {
+            operation: body.operation,
+            paths: body.paths,
+            prefix,
+            destination,
+            results: Vec::new(),
+        },
+    )
+    .with_run_at(body.run_at);
+    let task_id = task.id.clone();
+
+    state.task_scheduler.push(task)?;
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "task_id": task_id }))).into_response())
+}
+
+fn normalize_optional_folder(folder: Option<&str>) -> Result<Option<String>, MindiaError> {
+    folder
+        .map(normalize_folder)
+        .transpose()
+        .map_err(MindiaError::validation)
 }
 
 pub(crate) async fn delete_media(
-    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
     PathExtractor(path): PathExtractor,
-) -> impl IntoResponse {
-    match state.media_handler.delete(path).await {
-        Ok(()) => (StatusCode::OK, "Deleted").into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
-    }
+) -> Result<Response, MindiaError> {
+    caller.require_access(path.as_str())?;
+
+    project
+        .media_handler
+        .delete(path)
+        .await
+        .map_err(MindiaError::from)?;
+
+    Ok((StatusCode::OK, "Deleted").into_response())
 }
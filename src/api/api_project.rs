@@ -0,0 +1,94 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+use crate::api::app_state::AppState;
+use crate::error::MindiaError;
+use crate::project::{Project, DEFAULT_PROJECT};
+
+pub(crate) async fn get_projects(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let projects = state.projects.storage().get_all()?;
+    let mut projects: Vec<Project> = projects.into_values().collect();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok((StatusCode::OK, Json(projects)))
+}
+
+pub(crate) async fn get_project(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let project = state
+        .projects
+        .storage()
+        .get_by_name(&name)?
+        .ok_or_else(|| MindiaError::not_found("Project not found"))?;
+
+    Ok((StatusCode::OK, Json(project)))
+}
+
+#[derive(Deserialize)]
+pub struct SaveProjectBody {
+    name: String,
+    description: Option<String>,
+}
+
+pub(crate) async fn save_project(
+    State(state): State<AppState>,
+    Json(body): Json<SaveProjectBody>,
+) -> Result<impl IntoResponse, MindiaError> {
+    if !Project::is_valid_name(&body.name) {
+        return Err(MindiaError::validation(
+            "Project names are made of lowercase letters, digits, - and _",
+        ));
+    }
+
+    if body.name == DEFAULT_PROJECT {
+        return Err(MindiaError::conflict("The default project always exists"));
+    }
+
+    let project = match state.projects.storage().get_by_name(&body.name)? {
+        Some(project) => Project {
+            description: body.description,
+            ..project
+        },
+        None => Project::new(body.name, body.description),
+    };
+
+    state.projects.storage().save(project.clone())?;
+
+    Ok((StatusCode::OK, Json(project)))
+}
+
+/// Deletes the project, its API keys and its schedules. Its media are left in
+/// the storages.
+pub(crate) async fn delete_project(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    if name == DEFAULT_PROJECT {
+        return Err(MindiaError::validation("The default project cannot be deleted"));
+    }
+
+    if state.projects.storage().get_by_name(&name)?.is_none() {
+        return Err(MindiaError::not_found("Project not found"));
+    }
+
+    let apikeys = state.apikey_storage.get_all()?;
+    for apikey in apikeys.into_values().filter(|apikey| apikey.project == name) {
+        state.apikey_storage.delete(&apikey.name)?;
+    }
+
+    let schedules = state.task_scheduler.schedules().get_all(Some(&name))?;
+    for schedule in schedules {
+        state.task_scheduler.schedules().delete(&name, &schedule.name)?;
+    }
+
+    state.projects.delete(&name)?;
+
+    Ok((StatusCode::OK, format!("Project {} deleted", name)))
+}
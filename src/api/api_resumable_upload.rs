@@ -0,0 +1,389 @@
+use axum::body::Body;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use crate::api::app_state::AppState;
+use crate::api::conditional_request::format_http_date;
+use crate::api::middleware_apikey::Caller;
+use crate::api::project_extractor::CurrentProject;
+use crate::api::rate_limit::{check_rate_limit, ClientIdentity};
+use crate::api::utils::{extract_transformation_chains, parse_transformation_list};
+use crate::error::MindiaError;
+use crate::media::path::{generate_path, normalize_folder};
+use crate::ratelimit::RateLimitBudget;
+use crate::resumable::ResumableUpload;
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,expiration,termination";
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+static TUS_RESUMABLE: HeaderName = HeaderName::from_static("tus-resumable");
+static TUS_VERSION_HEADER: HeaderName = HeaderName::from_static("tus-version");
+static TUS_EXTENSION: HeaderName = HeaderName::from_static("tus-extension");
+static TUS_MAX_SIZE: HeaderName = HeaderName::from_static("tus-max-size");
+static UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+static UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+static UPLOAD_METADATA: HeaderName = HeaderName::from_static("upload-metadata");
+static UPLOAD_EXPIRES: HeaderName = HeaderName::from_static("upload-expires");
+/// Not part of tus: tells the client where the completed upload was stored.
+static MEDIA_PATH: HeaderName = HeaderName::from_static("mindia-media-path");
+
+pub(crate) async fn options_resumable_upload(State(state): State<AppState>) -> impl IntoResponse {
+    let mut response_headers = tus_headers();
+    response_headers.insert(TUS_VERSION_HEADER.clone(), HeaderValue::from_static(TUS_VERSION));
+    response_headers.insert(TUS_EXTENSION.clone(), HeaderValue::from_static(TUS_EXTENSIONS));
+    response_headers.insert(
+        TUS_MAX_SIZE.clone(),
+        HeaderValue::from(state.config.upload.max_field_size),
+    );
+
+    (StatusCode::NO_CONTENT, response_headers)
+}
+
+pub(crate) async fn create_resumable_upload(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    identity: ClientIdentity,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(response) = check_tus_resumable(&headers) {
+        return response;
+    }
+
+    if let Err(e) = check_rate_limit(&state, &identity, RateLimitBudget::Upload) {
+        return tus_error(e);
+    }
+
+    let length = match header_u64(&headers, &UPLOAD_LENGTH) {
+        Some(length) => length,
+        None => return tus_error(MindiaError::validation("Missing or invalid Upload-Length")),
+    };
+
+    if length > state.config.upload.max_field_size as u64 {
+        return tus_error(MindiaError::payload_too_large(format!(
+            "Upload-Length exceeds the maximum size of {} bytes",
+            state.config.upload.max_field_size
+        )));
+    }
+
+    let metadata = match headers.get(&UPLOAD_METADATA).map(parse_upload_metadata) {
+        Some(Ok(metadata)) => metadata,
+        Some(Err(e)) => return tus_error(MindiaError::validation(e)),
+        None => Vec::new(),
+    };
+
+    let metadata_value = |key: &str| {
+        metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+
+    let folder = match metadata_value("folder").map(|folder| normalize_folder(&folder)) {
+        Some(Ok(folder)) if folder != "/" => folder,
+        Some(Err(e)) => return tus_error(MindiaError::validation(e)),
+        _ => return tus_error(MindiaError::validation("Missing folder in Upload-Metadata")),
+    };
+
+    if let Err(e) = caller.require_access(&folder) {
+        return tus_error(e);
+    }
+
+    let filename = metadata_value("filename")
+        .or_else(|| metadata_value("name"))
+        .map(|filename| sanitize_filename::sanitize(filename))
+        .filter(|filename| !filename.is_empty());
+
+    let transformations = match metadata_value("transformations") {
+        Some(transformations) => match parse_transformation_list(transformations.as_bytes()) {
+            Ok(transformations) => transformations,
+            Err(e) => return tus_error(e),
+        },
+        None => Vec::new(),
+    };
+
+    // Fail early rather than after the whole file has been sent.
+    if let Err(e) = extract_transformation_chains(&state, &project, &transformations) {
+        return tus_error(e);
+    }
+
+    let upload = match project
+        .resumable_upload_handler
+        .create(
+            length,
+            folder,
+            filename,
+            transformations,
+            Some(caller.account().to_string()),
+        )
+        .await
+    {
+        Ok(upload) => upload,
+        Err(e) => return tus_error(e),
+    };
+
+    let mut response_headers = upload_headers(&upload);
+    let location = format!("/api/v0{}/uploads/{}", project.url_prefix(), upload.id);
+    if let Ok(location) = HeaderValue::from_str(&location) {
+        response_headers.insert(header::LOCATION, location);
+    }
+
+    (StatusCode::CREATED, response_headers).into_response()
+}
+
+pub(crate) async fn head_resumable_upload(
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(response) = check_tus_resumable(&headers) {
+        return response;
+    }
+
+    let upload = match project.resumable_upload_handler.get(&id).await {
+        Ok(Some(upload)) if is_owner(&caller, &upload) => upload,
+        Ok(_) => return tus_error(MindiaError::not_found("Upload not found")),
+        Err(e) => return tus_error(e),
+    };
+
+    let mut response_headers = upload_headers(&upload);
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+    (StatusCode::OK, response_headers).into_response()
+}
+
+pub(crate) async fn patch_resumable_upload(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> impl IntoResponse {
+    if let Err(response) = check_tus_resumable(&headers) {
+        return response;
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some(OFFSET_CONTENT_TYPE) {
+        return tus_status_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            MindiaError::validation("Content-Type must be application/offset+octet-stream"),
+        );
+    }
+
+    let offset = match header_u64(&headers, &UPLOAD_OFFSET) {
+        Some(offset) => offset,
+        None => return tus_error(MindiaError::validation("Missing or invalid Upload-Offset")),
+    };
+
+    let resumable_upload_handler = project.resumable_upload_handler.clone();
+
+    let _lock = match resumable_upload_handler.lock(&id) {
+        Ok(Some(lock)) => lock,
+        Ok(None) => {
+            return tus_status_error(
+                StatusCode::LOCKED,
+                MindiaError::conflict("Upload is being written by another request"),
+            )
+        }
+        Err(e) => return tus_error(e),
+    };
+
+    let mut upload = match resumable_upload_handler.get(&id).await {
+        Ok(Some(upload)) if is_owner(&caller, &upload) => upload,
+        Ok(_) => return tus_error(MindiaError::not_found("Upload not found")),
+        Err(e) => return tus_error(e),
+    };
+
+    if upload.media_path.is_some() || upload.offset != offset {
+        return tus_error(MindiaError::conflict("Upload-Offset does not match the upload"));
+    }
+
+    if let Err(e) = resumable_upload_handler
+        .append(&mut upload, body.into_data_stream())
+        .await
+    {
+        return tus_error(e);
+    }
+
+    let mut response_headers = upload_headers(&upload);
+
+    if upload.is_complete() {
+        let transformation_chains =
+            match extract_transformation_chains(&state, &project, &upload.transformations) {
+                Ok(transformation_chains) => transformation_chains,
+                Err(e) => return tus_error(e),
+            };
+
+        let filename = upload.filename.clone().unwrap_or_else(|| "file".to_string());
+        let path = match generate_path(format!("{}/{}", upload.folder, filename).as_str()) {
+            Ok(path) => path,
+            Err(e) => return tus_error(MindiaError::validation(e)),
+        };
+
+        match resumable_upload_handler
+            .complete(&mut upload, path, transformation_chains)
+            .await
+        {
+            Ok(metadata) => {
+                if let Ok(media_path) = HeaderValue::from_str(metadata.path.as_str()) {
+                    response_headers.insert(MEDIA_PATH.clone(), media_path);
+                }
+            }
+            Err(e) => return tus_error(e),
+        }
+    }
+
+    (StatusCode::NO_CONTENT, response_headers).into_response()
+}
+
+pub(crate) async fn delete_resumable_upload(
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(response) = check_tus_resumable(&headers) {
+        return response;
+    }
+
+    let resumable_upload_handler = project.resumable_upload_handler.clone();
+
+    let _lock = match resumable_upload_handler.lock(&id) {
+        Ok(Some(lock)) => lock,
+        Ok(None) => {
+            return tus_status_error(
+                StatusCode::LOCKED,
+                MindiaError::conflict("Upload is being written by another request"),
+            )
+        }
+        Err(e) => return tus_error(e),
+    };
+
+    let upload = match resumable_upload_handler.get(&id).await {
+        Ok(Some(upload)) if is_owner(&caller, &upload) => upload,
+        Ok(_) => return tus_error(MindiaError::not_found("Upload not found")),
+        Err(e) => return tus_error(e),
+    };
+
+    match resumable_upload_handler.terminate(&upload).await {
+        Ok(()) => (StatusCode::NO_CONTENT, tus_headers()).into_response(),
+        Err(e) => tus_error(e),
+    }
+}
+
+/// Uploads of other accounts are answered as missing. The master key owns them all.
+fn is_owner(caller: &Caller, upload: &ResumableUpload) -> bool {
+    match caller {
+        Caller::MasterKey => true,
+        Caller::ApiKey(_) => upload.owner.as_deref() == Some(caller.account()),
+    }
+}
+
+fn tus_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(TUS_RESUMABLE.clone(), HeaderValue::from_static(TUS_VERSION));
+    headers
+}
+
+fn upload_headers(upload: &ResumableUpload) -> HeaderMap {
+    let mut headers = tus_headers();
+    headers.insert(UPLOAD_OFFSET.clone(), HeaderValue::from(upload.offset));
+    headers.insert(UPLOAD_LENGTH.clone(), HeaderValue::from(upload.length));
+
+    if let Ok(expires) = HeaderValue::from_str(&format_http_date(upload.expires_at)) {
+        headers.insert(UPLOAD_EXPIRES.clone(), expires);
+    }
+
+    if let Some(media_path) = upload
+        .media_path
+        .as_deref()
+        .and_then(|media_path| HeaderValue::from_str(media_path).ok())
+    {
+        headers.insert(MEDIA_PATH.clone(), media_path);
+    }
+
+    headers
+}
+
+fn tus_error(error: impl Into<MindiaError>) -> Response {
+    (tus_headers(), error.into()).into_response()
+}
+
+/// For the statuses tus prescribes that have no matching error code.
+fn tus_status_error(status: StatusCode, error: MindiaError) -> Response {
+    (status, tus_headers(), error).into_response()
+}
+
+fn check_tus_resumable(headers: &HeaderMap) -> Result<(), Response> {
+    match headers.get(&TUS_RESUMABLE) {
+        Some(version) if version == TUS_VERSION => Ok(()),
+        _ => {
+            let mut response_headers = tus_headers();
+            response_headers.insert(TUS_VERSION_HEADER.clone(), HeaderValue::from_static(TUS_VERSION));
+            Err((
+                StatusCode::PRECONDITION_FAILED,
+                response_headers,
+                MindiaError::validation("Unsupported tus version"),
+            )
+                .into_response())
+        }
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &HeaderName) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+/// Decodes `Upload-Metadata`: comma separated pairs of a key and a base64 value,
+/// the value being optional.
+fn parse_upload_metadata(value: &HeaderValue) -> Result<Vec<(String, String)>, String> {
+    let value = value.to_str().map_err(|_| "Invalid Upload-Metadata".to_string())?;
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, encoded) = pair.split_once(' ').unwrap_or((pair, ""));
+
+            let decoded = BASE64
+                .decode(encoded.trim())
+                .map_err(|_| format!("Invalid base64 value for {}", key))?;
+            let decoded = String::from_utf8(decoded)
+                .map_err(|_| format!("Invalid UTF-8 value for {}", key))?;
+
+            Ok((key.to_string(), decoded))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_upload_metadata() {
+        let value = HeaderValue::from_static("filename d29ybGRfZG9taW5hdGlvbi5qcGc=,folder cHJvZHVjdHM=,is_confidential");
+
+        let metadata = parse_upload_metadata(&value).unwrap();
+
+        assert_eq!(
+            metadata,
+            vec![
+                ("filename".to_string(), "world_domination.jpg".to_string()),
+                ("folder".to_string(), "products".to_string()),
+                ("is_confidential".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_upload_metadata(&HeaderValue::from_static("filename !!!")).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::api::api_task::required_scope;
+use crate::api::app_state::AppState;
+use crate::api::middleware_apikey::Caller;
+use crate::api::project_extractor::CurrentProject;
+use crate::error::MindiaError;
+use crate::project::Project;
+use crate::scheduler::{Schedule, ScheduledJob};
+
+/// A schedule needs the scope of the tasks it pushes.
+fn check_can_schedule(
+    caller: &Caller,
+    project: &str,
+    job: &ScheduledJob,
+) -> Result<(), MindiaError> {
+    caller.require_scope(required_scope(
+        &job.to_task(project.to_string(), Utc::now()),
+    ))?;
+
+    if let ScheduledJob::BulkMedia {
+        paths,
+        prefix,
+        destination,
+        ..
+    } = job
+    {
+        for target in paths.iter().chain(prefix.iter()).chain(destination.iter()) {
+            caller.require_access(target)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn get_project_schedule(
+    state: &AppState,
+    caller: &Caller,
+    project: &str,
+    name: &str,
+) -> Result<Schedule, MindiaError> {
+    let schedule = state
+        .task_scheduler
+        .schedules()
+        .get(project, name)?
+        .ok_or_else(|| MindiaError::not_found("Schedule not found"))?;
+
+    check_can_schedule(caller, project, &schedule.job)?;
+
+    Ok(schedule)
+}
+
+pub(crate) async fn get_schedules(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+) -> Result<impl IntoResponse, MindiaError> {
+    let schedules: Vec<Schedule> = state
+        .task_scheduler
+        .schedules()
+        .get_all(Some(&project.name))?
+        .into_iter()
+        .filter(|schedule| check_can_schedule(&caller, &project.name, &schedule.job).is_ok())
+        .collect();
+
+    Ok((StatusCode::OK, Json(schedules)))
+}
+
+pub(crate) async fn get_schedule(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let schedule = get_project_schedule(&state, &caller, &project.name, &name)?;
+
+    Ok((StatusCode::OK, Json(schedule)))
+}
+
+#[derive(Deserialize)]
+pub struct SaveScheduleBody {
+    name: String,
+    cron: String,
+    job: ScheduledJob,
+}
+
+pub(crate) async fn save_schedule(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Json(body): Json<SaveScheduleBody>,
+) -> Result<impl IntoResponse, MindiaError> {
+    if !Project::is_valid_name(&body.name) {
+        return Err(MindiaError::validation(
+            "Schedule names are made of lowercase letters, digits, - and _",
+        ));
+    }
+
+    check_can_schedule(&caller, &project.name, &body.job)?;
+
+    let existing = state
+        .task_scheduler
+        .schedules()
+        .get(&project.name, &body.name)?;
+    if existing
+        .as_ref()
+        .is_some_and(|existing| existing.from_config)
+    {
+        return Err(MindiaError::conflict("Schedule is defined in config.toml"));
+    }
+
+    let mut schedule = Schedule::new(body.name, project.name.clone(), body.cron, body.job, false)
+        .map_err(MindiaError::validation)?;
+    if let Some(existing) = existing {
+        schedule.last_run_at = existing.last_run_at;
+        schedule.last_task_id = existing.last_task_id;
+    }
+
+    state.task_scheduler.schedules().save(&schedule)?;
+
+    Ok((StatusCode::OK, Json(schedule)))
+}
+
+pub(crate) async fn delete_schedule(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let schedule = get_project_schedule(&state, &caller, &project.name, &name)?;
+    if schedule.from_config {
+        return Err(MindiaError::conflict("Schedule is defined in config.toml"));
+    }
+
+    state
+        .task_scheduler
+        .schedules()
+        .delete(&project.name, &name)?;
+
+    Ok((StatusCode::OK, format!("Schedule {} deleted", name)))
+}
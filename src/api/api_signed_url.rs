@@ -0,0 +1,83 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::api::app_state::AppState;
+use crate::api::middleware_apikey::Caller;
+use crate::api::project_extractor::CurrentProject;
+use crate::api::url_signer::UrlSigner;
+use crate::api::utils::parse_transformation_from_path;
+use crate::error::MindiaError;
+use crate::media::Path;
+
+#[derive(Deserialize)]
+pub(crate) struct SignUrlBody {
+    path: String,
+    transformations: Option<String>,
+    /// Lifetime of the URL in seconds.
+    expires_in: Option<i64>,
+}
+
+pub(crate) async fn sign_url(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Json(body): Json<SignUrlBody>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let signing = &state.config.signing;
+
+    let secret = signing
+        .secret
+        .as_deref()
+        .ok_or_else(|| MindiaError::forbidden("Signed URLs are disabled"))?;
+
+    let expires_in = body.expires_in.unwrap_or(signing.default_ttl);
+    if expires_in <= 0 || expires_in > signing.max_ttl {
+        return Err(MindiaError::validation(format!(
+            "expires_in must be between 1 and {}",
+            signing.max_ttl
+        )));
+    }
+
+    Path::new(&body.path).map_err(MindiaError::validation)?;
+    caller.require_access(&body.path)?;
+
+    // Normalize the chain the same way the download route will read it back.
+    let request_path = match body.transformations.as_deref() {
+        Some(transformations) if !transformations.is_empty() => {
+            format!("/{}{}", transformations.trim_matches('/'), body.path)
+        }
+        _ => body.path.clone(),
+    };
+    let (transformation_chain, media_path) = parse_transformation_from_path(&request_path);
+
+    if media_path != body.path {
+        return Err(MindiaError::validation("Invalid transformations"));
+    }
+
+    let expires = Utc::now().timestamp() + expires_in;
+    let signature = UrlSigner::new(secret).sign(
+        &format!("{}{}", project.url_prefix(), media_path),
+        &transformation_chain,
+        expires,
+    );
+
+    let url = format!(
+        "/api/v0{}/media{}?expires={}&signature={}",
+        project.url_prefix(),
+        request_path,
+        expires,
+        signature
+    );
+
+    let expires_at = Utc.timestamp_opt(expires, 0).single();
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "url": url, "expires_at": expires_at })),
+    ))
+}
@@ -0,0 +1,172 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use serde::Deserialize;
+
+use crate::api::app_state::AppState;
+use crate::api::middleware_apikey::Caller;
+use crate::api::project_extractor::CurrentProject;
+use crate::apikey::Scope;
+use crate::error::MindiaError;
+use crate::handler::ProjectContext;
+use crate::scheduler::{BulkOperation, Details, Task, TaskFilter, TaskKind, TaskStatus};
+
+const DEFAULT_LIST_LIMIT: usize = 100;
+const MAX_LIST_LIMIT: usize = 1000;
+
+/// The scope needed to create the task, which is also needed to see or cancel it.
+pub(crate) fn required_scope(task: &Task) -> Scope {
+    match &task.details {
+        Details::ClearCache { .. } => Scope::TransformationsAdmin,
+        Details::BulkMedia {
+            operation: BulkOperation::Delete,
+            ..
+        } => Scope::MediaDelete,
+        Details::BulkMedia { .. } => Scope::MediaWrite,
+        Details::WebhookDelivery { .. } => Scope::WebhooksAdmin,
+    }
+}
+
+/// Tasks of other projects are answered as missing.
+fn get_project_task(
+    state: &AppState,
+    caller: &Caller,
+    project: &ProjectContext,
+    id: &str,
+) -> Result<Task, MindiaError> {
+    let task = state
+        .task_scheduler
+        .storage()
+        .get(id)?
+        .filter(|task| task.project == project.name)
+        .ok_or_else(|| MindiaError::not_found("Task not found"))?;
+
+    caller.require_scope(required_scope(&task))?;
+
+    Ok(task)
+}
+
+fn list_limit(limit: Option<usize>) -> Result<usize, MindiaError> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if limit == 0 || limit > MAX_LIST_LIMIT {
+        return Err(MindiaError::validation(format!(
+            "limit must be between 1 and {}",
+            MAX_LIST_LIMIT
+        )));
+    }
+
+    Ok(limit)
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TasksQuery {
+    status: Option<TaskStatus>,
+    kind: Option<TaskKind>,
+    limit: Option<usize>,
+}
+
+pub(crate) async fn get_tasks(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Query(query): Query<TasksQuery>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let limit = list_limit(query.limit)?;
+
+    let filter = TaskFilter {
+        project: Some(project.name.clone()),
+        status: query.status,
+        kind: query.kind,
+    };
+
+    let tasks: Vec<Task> = state
+        .task_scheduler
+        .storage()
+        .list(&filter, limit)?
+        .into_iter()
+        .filter(|task| caller.has_scope(required_scope(task)))
+        .collect();
+
+    Ok((StatusCode::OK, Json(tasks)))
+}
+
+/// Failed tasks, oldest first.
+pub(crate) async fn get_dead_letters(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Query(query): Query<TasksQuery>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let limit = list_limit(query.limit)?;
+
+    let filter = TaskFilter {
+        project: Some(project.name.clone()),
+        status: Some(TaskStatus::Failed),
+        kind: query.kind,
+    };
+
+    let tasks: Vec<Task> = state
+        .task_scheduler
+        .storage()
+        .list_dead_letters(&filter, limit)?
+        .into_iter()
+        .filter(|task| caller.has_scope(required_scope(task)))
+        .collect();
+
+    Ok((StatusCode::OK, Json(tasks)))
+}
+
+pub(crate) async fn get_task(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let task = get_project_task(&state, &caller, &project, &id)?;
+
+    Ok((StatusCode::OK, Json(task)))
+}
+
+/// A task being run stops before its next batch, and its result is discarded.
+pub(crate) async fn cancel_task(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let mut task = get_project_task(&state, &caller, &project, &id)?;
+
+    if task.status.is_finished() {
+        return Err(MindiaError::conflict(format!(
+            "Task is already {}",
+            task.status.to_string().to_lowercase()
+        )));
+    }
+
+    task.set_status(TaskStatus::Cancelled);
+    state.task_scheduler.storage().save(&task)?;
+
+    Ok((StatusCode::OK, Json(task)))
+}
+
+/// Gives a failed task a new round of attempts.
+pub(crate) async fn requeue_task(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let mut task = get_project_task(&state, &caller, &project, &id)?;
+
+    if task.status != TaskStatus::Failed {
+        return Err(MindiaError::conflict("Only failed tasks can be requeued"));
+    }
+
+    task.attempts = 0;
+    task.run_at = None;
+    task.set_status(TaskStatus::Queued);
+    state.task_scheduler.storage().requeue(task.clone())?;
+
+    Ok((StatusCode::OK, Json(task)))
+}
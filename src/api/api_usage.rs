@@ -0,0 +1,77 @@
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::app_state::AppState;
+use crate::api::api_apikey::get_project_apikey;
+use crate::api::middleware_apikey::Caller;
+use crate::api::project_extractor::CurrentProject;
+use crate::apikey::Scope;
+use crate::config::QuotaConfig;
+use crate::error::MindiaError;
+use crate::usage::DailyUsage;
+
+const DEFAULT_RANGE_DAYS: i64 = 30;
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Deserialize)]
+pub(crate) struct UsageQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    /// Name of the API key to report on, the caller's own by default.
+    key: Option<String>,
+}
+
+#[derive(Serialize)]
+struct UsageReport {
+    account: String,
+    bytes_stored: u64,
+    quota: QuotaConfig,
+    days: Vec<DailyUsage>,
+}
+
+pub(crate) async fn get_usage(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    CurrentProject(project): CurrentProject,
+    Query(query): Query<UsageQuery>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let account = query.key.unwrap_or_else(|| caller.account().to_string());
+    if account != caller.account() {
+        caller.require_scope(Scope::KeysAdmin)?;
+        get_project_apikey(&state, &project, &account)?;
+    }
+
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query
+        .from
+        .unwrap_or_else(|| to - Duration::days(DEFAULT_RANGE_DAYS - 1));
+
+    if from > to {
+        return Err(MindiaError::validation("from must not be after to"));
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(MindiaError::validation(format!(
+            "The range cannot exceed {} days",
+            MAX_RANGE_DAYS
+        )));
+    }
+
+    let storage = state.usage_tracker.storage();
+    let days = storage.get_daily(&account, from, to)?;
+    let bytes_stored = storage.get_bytes_stored(&account)?;
+    let quota = state.usage_tracker.quota_for(&account).clone();
+
+    Ok((
+        StatusCode::OK,
+        Json(UsageReport {
+            account,
+            bytes_stored,
+            quota,
+            days,
+        }),
+    ))
+}
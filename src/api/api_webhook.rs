@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::app_state::AppState;
+use crate::api::project_extractor::CurrentProject;
+use crate::error::MindiaError;
+use crate::handler::ProjectContext;
+use crate::utils::generate_apikey;
+use crate::webhook::{resolve_webhook_url, validate_name, Webhook, WebhookEvent, WebhookStorage};
+
+/// A webhook as answered by the API. The secret is only sent back on creation.
+#[derive(Serialize)]
+struct WebhookResponse {
+    name: String,
+    url: String,
+    events: Vec<WebhookEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl WebhookResponse {
+    fn new(webhook: Webhook, with_secret: bool) -> Self {
+        Self {
+            name: webhook.name,
+            url: webhook.url,
+            events: webhook.events,
+            secret: with_secret.then_some(webhook.secret),
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+fn webhook_storage(project: &ProjectContext) -> Result<&Arc<dyn WebhookStorage>, MindiaError> {
+    project
+        .webhook_storage
+        .as_ref()
+        .ok_or_else(|| MindiaError::internal("Webhooks are not enabled"))
+}
+
+pub(crate) async fn get_webhooks(
+    CurrentProject(project): CurrentProject,
+) -> Result<impl IntoResponse, MindiaError> {
+    let mut webhooks: Vec<WebhookResponse> = webhook_storage(&project)?
+        .get_all()?
+        .into_values()
+        .map(|webhook| WebhookResponse::new(webhook, false))
+        .collect();
+    webhooks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok((StatusCode::OK, Json(webhooks)))
+}
+
+#[derive(Deserialize)]
+pub struct SaveWebhookBody {
+    name: String,
+    url: String,
+    events: Vec<WebhookEvent>,
+    /// Generated when creating a webhook without one, kept when updating it.
+    secret: Option<String>,
+}
+
+pub(crate) async fn save_webhook(
+    State(state): State<AppState>,
+    CurrentProject(project): CurrentProject,
+    Json(body): Json<SaveWebhookBody>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let webhook_storage = webhook_storage(&project)?;
+
+    validate_name(&body.name).map_err(MindiaError::validation)?;
+
+    let url = reqwest::Url::parse(&body.url)
+        .map_err(|_| MindiaError::validation("url must be a valid URL"))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(MindiaError::validation("url must be an http or https URL"));
+    }
+
+    // Checked again before each delivery, in case the host resolves elsewhere.
+    resolve_webhook_url(&url, &state.config.webhook.allowed_hosts)
+        .await
+        .map_err(MindiaError::validation)?;
+
+    if body.events.is_empty() {
+        return Err(MindiaError::validation("At least one event is required"));
+    }
+
+    let existing = webhook_storage.get_by_name(&body.name)?;
+    let created = existing.is_none();
+
+    let webhook = Webhook {
+        secret: body
+            .secret
+            .or_else(|| existing.as_ref().map(|webhook| webhook.secret.clone()))
+            .unwrap_or_else(generate_apikey),
+        created_at: existing.map_or_else(Utc::now, |webhook| webhook.created_at),
+        name: body.name,
+        url: body.url,
+        events: body.events,
+    };
+
+    webhook_storage.save(webhook.clone())?;
+
+    Ok((StatusCode::OK, Json(WebhookResponse::new(webhook, created))))
+}
+
+pub(crate) async fn delete_webhook(
+    CurrentProject(project): CurrentProject,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let webhook_storage = webhook_storage(&project)?;
+
+    if webhook_storage.get_by_name(&name)?.is_none() {
+        return Err(MindiaError::not_found("Webhook not found"));
+    }
+    webhook_storage.delete(&name)?;
+
+    Ok((StatusCode::OK, format!("Webhook {} deleted", name)))
+}
+
+pub(crate) async fn get_webhook_deliveries(
+    CurrentProject(project): CurrentProject,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, MindiaError> {
+    let webhook_storage = webhook_storage(&project)?;
+
+    if webhook_storage.get_by_name(&name)?.is_none() {
+        return Err(MindiaError::not_found("Webhook not found"));
+    }
+
+    Ok((StatusCode::OK, Json(webhook_storage.get_attempts(&name)?)))
+}
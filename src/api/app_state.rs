@@ -2,17 +2,19 @@ use std::sync::Arc;
 
 use crate::apikey::ApiKeyStorage;
 use crate::config::Config;
-use crate::handler::{CacheHandler, MediaHandler};
+use crate::handler::ProjectRegistry;
+use crate::ratelimit::RateLimiter;
 use crate::scheduler::TaskScheduler;
-use crate::transform::{NamedTransformationStorage, TransformationTemplateRegistry};
+use crate::transform::TransformationTemplateRegistry;
+use crate::usage::UsageTracker;
 
 #[derive(Clone)]
 pub(crate) struct AppState {
     pub apikey_storage: Arc<dyn ApiKeyStorage>,
-    pub named_transformation_storage: Arc<dyn NamedTransformationStorage>,
+    pub projects: Arc<ProjectRegistry>,
     pub transformation_template_registry: Arc<TransformationTemplateRegistry>,
-    pub media_handler: MediaHandler,
-    pub cache_handler: CacheHandler,
     pub task_scheduler: Arc<TaskScheduler>,
+    pub rate_limiter: Arc<dyn RateLimiter>,
+    pub usage_tracker: UsageTracker,
     pub config: Config,
 }
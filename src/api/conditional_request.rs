@@ -0,0 +1,134 @@
+use axum::http::{header, HeaderMap, HeaderValue};
+use chrono::{DateTime, Utc};
+
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+const WEAK_ETAG_PREFIX: &str = "W/";
+
+/// Validators of a representation, used to answer conditional requests.
+pub(crate) struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl Validators {
+    pub fn new(content_hash: Option<&str>, last_modified: DateTime<Utc>) -> Self {
+        Self {
+            etag: content_hash.map(|content_hash| format!("\"{}\"", content_hash)),
+            last_modified,
+        }
+    }
+
+    pub fn write_headers(&self, response_headers: &mut HeaderMap) {
+        if let Some(etag) = self.etag.as_ref().and_then(|etag| HeaderValue::from_str(etag).ok()) {
+            response_headers.insert(header::ETAG, etag);
+        }
+
+        if let Ok(last_modified) = HeaderValue::from_str(&format_http_date(self.last_modified)) {
+            response_headers.insert(header::LAST_MODIFIED, last_modified);
+        }
+    }
+
+    /// `If-None-Match` takes precedence over `If-Modified-Since` when both are sent.
+    pub fn is_not_modified(&self, request_headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = header_str(request_headers, header::IF_NONE_MATCH) {
+            return if_none_match.split(',').map(str::trim).any(|candidate| {
+                candidate == "*"
+                    || self.etag.as_deref().map(strip_weak_prefix) == Some(strip_weak_prefix(candidate))
+            });
+        }
+
+        match header_str(request_headers, header::IF_MODIFIED_SINCE).and_then(parse_http_date) {
+            Some(if_modified_since) => {
+                self.last_modified.timestamp() <= if_modified_since.timestamp()
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a `Range` header may be honored, given an optional `If-Range` precondition.
+    pub fn if_range_matches(&self, request_headers: &HeaderMap) -> bool {
+        let if_range = match header_str(request_headers, header::IF_RANGE) {
+            Some(if_range) => if_range.trim(),
+            None => return true,
+        };
+
+        if if_range.starts_with('"') {
+            return self.etag.as_deref() == Some(if_range);
+        }
+
+        match parse_http_date(if_range) {
+            Some(date) => self.last_modified.timestamp() == date.timestamp(),
+            None => false,
+        }
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn strip_weak_prefix(etag: &str) -> &str {
+    etag.strip_prefix(WEAK_ETAG_PREFIX).unwrap_or(etag)
+}
+
+pub(crate) fn format_http_date(date: DateTime<Utc>) -> String {
+    date.format(HTTP_DATE_FORMAT).to_string()
+}
+
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, HeaderMap, HeaderValue};
+    use chrono::{TimeZone, Utc};
+
+    use super::Validators;
+
+    fn validators() -> Validators {
+        Validators::new(
+            Some("abc"),
+            Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"xyz\", W/\"abc\""));
+        assert!(validators().is_not_modified(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"xyz\""));
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Tue, 02 Jan 2024 03:04:05 GMT"),
+        );
+        assert!(!validators().is_not_modified(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Tue, 02 Jan 2024 03:04:05 GMT"),
+        );
+        assert!(validators().is_not_modified(&headers));
+    }
+
+    #[test]
+    fn test_if_range_matches() {
+        let mut headers = HeaderMap::new();
+        assert!(validators().if_range_matches(&headers));
+
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"abc\""));
+        assert!(validators().if_range_matches(&headers));
+
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"old\""));
+        assert!(!validators().if_range_matches(&headers));
+
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("Tue, 02 Jan 2024 03:04:05 GMT"));
+        assert!(validators().if_range_matches(&headers));
+    }
+}
@@ -1,41 +1,163 @@
 use async_trait::async_trait;
-use axum::extract::FromRequestParts;
+use axum::extract::{FromRequestParts, Request, State};
 use axum::http::{HeaderMap, HeaderValue};
 use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{Duration, Utc};
+use log::error;
 
 use crate::api::app_state::AppState;
+use crate::api::project_extractor::RequestedProject;
+use crate::apikey::{ApiKey, Scope};
+use crate::error::MindiaError;
 
+/// `last_used_at` is only rewritten when it is older than this, so that busy
+/// keys do not cost a write per request.
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+pub(crate) const MASTER_ACCOUNT: &str = "master";
+
+/// The authenticated caller of a route behind `ApiKeyChecker`, available to
+/// handlers as an `Extension`.
+#[derive(Debug, Clone)]
+pub(crate) enum Caller {
+    MasterKey,
+    ApiKey(ApiKey),
+}
+
+impl Caller {
+    /// The name usage is accounted under.
+    pub fn account(&self) -> &str {
+        match self {
+            Caller::MasterKey => MASTER_ACCOUNT,
+            Caller::ApiKey(apikey) => &apikey.name,
+        }
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        match self {
+            Caller::MasterKey => true,
+            Caller::ApiKey(apikey) => apikey.has_scope(scope),
+        }
+    }
+
+    pub fn can_access(&self, path: &str) -> bool {
+        match self {
+            Caller::MasterKey => true,
+            Caller::ApiKey(apikey) => apikey.can_access(path),
+        }
+    }
+
+    pub fn require_scope(&self, scope: Scope) -> Result<(), MindiaError> {
+        if !self.has_scope(scope) {
+            return Err(MindiaError::forbidden(format!(
+                "API key lacks the {} scope",
+                scope
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn require_access(&self, path: &str) -> Result<(), MindiaError> {
+        if !self.can_access(path) {
+            return Err(MindiaError::forbidden(format!(
+                "API key cannot access {}",
+                path
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects requests without a valid, unexpired key with a 401. Routes pick the
+/// scope they need with `require_scope`, layered inside this checker.
 pub(crate) struct ApiKeyChecker {}
 
 #[async_trait]
 impl FromRequestParts<AppState> for ApiKeyChecker {
-    type Rejection = ();
+    type Rejection = MindiaError;
 
     async fn from_request_parts(
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        let api_key_storage = state.apikey_storage.clone();
-        let master_key = state.config.master_key.clone();
+        let caller = authenticate(parts, state)?;
+        parts.extensions.insert(caller);
+
+        Ok(ApiKeyChecker {})
+    }
+}
 
-        let headers = parts.headers.clone();
+/// Middleware answering 403 to any caller but the master key.
+pub(crate) async fn require_master_key(request: Request, next: Next) -> Result<Response, MindiaError> {
+    match request.extensions().get::<Caller>() {
+        Some(Caller::MasterKey) => Ok(next.run(request).await),
+        Some(Caller::ApiKey(_)) => Err(MindiaError::forbidden("Only the master key is allowed")),
+        None => Err(MindiaError::unauthorized("Missing API key")),
+    }
+}
 
-        match extract_api_key(headers) {
-            Some(api_key_from_req) => {
-                if api_key_from_req != master_key {
-                    let api_key = { api_key_storage.get_by_key(&api_key_from_req) };
+/// Middleware answering 403 when the caller lacks the scope given as state:
+/// `from_fn_with_state(Scope::MediaWrite, require_scope)`.
+pub(crate) async fn require_scope(
+    State(scope): State<Scope>,
+    request: Request,
+    next: Next,
+) -> Result<Response, MindiaError> {
+    request
+        .extensions()
+        .get::<Caller>()
+        .ok_or_else(|| MindiaError::unauthorized("Missing API key"))?
+        .require_scope(scope)?;
+
+    Ok(next.run(request).await)
+}
+
+pub(crate) fn authenticate(parts: &Parts, state: &AppState) -> Result<Caller, MindiaError> {
+    let api_key_from_req = extract_api_key(parts.headers.clone())
+        .ok_or_else(|| MindiaError::unauthorized("Missing API key"))?;
+
+    if api_key_from_req == state.config.master_key {
+        return Ok(Caller::MasterKey);
+    }
 
-                    match api_key {
-                        Ok(Some(_)) => (),
-                        _ => return Err(()),
-                    }
-                }
-            }
-            None => return Err(()),
+    let mut apikey = state
+        .apikey_storage
+        .get_by_key(&api_key_from_req)?
+        .ok_or_else(|| MindiaError::unauthorized("Invalid API key"))?;
+
+    let now = Utc::now();
+    if apikey.is_expired(now) {
+        return Err(MindiaError::unauthorized("API key has expired"));
+    }
+
+    check_project(parts, &apikey)?;
+
+    let stale = apikey.last_used_at.map_or(true, |last_used_at| {
+        now - last_used_at >= Duration::seconds(LAST_USED_RESOLUTION_SECONDS)
+    });
+    if stale {
+        apikey.last_used_at = Some(now);
+        if let Err(e) = state.apikey_storage.save_last_used_at(&apikey.name, now) {
+            error!("Error: {}", e);
         }
+    }
 
-        Ok(ApiKeyChecker {})
+    Ok(Caller::ApiKey(apikey))
+}
+
+/// A key only works on its project, whichever project the URL names.
+fn check_project(parts: &Parts, apikey: &ApiKey) -> Result<(), MindiaError> {
+    if let Some(RequestedProject(project)) = parts.extensions.get::<RequestedProject>() {
+        if *project != apikey.project {
+            return Err(MindiaError::forbidden("API key belongs to another project"));
+        }
     }
+
+    Ok(())
 }
 
 fn extract_api_key(headers: HeaderMap) -> Option<String> {
@@ -45,3 +167,32 @@ fn extract_api_key(headers: HeaderMap) -> Option<String> {
         .and_then(|value| value.strip_prefix("Bearer "))
         .map(|value| value.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::project_extractor::project_name;
+
+    fn parts(requested_project: Option<&str>) -> Parts {
+        let (mut parts, _) = Request::new(()).into_parts();
+        if let Some(project) = requested_project {
+            parts.extensions.insert(RequestedProject(project.to_string()));
+        }
+        parts
+    }
+
+    #[test]
+    fn test_api_key_cannot_reach_another_project() {
+        let apikey = ApiKey::new("reader".to_string(), "secret", Scope::all(), vec![], None)
+            .with_project("acme".to_string());
+
+        assert!(check_project(&parts(Some("acme")), &apikey).is_ok());
+        assert!(check_project(&parts(Some("other")), &apikey).is_err());
+
+        // Without a project in the URL, the key works on its own project.
+        let mut parts = parts(None);
+        assert!(check_project(&parts, &apikey).is_ok());
+        parts.extensions.insert(Caller::ApiKey(apikey));
+        assert_eq!(project_name(&parts), "acme");
+    }
+}
@@ -1,10 +1,23 @@
 mod api_apikey;
 mod api_clear_cache;
+mod api_folder;
 mod api_media;
+mod api_project;
+mod api_resumable_upload;
+mod api_schedule;
+mod api_signed_url;
+mod api_task;
 mod api_transformation;
+mod api_usage;
+mod api_webhook;
 mod app_state;
+mod conditional_request;
 mod middleware_apikey;
 mod path_extractor;
+mod project_extractor;
+mod rate_limit;
+mod request_access;
 pub mod server;
 mod transformation_chain_extractor;
+mod url_signer;
 mod utils;
@@ -1,10 +1,10 @@
 use async_trait::async_trait;
 use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
-use axum::http::StatusCode;
 
 use crate::api::app_state::AppState;
-use crate::api::transformation_chain_extractor::TransformationChainExtractor;
+use crate::api::utils::{parse_transformation_from_path, wildcard_path};
+use crate::error::MindiaError;
 use crate::media::Path;
 
 pub(crate) struct PathExtractor(pub Path);
@@ -12,16 +12,16 @@ pub(crate) struct PathExtractor(pub Path);
 #[async_trait]
 impl FromRequestParts<AppState> for PathExtractor
 {
-    type Rejection = StatusCode;
+    type Rejection = MindiaError;
 
     async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
-        let transformation_chain = TransformationChainExtractor::from_request_parts(parts, state)
+        let wildcard_path = wildcard_path(parts, state)
             .await
-            .map_err(|_| StatusCode::BAD_REQUEST)?;
+            .ok_or_else(|| MindiaError::validation("Invalid path"))?;
 
-        let path = Path::new(
-            parts.uri.path().trim_start_matches(&transformation_chain.transformation_chain_str)
-        ).map_err(|_| StatusCode::BAD_REQUEST)?;
+        let (_, media_path) = parse_transformation_from_path(wildcard_path.as_str());
+
+        let path = Path::new(media_path.as_str()).map_err(MindiaError::validation)?;
 
         Ok(PathExtractor(path))
     }
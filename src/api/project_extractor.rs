@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::extract::{FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::Uri;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::api::app_state::AppState;
+use crate::api::middleware_apikey::Caller;
+use crate::error::MindiaError;
+use crate::handler::ProjectContext;
+use crate::project::{Project, DEFAULT_PROJECT};
+
+const PROJECT_ROUTES_PREFIX: &str = "/api/v0/projects/";
+
+/// The project named by a `/api/v0/projects/:project/...` URL.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestedProject(pub String);
+
+/// Serves `/api/v0/projects/:project/*rest` as `/api/v0/*rest` on behalf of
+/// `project`. It wraps the router, which routes the rewritten URI.
+pub(crate) async fn select_project(mut request: Request, next: Next) -> Response {
+    let rewrite = request
+        .uri()
+        .path()
+        .strip_prefix(PROJECT_ROUTES_PREFIX)
+        .and_then(|rest| rest.split_once('/'))
+        .filter(|(project, rest)| Project::is_valid_name(project) && !rest.is_empty())
+        .map(|(project, rest)| {
+            let path_and_query = match request.uri().query() {
+                Some(query) => format!("/api/v0/{}?{}", rest, query),
+                None => format!("/api/v0/{}", rest),
+            };
+            (project.to_string(), path_and_query)
+        });
+
+    if let Some((project, path_and_query)) = rewrite {
+        let mut parts = request.uri().clone().into_parts();
+        if let Ok(path_and_query) = path_and_query.parse() {
+            parts.path_and_query = Some(path_and_query);
+            if let Ok(uri) = Uri::from_parts(parts) {
+                *request.uri_mut() = uri;
+                request.extensions_mut().insert(RequestedProject(project));
+            }
+        }
+    }
+
+    next.run(request).await
+}
+
+/// The name of the project a request works on: the one of its URL, else the
+/// one of its API key, else the default project.
+pub(crate) fn project_name(parts: &Parts) -> String {
+    if let Some(RequestedProject(project)) = parts.extensions.get::<RequestedProject>() {
+        return project.clone();
+    }
+
+    match parts.extensions.get::<Caller>() {
+        Some(Caller::ApiKey(apikey)) => apikey.project.clone(),
+        _ => DEFAULT_PROJECT.to_string(),
+    }
+}
+
+/// The handlers of the project a request works on. Extract it after the API key
+/// was checked, as the key may be what names the project.
+pub(crate) struct CurrentProject(pub Arc<ProjectContext>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentProject {
+    type Rejection = MindiaError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(project) = parts.extensions.get::<Arc<ProjectContext>>() {
+            return Ok(CurrentProject(project.clone()));
+        }
+
+        let project = state
+            .projects
+            .get(&project_name(parts))
+            .await
+            .map_err(MindiaError::from)?
+            .ok_or_else(|| MindiaError::not_found("Project not found"))?;
+
+        parts.extensions.insert(project.clone());
+
+        Ok(CurrentProject(project))
+    }
+}
@@ -0,0 +1,80 @@
+use std::net::{IpAddr, SocketAddr};
+
+use async_trait::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use log::error;
+
+use crate::api::app_state::AppState;
+use crate::api::middleware_apikey::Caller;
+use crate::error::MindiaError;
+use crate::ratelimit::RateLimitBudget;
+
+/// Who a request is rate limited as: the API key identified by `ApiKeyChecker`
+/// or `RequestAccess`, which must run first, or else the client IP.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ClientIdentity {
+    MasterKey,
+    ApiKey(String),
+    Ip(IpAddr),
+    Unknown,
+}
+
+impl ClientIdentity {
+    fn bucket_key(&self) -> Option<String> {
+        match self {
+            ClientIdentity::ApiKey(name) => Some(format!("key:{}", name)),
+            ClientIdentity::Ip(ip) => Some(format!("ip:{}", ip)),
+            ClientIdentity::MasterKey | ClientIdentity::Unknown => None,
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIdentity {
+    type Rejection = MindiaError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(caller) = parts.extensions.get::<Caller>() {
+            return Ok(match caller {
+                Caller::MasterKey => ClientIdentity::MasterKey,
+                Caller::ApiKey(apikey) => ClientIdentity::ApiKey(apikey.name.clone()),
+            });
+        }
+
+        Ok(parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| ClientIdentity::Ip(addr.ip()))
+            .unwrap_or(ClientIdentity::Unknown))
+    }
+}
+
+/// Takes a token from the client's bucket for `budget`. The limiter failing
+/// lets the request through rather than taking the API down with it.
+pub(crate) fn check_rate_limit(
+    state: &AppState,
+    identity: &ClientIdentity,
+    budget: RateLimitBudget,
+) -> Result<(), MindiaError> {
+    let config = &state.config.rate_limit;
+
+    let client = match identity.bucket_key() {
+        Some(client) if config.enabled => client,
+        _ => return Ok(()),
+    };
+
+    let key = format!("{}:{}", budget.as_str(), client);
+
+    match state.rate_limiter.acquire(&key, budget.bucket(config)) {
+        Ok(decision) if !decision.allowed => Err(MindiaError::rate_limited(
+            format!("Too many {} requests", budget.as_str()),
+            decision.retry_after,
+        )),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("Error: {}", e);
+            Ok(())
+        }
+    }
+}
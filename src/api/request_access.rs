@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::api::app_state::AppState;
+use crate::api::middleware_apikey::authenticate;
+use crate::api::project_extractor::project_name;
+use crate::api::url_signer::UrlSigner;
+use crate::api::utils::{parse_transformation_from_path, wildcard_path};
+use crate::apikey::Scope;
+use crate::error::MindiaError;
+use crate::project::Project;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Access {
+    ApiKey,
+    Signed,
+    Anonymous,
+}
+
+#[derive(Deserialize)]
+struct SignatureQuery {
+    expires: Option<i64>,
+    signature: Option<String>,
+}
+
+/// How a media request was authorized. A signature, when present, must be valid;
+/// anonymous requests are rejected for folders and routes that require signing.
+/// The outcome is kept in the request extensions so other extractors can reuse it.
+pub(crate) struct RequestAccess(pub Access);
+
+#[async_trait]
+impl FromRequestParts<AppState> for RequestAccess {
+    type Rejection = MindiaError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(access) = parts.extensions.get::<Access>() {
+            return Ok(RequestAccess(*access));
+        }
+
+        let access = check_access(parts, state).await?;
+        parts.extensions.insert(access);
+
+        Ok(RequestAccess(access))
+    }
+}
+
+async fn check_access(parts: &mut Parts, state: &AppState) -> Result<Access, MindiaError> {
+    let path = wildcard_path(parts, state)
+        .await
+        .ok_or_else(|| MindiaError::validation("Invalid path"))?;
+    let (transformation_chain, media_path) = parse_transformation_from_path(path.as_str());
+
+    let Query(query) = Query::<SignatureQuery>::from_request_parts(parts, state)
+        .await
+        .map_err(|e| MindiaError::validation(e.body_text()))?;
+
+    if let Some(signature) = query.signature {
+        let expires = query
+            .expires
+            .ok_or_else(|| MindiaError::validation("Missing expires"))?;
+
+        let secret = state
+            .config
+            .signing
+            .secret
+            .as_deref()
+            .ok_or_else(|| MindiaError::forbidden("Signed URLs are disabled"))?;
+
+        // Signatures cover the project, so they cannot be replayed on another one.
+        let signed_path = format!("{}{}", Project::url_prefix(&project_name(parts)), media_path);
+
+        let valid = expires > Utc::now().timestamp()
+            && UrlSigner::new(secret).verify(&signed_path, &transformation_chain, expires, &signature);
+
+        if !valid {
+            return Err(MindiaError::forbidden("Invalid or expired signature"));
+        }
+
+        return Ok(Access::Signed);
+    }
+
+    // A key that cannot read this media is treated like no key at all.
+    let caller = authenticate(parts, state)
+        .ok()
+        .filter(|caller| caller.has_scope(Scope::MediaRead) && caller.can_access(&media_path));
+    if let Some(caller) = caller {
+        parts.extensions.insert(caller);
+        return Ok(Access::ApiKey);
+    }
+
+    if state
+        .config
+        .signing
+        .is_required(&media_path, !transformation_chain.is_empty())
+    {
+        return Err(MindiaError::forbidden("Signature required"));
+    }
+
+    Ok(Access::Anonymous)
+}
@@ -1,49 +1,65 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::{Router, routing::get};
+use axum::{Router, ServiceExt, routing::get};
+use axum::extract::{DefaultBodyLimit, Request};
+use axum::middleware::{from_extractor_with_state, from_fn, from_fn_with_state};
 use axum::http::{HeaderName, HeaderValue, Method};
-use axum::routing::{delete, post};
-use tokio::sync::Mutex;
+use axum::routing::{delete, head, post};
+use tower::Layer;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 
-use crate::api::api_apikey::{delete_apikey, get_apikeys, save_apikey};
+use crate::api::api_apikey::{delete_apikey, get_apikeys, rotate_apikey, save_apikey};
 use crate::api::api_clear_cache::clear_cache;
-use crate::api::api_media::{delete_media, read_media, upload_media};
+use crate::api::api_folder::list_folder;
+use crate::api::api_resumable_upload::{
+    create_resumable_upload, delete_resumable_upload, head_resumable_upload,
+    options_resumable_upload, patch_resumable_upload,
+};
+use crate::api::api_schedule::{delete_schedule, get_schedule, get_schedules, save_schedule};
+use crate::api::api_signed_url::sign_url;
+use crate::api::api_task::{
+    cancel_task, get_dead_letters, get_task, get_tasks, requeue_task,
+};
+use crate::api::api_project::{delete_project, get_project, get_projects, save_project};
+use crate::api::api_media::{
+    bulk_media, copy_media, delete_media, download_media, move_media, read_media, upload_media,
+};
 use crate::api::api_transformation::{
     delete_named_transformation, get_named_transformations, get_transformation_templates,
     save_named_transformation,
 };
+use crate::api::api_usage::get_usage;
 use crate::api::app_state::AppState;
-use crate::apikey::ApiKeyStorage;
+use crate::api::api_webhook::{
+    delete_webhook, get_webhook_deliveries, get_webhooks, save_webhook,
+};
+use crate::api::middleware_apikey::{require_master_key, require_scope, ApiKeyChecker};
+use crate::api::project_extractor::select_project;
+use crate::apikey::{ApiKeyStorage, Scope};
 use crate::config::Config;
-use crate::handler::{CacheHandler, MediaHandler};
-use crate::metadata::MetadataStorage;
+use crate::handler::ProjectRegistry;
+use crate::ratelimit::RateLimiter;
 use crate::scheduler::TaskScheduler;
-use crate::storage::FileStorage;
-use crate::transform::{NamedTransformationStorage, TransformationTemplateRegistry};
+use crate::transform::TransformationTemplateRegistry;
+use crate::usage::UsageTracker;
 
 pub async fn run_server(
     config: Config,
-    file_storage: Arc<Mutex<dyn FileStorage>>,
-    cache_storage: Arc<Mutex<dyn FileStorage>>,
-    metadata_storage: Arc<Mutex<dyn MetadataStorage>>,
-    named_transformation_storage: Arc<dyn NamedTransformationStorage>,
+    projects: Arc<ProjectRegistry>,
     apikey_storage: Arc<dyn ApiKeyStorage>,
     task_scheduler: Arc<TaskScheduler>,
+    rate_limiter: Arc<dyn RateLimiter>,
+    usage_tracker: UsageTracker,
 ) -> std::io::Result<()> {
     let shared_state = AppState {
         apikey_storage: apikey_storage.clone(),
-        named_transformation_storage: named_transformation_storage.clone(),
+        projects,
         transformation_template_registry: Arc::new(TransformationTemplateRegistry::new()),
-        media_handler: MediaHandler::new(
-            file_storage.clone(),
-            cache_storage.clone(),
-            metadata_storage.clone(),
-        ),
-        cache_handler: CacheHandler::new(cache_storage.clone(), metadata_storage.clone()),
         task_scheduler: task_scheduler.clone(),
+        rate_limiter,
+        usage_tracker,
         config: config.clone(),
     };
 
@@ -54,14 +70,35 @@ pub async fn run_server(
             Method::PATCH,
             Method::PUT,
             Method::DELETE,
+            Method::HEAD,
             Method::OPTIONS,
         ])
         .allow_origin(AllowOrigin::exact(HeaderValue::from_static(
             "http://localhost:3000",
         )))
-        .allow_headers(vec![HeaderName::from_static("content-type")])
+        .allow_headers(vec![
+            HeaderName::from_static("authorization"),
+            HeaderName::from_static("content-type"),
+            HeaderName::from_static("tus-resumable"),
+            HeaderName::from_static("upload-length"),
+            HeaderName::from_static("upload-offset"),
+            HeaderName::from_static("upload-metadata"),
+        ])
+        .expose_headers(vec![
+            HeaderName::from_static("location"),
+            HeaderName::from_static("tus-resumable"),
+            HeaderName::from_static("upload-length"),
+            HeaderName::from_static("upload-offset"),
+            HeaderName::from_static("upload-expires"),
+            HeaderName::from_static("mindia-media-path"),
+        ])
         .allow_credentials(true);
 
+    // Routes are public unless they sit behind the API key checker. Scopes are
+    // layered inside it, as the checker is what identifies the caller.
+    let api_key = from_extractor_with_state::<ApiKeyChecker, _>(shared_state.clone());
+    let scope = |scope: Scope| from_fn_with_state(scope, require_scope);
+
     let app = Router::new()
         .route("/", get(|| async { "Mindia API" }))
         .nest(
@@ -72,29 +109,153 @@ pub async fn run_server(
                     Router::new()
                         .route("/templates", get(get_transformation_templates))
                         .route("/", get(get_named_transformations))
-                        .route("/", post(save_named_transformation))
-                        .route("/:name", delete(delete_named_transformation)),
+                        .route(
+                            "/",
+                            post(save_named_transformation)
+                                .route_layer(scope(Scope::TransformationsAdmin))
+                                .route_layer(api_key.clone()),
+                        )
+                        .route(
+                            "/:name",
+                            delete(delete_named_transformation)
+                                .route_layer(scope(Scope::TransformationsAdmin))
+                                .route_layer(api_key.clone()),
+                        ),
+                )
+                .nest(
+                    "/projects",
+                    Router::new()
+                        .route("/", get(get_projects))
+                        .route("/", post(save_project))
+                        .route("/:name", get(get_project))
+                        .route("/:name", delete(delete_project))
+                        .route_layer(from_fn(require_master_key))
+                        .route_layer(api_key.clone()),
                 )
                 .nest(
                     "/apikey",
                     Router::new()
                         .route("/", get(get_apikeys))
                         .route("/", post(save_apikey))
-                        .route("/:name", delete(delete_apikey)),
+                        .route("/:name", delete(delete_apikey))
+                        .route("/:name/rotate", post(rotate_apikey))
+                        .route_layer(scope(Scope::KeysAdmin))
+                        .route_layer(api_key.clone()),
+                )
+                .nest(
+                    "/webhooks",
+                    Router::new()
+                        .route("/", get(get_webhooks))
+                        .route("/", post(save_webhook))
+                        .route("/:name", delete(delete_webhook))
+                        .route("/:name/deliveries", get(get_webhook_deliveries))
+                        .route_layer(scope(Scope::WebhooksAdmin))
+                        .route_layer(api_key.clone()),
                 )
                 .nest(
                     "/media",
                     Router::new()
-                        .route("/*path", get(read_media))
-                        .route("/upload/*path", post(upload_media))
-                        .route("/*path", delete(delete_media)),
+                        .route("/*path", get(download_media))
+                        .merge(
+                            Router::new()
+                                .route(
+                                    "/upload/*path",
+                                    post(upload_media)
+                                        .layer(DefaultBodyLimit::max(
+                                            config.upload.max_request_size,
+                                        ))
+                                        .route_layer(scope(Scope::MediaWrite)),
+                                )
+                                .route(
+                                    "/move",
+                                    post(move_media).route_layer(scope(Scope::MediaWrite)),
+                                )
+                                .route(
+                                    "/copy",
+                                    post(copy_media).route_layer(scope(Scope::MediaWrite)),
+                                )
+                                // The scope depends on the operation, checked by the handler.
+                                .route("/bulk", post(bulk_media))
+                                .route(
+                                    "/*path",
+                                    delete(delete_media).route_layer(scope(Scope::MediaDelete)),
+                                )
+                                .route_layer(api_key.clone()),
+                        ),
+                )
+                // Apart from /media, where it would shadow a folder named metadata.
+                .route("/metadata/*path", get(read_media))
+                .nest(
+                    "/uploads",
+                    Router::new()
+                        .route(
+                            "/",
+                            post(create_resumable_upload)
+                                .route_layer(scope(Scope::MediaWrite))
+                                .route_layer(api_key.clone())
+                                .options(options_resumable_upload),
+                        )
+                        .route(
+                            "/:id",
+                            head(head_resumable_upload)
+                                .patch(patch_resumable_upload)
+                                .delete(delete_resumable_upload)
+                                .route_layer(scope(Scope::MediaWrite))
+                                .route_layer(api_key.clone()),
+                        ),
+                )
+                .route(
+                    "/signed_urls",
+                    post(sign_url)
+                        .route_layer(scope(Scope::MediaRead))
+                        .route_layer(api_key.clone()),
+                )
+                .nest(
+                    "/folders",
+                    Router::new()
+                        .route("/", get(list_folder))
+                        .route("/*path", get(list_folder))
+                        .route_layer(scope(Scope::MediaRead))
+                        .route_layer(api_key.clone()),
+                )
+                .nest(
+                    "/cache",
+                    Router::new()
+                        .route("/", post(clear_cache))
+                        .route_layer(scope(Scope::TransformationsAdmin))
+                        .route_layer(api_key.clone()),
                 )
-                .nest("/cache", Router::new().route("/", post(clear_cache))),
+                // Each task needs the scope it was created with.
+                .nest(
+                    "/tasks",
+                    Router::new()
+                        .route("/", get(get_tasks))
+                        .route("/:id", get(get_task))
+                        .route("/dead_letter", get(get_dead_letters))
+                        .route("/:id/cancel", post(cancel_task))
+                        .route("/:id/requeue", post(requeue_task))
+                        .route_layer(api_key.clone()),
+                )
+                // Each schedule needs the scope of the tasks it pushes.
+                .nest(
+                    "/schedules",
+                    Router::new()
+                        .route("/", get(get_schedules))
+                        .route("/", post(save_schedule))
+                        .route("/:name", get(get_schedule))
+                        .route("/:name", delete(delete_schedule))
+                        .route_layer(api_key.clone()),
+                )
+                // Any key reads its own usage, the one of others needs keys:admin.
+                .route("/usage", get(get_usage).route_layer(api_key)),
         )
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(shared_state);
 
+    // Outside the router, so that routing happens on the rewritten URI.
+    let app = from_fn(select_project).layer(app);
+
     let bind_address = format!("127.0.0.1:{}", config.server.port.clone());
 
     let listener = tokio::net::TcpListener::bind(bind_address.clone())
@@ -105,7 +266,7 @@ pub async fn run_server(
 
     axum::serve(
         listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
+        ServiceExt::<Request>::into_make_service_with_connect_info::<SocketAddr>(app),
     )
     .await
     .unwrap();
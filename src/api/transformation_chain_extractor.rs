@@ -6,7 +6,10 @@ use crate::{
     transform::TransformationDescriptorChain,
 };
 use crate::api::app_state::AppState;
-use crate::api::utils::parse_transformation_from_path;
+use crate::api::project_extractor::CurrentProject;
+use crate::api::request_access::{Access, RequestAccess};
+use crate::api::utils::{parse_transformation_from_path, wildcard_path};
+use crate::error::MindiaError;
 
 pub(crate) struct TransformationChainExtractor {
     pub transformation_chain: Option<TransformationDescriptorChain>,
@@ -16,29 +19,41 @@ pub(crate) struct TransformationChainExtractor {
 #[async_trait]
 impl FromRequestParts<AppState> for TransformationChainExtractor
 {
-    type Rejection = ();
+    type Rejection = MindiaError;
 
     async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
-        let path = parts.uri.path();
+        let path = wildcard_path(parts, state)
+            .await
+            .ok_or_else(|| MindiaError::validation("Invalid path"))?;
 
-        let (transformation_chain_str, _) = parse_transformation_from_path(path);
+        let (transformation_chain_str, _) = parse_transformation_from_path(path.as_str());
 
         let transformation_chain = if transformation_chain_str.is_empty() {
              None
          } else {
-             let named_transformation_storage = state.named_transformation_storage.clone();
+             let CurrentProject(project) = CurrentProject::from_request_parts(parts, state).await?;
+             let named_transformation_storage = project.named_transformation_storage.clone();
              let transformation_template_registry =
                  state.transformation_template_registry.clone();
 
-             match TransformationsExtractor::new(
+             let mut transformations_extractor = TransformationsExtractor::new(
                  named_transformation_storage,
                  transformation_template_registry,
-             )
-                 .extract_one(transformation_chain_str.as_str())
-             {
-                 Ok(transformation_chain) => Some(transformation_chain),
-                 Err(e) => return Err(()),
+             );
+
+             let policy = &state.config.transformation_policy;
+             if policy.strict {
+                 let RequestAccess(access) = RequestAccess::from_request_parts(parts, state).await?;
+                 if access == Access::Anonymous {
+                     transformations_extractor = transformations_extractor.with_policy(policy.clone());
+                 }
              }
+
+             let transformation_chain = transformations_extractor
+                 .extract_one(transformation_chain_str.as_str())
+                 .map_err(MindiaError::from)?;
+
+             Some(transformation_chain)
          };
 
          Ok(TransformationChainExtractor {
@@ -0,0 +1,70 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs media URLs so they can be shared without an API key. The signature covers
+/// the media path, the transformation chain and the expiry timestamp.
+pub(crate) struct UrlSigner<'a> {
+    secret: &'a str,
+}
+
+impl<'a> UrlSigner<'a> {
+    pub fn new(secret: &'a str) -> Self {
+        Self { secret }
+    }
+
+    pub fn sign(&self, media_path: &str, transformation_chain: &str, expires: i64) -> String {
+        hex::encode(
+            self.mac(media_path, transformation_chain, expires)
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    pub fn verify(
+        &self,
+        media_path: &str,
+        transformation_chain: &str,
+        expires: i64,
+        signature: &str,
+    ) -> bool {
+        let signature = match hex::decode(signature) {
+            Ok(signature) => signature,
+            Err(_) => return false,
+        };
+
+        self.mac(media_path, transformation_chain, expires)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+
+    fn mac(&self, media_path: &str, transformation_chain: &str, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(format!("{}\n{}\n{}", media_path, transformation_chain, expires).as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let signer = UrlSigner::new("secret");
+        let signature = signer.sign("/products/image.webp", "c_scale:w_100", 1700000000);
+
+        assert!(signer.verify("/products/image.webp", "c_scale:w_100", 1700000000, &signature));
+        assert!(!signer.verify("/products/image.webp", "c_scale:w_200", 1700000000, &signature));
+        assert!(!signer.verify("/products/other.webp", "c_scale:w_100", 1700000000, &signature));
+        assert!(!signer.verify("/products/image.webp", "c_scale:w_100", 1800000000, &signature));
+        assert!(!UrlSigner::new("other").verify(
+            "/products/image.webp",
+            "c_scale:w_100",
+            1700000000,
+            &signature
+        ));
+    }
+}
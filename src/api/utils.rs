@@ -1,9 +1,21 @@
+use std::collections::HashMap;
+
+use axum::extract::{FromRequestParts, Path};
+use axum::http::request::Parts;
+use serde_json::Value;
+
+use crate::api::app_state::AppState;
+use crate::error::MindiaError;
+use crate::extractor::TransformationsExtractor;
+use crate::handler::ProjectContext;
+use crate::transform::TransformationDescriptorChain;
+
 pub(crate) fn parse_transformation_from_path(path: &str) -> (String, String) {
     let mut transformation_chain = String::new();
     let mut image_path = String::new();
 
     for part in path.split("/") {
-        if part.starts_with("t_") || part.starts_with("c_") {
+        if part.starts_with("t_") || part.starts_with("c_") || part.starts_with("f_") {
             transformation_chain.push_str(part);
             transformation_chain.push_str("/");
         } else {
@@ -17,3 +29,40 @@ pub(crate) fn parse_transformation_from_path(path: &str) -> (String, String) {
 
     (transformation_chain, image_path)
 }
+
+pub(crate) async fn wildcard_path(parts: &mut Parts, state: &AppState) -> Option<String> {
+    let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+        .await
+        .ok()?;
+
+    params.get("path").map(|path| format!("/{}", path))
+}
+
+/// Parses the JSON array of transformation chains sent along an upload.
+pub(crate) fn parse_transformation_list(data: &[u8]) -> Result<Vec<String>, MindiaError> {
+    let transformations_json: Value = serde_json::from_slice(data)
+        .map_err(|e| MindiaError::validation(e.to_string()))?;
+
+    transformations_json
+        .as_array()
+        .and_then(|array| {
+            array
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect::<Option<Vec<String>>>()
+        })
+        .ok_or_else(|| MindiaError::validation("Invalid transformations"))
+}
+
+pub(crate) fn extract_transformation_chains(
+    state: &AppState,
+    project: &ProjectContext,
+    transformations: &[String],
+) -> Result<Vec<TransformationDescriptorChain>, MindiaError> {
+    TransformationsExtractor::new(
+        project.named_transformation_storage.clone(),
+        state.transformation_template_registry.clone(),
+    )
+    .extract(transformations.iter().map(String::as_str).collect())
+    .map_err(MindiaError::from)
+}
@@ -1,12 +1,208 @@
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::clone::Clone;
 use std::collections::HashMap;
 use std::fmt::Debug;
 
+use crate::apikey::Scope;
+use crate::media::path::normalize_folder;
+use crate::project::default_project;
+use crate::utils::sha256_hex;
+
+/// Number of leading characters of a key kept in clear to tell keys apart.
+const KEY_PREFIX_LENGTH: usize = 8;
+const MAX_NAME_LENGTH: usize = 64;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKey {
     pub name: String,
-    pub key: String,
+    /// Keys saved before projects existed belong to the default project.
+    #[serde(default = "default_project")]
+    pub project: String,
+    /// Only a hash of the key is kept; the key itself is shown once.
+    pub key_hash: String,
+    pub key_prefix: String,
+    /// The key replaced by the last rotation, accepted until `previous_key_expires_at`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_key_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_key_expires_at: Option<DateTime<Utc>>,
+    /// Keys saved before scopes existed keep full access.
+    #[serde(default = "Scope::all")]
+    pub scopes: Vec<Scope>,
+    /// When not empty, the key only reaches media under these folders.
+    #[serde(default)]
+    pub folders: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn new(
+        name: String,
+        key: &str,
+        scopes: Vec<Scope>,
+        folders: Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            name,
+            project: default_project(),
+            key_hash: hash_key(key),
+            key_prefix: key_prefix(key),
+            previous_key_hash: None,
+            previous_key_expires_at: None,
+            scopes,
+            folders,
+            created_at: Some(Utc::now()),
+            expires_at,
+            last_used_at: None,
+        }
+    }
+
+    pub fn with_project(self, project: String) -> Self {
+        Self { project, ..self }
+    }
+
+    /// Replaces the key, the current one keeping working for `grace_period`.
+    pub fn rotate(&mut self, key: &str, grace_period: Duration, now: DateTime<Utc>) {
+        self.previous_key_hash = Some(std::mem::replace(&mut self.key_hash, hash_key(key)));
+        self.previous_key_expires_at = Some(now + grace_period);
+        self.key_prefix = key_prefix(key);
+    }
+
+    pub fn matches(&self, key_hash: &str, now: DateTime<Utc>) -> bool {
+        self.key_hash == key_hash
+            || (self.previous_key_hash.as_deref() == Some(key_hash)
+                && self
+                    .previous_key_expires_at
+                    .is_some_and(|expires_at| expires_at > now))
+    }
+
+    /// Hashes this key can currently be looked up by.
+    pub fn key_hashes(&self) -> Vec<&str> {
+        std::iter::once(self.key_hash.as_str())
+            .chain(self.previous_key_hash.as_deref())
+            .collect()
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn can_access(&self, path: &str) -> bool {
+        self.folders.is_empty()
+            || self
+                .folders
+                .iter()
+                .any(|folder| is_in_folder(path, folder))
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+pub fn hash_key(key: &str) -> String {
+    sha256_hex(key.as_bytes())
+}
+
+pub fn key_prefix(key: &str) -> String {
+    key.chars().take(KEY_PREFIX_LENGTH).collect()
+}
+
+/// Names are keys of the stored document, so they are limited to letters, digits,
+/// `-` and `_`.
+pub fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.trim().is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err("API key name must have between 1 and 64 characters");
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err("API key name may only contain letters, digits, - and _");
+    }
+
+    Ok(())
+}
+
+/// Whether `path` is `folder` itself or lies anywhere below it. Paths that are not
+/// normalized, such as ones with `..` segments, are in no folder.
+pub fn is_in_folder(path: &str, folder: &str) -> bool {
+    let path = match normalize_folder(path) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let folder = folder.trim_end_matches('/');
+
+    folder.is_empty()
+        || path == folder
+        || path
+            .strip_prefix(folder)
+            .is_some_and(|rest| rest.starts_with('/'))
 }
 
 pub type ApiKeyMap = HashMap<String, ApiKey>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("ci-deploy_key2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("ci deploy").is_err());
+        assert!(validate_name("a.b").is_err());
+        assert!(validate_name("a[\"b\"]").is_err());
+        assert!(validate_name(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_can_access() {
+        let apikey = ApiKey::new(
+            "integrator".to_string(),
+            "key",
+            vec![Scope::MediaRead],
+            vec!["/products".to_string()],
+            None,
+        );
+
+        assert!(apikey.can_access("/products"));
+        assert!(apikey.can_access("/products/shoes/a.jpg"));
+        assert!(!apikey.can_access("/products-archive/a.jpg"));
+        assert!(!apikey.can_access("/"));
+    }
+
+    #[test]
+    fn test_can_access_rejects_traversal() {
+        let apikey = ApiKey::new(
+            "integrator".to_string(),
+            "key",
+            vec![Scope::MediaRead],
+            vec!["/allowed".to_string()],
+            None,
+        );
+
+        assert!(apikey.can_access("/allowed/"));
+        assert!(!apikey.can_access("/allowed/../secret/bbd2fa99-f35e-4062-92eb-9d26caa943ae.webp"));
+        assert!(!apikey.can_access("/allowed/./../secret"));
+        assert!(!apikey.can_access("/allowed//../secret"));
+    }
+
+    #[test]
+    fn test_rotate() {
+        let now = Utc::now();
+        let mut apikey = ApiKey::new("integrator".to_string(), "old", Scope::all(), vec![], None);
+
+        apikey.rotate("new", Duration::hours(1), now);
+
+        assert_eq!(apikey.key_prefix, "new");
+        assert!(apikey.matches(&hash_key("new"), now));
+        assert!(apikey.matches(&hash_key("old"), now));
+        assert!(!apikey.matches(&hash_key("old"), now + Duration::hours(2)));
+    }
+}
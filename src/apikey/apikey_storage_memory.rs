@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use std::sync::RwLock;
+
+use crate::apikey::apikey::hash_key;
+use crate::apikey::{ApiKey, ApiKeyMap, ApiKeyStorage};
+
+/// Keeps the API keys in memory, for development and tests. They are lost when the
+/// server stops.
+#[derive(Default)]
+pub struct MemoryApiKeyStorage {
+    apikeys: RwLock<ApiKeyMap>,
+}
+
+impl MemoryApiKeyStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ApiKeyStorage for MemoryApiKeyStorage {
+    fn get_all(&self) -> Result<ApiKeyMap, Box<dyn Error>> {
+        Ok(self.apikeys.read().unwrap().clone())
+    }
+
+    fn get_by_name(&self, name: &str) -> Result<Option<ApiKey>, Box<dyn Error>> {
+        Ok(self.apikeys.read().unwrap().get(name).cloned())
+    }
+
+    fn get_by_key(&self, key: &str) -> Result<Option<ApiKey>, Box<dyn Error>> {
+        let key_hash = hash_key(key);
+        let now = Utc::now();
+
+        Ok(self
+            .apikeys
+            .read()
+            .unwrap()
+            .values()
+            .find(|apikey| apikey.matches(&key_hash, now))
+            .cloned())
+    }
+
+    fn save(&self, apikey: ApiKey) -> Result<(), Box<dyn Error>> {
+        self.apikeys
+            .write()
+            .unwrap()
+            .insert(apikey.name.clone(), apikey);
+
+        Ok(())
+    }
+
+    fn save_last_used_at(
+        &self,
+        name: &str,
+        last_used_at: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(apikey) = self.apikeys.write().unwrap().get_mut(name) {
+            apikey.last_used_at = Some(last_used_at);
+        }
+
+        Ok(())
+    }
+
+    fn delete(&self, apikey_name: &str) -> Result<(), Box<dyn Error>> {
+        self.apikeys.write().unwrap().remove(apikey_name);
+
+        Ok(())
+    }
+}
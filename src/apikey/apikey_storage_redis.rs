@@ -1,9 +1,14 @@
-use redis::Connection;
+use chrono::{DateTime, Utc};
+use redis::{Connection, ErrorKind, RedisError};
+use serde_json::Value;
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 
 const API_KEYS_KEY: &str = "internal:configuration:api_keys";
+/// Hash of every accepted key hash to the name of its API key.
+const API_KEY_HASHES_KEY: &str = "internal:configuration:api_key_hashes";
 
+use crate::apikey::apikey::{hash_key, key_prefix, validate_name};
 use crate::apikey::{ApiKey, ApiKeyMap, ApiKeyStorage};
 
 pub struct RedisApiKeyStorage {
@@ -29,11 +34,48 @@ impl RedisApiKeyStorage {
                 .arg(API_KEYS_KEY)
                 .arg(".")
                 .arg("{}")
-                .query(&mut self.conn.lock().unwrap())?;
+                .query::<()>(&mut self.conn.lock().unwrap())?;
+        }
+
+        self.migrate_plaintext_keys()
+    }
+
+    /// Replaces the keys stored in clear by earlier versions with their hash.
+    fn migrate_plaintext_keys(&self) -> Result<(), Box<dyn Error>> {
+        let result: String = redis::cmd("JSON.GET")
+            .arg(API_KEYS_KEY)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        let apikeys: serde_json::Map<String, Value> = serde_json::from_str(&result)?;
+
+        for apikey in apikeys.into_values() {
+            let mut apikey = match apikey {
+                Value::Object(apikey) => apikey,
+                _ => continue,
+            };
+
+            let key = match apikey.remove("key") {
+                Some(Value::String(key)) => key,
+                _ => continue,
+            };
+
+            apikey.insert("key_hash".to_string(), Value::String(hash_key(&key)));
+            apikey.insert("key_prefix".to_string(), Value::String(key_prefix(&key)));
+
+            self.save(serde_json::from_value(Value::Object(apikey))?)?;
         }
 
         Ok(())
     }
+
+    /// Names are validated before they reach a path, which they would otherwise
+    /// be able to escape. Keys saved before that may have spaces, stored as `_`.
+    fn apikey_path(name: &str) -> Result<String, Box<dyn Error>> {
+        let name = name.replace(" ", "_");
+        validate_name(&name)?;
+
+        Ok(format!(".{}", name))
+    }
 }
 
 impl ApiKeyStorage for RedisApiKeyStorage {
@@ -46,45 +88,120 @@ impl ApiKeyStorage for RedisApiKeyStorage {
     }
 
     fn get_by_name(&self, name: &str) -> Result<Option<ApiKey>, Box<dyn Error>> {
-        let result: Option<String> = redis::cmd("JSON.GET")
-            .arg(API_KEYS_KEY)
-            .arg(name)
-            .query(&mut self.conn.lock().unwrap())?;
+        // No key can be saved under an invalid name.
+        let path = match Self::apikey_path(name) {
+            Ok(path) => path,
+            Err(_) => return Ok(None),
+        };
 
-        result
-            .map(|json| serde_json::from_str(&json))
-            .transpose()
-            .map_err(|e| e.into())
+        let result: Result<String, RedisError> = redis::cmd("JSON.GET")
+            .arg(API_KEYS_KEY)
+            .arg(path)
+            .query(&mut self.conn.lock().unwrap());
+
+        match result {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            // RedisJSON answers an error for a path that does not exist.
+            Err(e) if e.kind() == ErrorKind::ResponseError => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
     fn get_by_key(&self, key: &str) -> Result<Option<ApiKey>, Box<dyn Error>> {
-        let apikeys = self.get_all()?;
+        let key_hash = hash_key(key);
 
-        let result = apikeys
-            .into_iter()
-            .find(|(_, apikey)| apikey.key == key)
-            .map(|(_, apikey)| apikey);
+        let name: Option<String> = redis::cmd("HGET")
+            .arg(API_KEY_HASHES_KEY)
+            .arg(&key_hash)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        let apikey = match name {
+            Some(name) => self.get_by_name(&name)?,
+            None => return Ok(None),
+        };
 
-        Ok(result)
+        match apikey {
+            Some(apikey) if apikey.matches(&key_hash, Utc::now()) => Ok(Some(apikey)),
+            _ => {
+                // The key was rotated past its grace period or deleted.
+                redis::cmd("HDEL")
+                    .arg(API_KEY_HASHES_KEY)
+                    .arg(&key_hash)
+                    .query::<()>(&mut self.conn.lock().unwrap())?;
+
+                Ok(None)
+            }
+        }
     }
 
     fn save(&self, apikey: ApiKey) -> Result<(), Box<dyn Error>> {
+        let path = Self::apikey_path(&apikey.name)?;
+
+        let stale_hashes: Vec<String> = match self.get_by_name(&apikey.name)? {
+            Some(stored) => stored
+                .key_hashes()
+                .into_iter()
+                .filter(|key_hash| !apikey.key_hashes().contains(key_hash))
+                .map(str::to_string)
+                .collect(),
+            None => Vec::new(),
+        };
+
         let apikey_json = serde_json::to_string(&apikey)?;
 
         redis::cmd("JSON.SET")
             .arg(API_KEYS_KEY)
-            .arg(format!(".{}", apikey.name.replace(" ", "_")))
+            .arg(path)
             .arg(apikey_json)
-            .query(&mut self.conn.lock().unwrap())?;
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        for key_hash in apikey.key_hashes() {
+            redis::cmd("HSET")
+                .arg(API_KEY_HASHES_KEY)
+                .arg(key_hash)
+                .arg(&apikey.name)
+                .query::<()>(&mut self.conn.lock().unwrap())?;
+        }
+
+        if !stale_hashes.is_empty() {
+            redis::cmd("HDEL")
+                .arg(API_KEY_HASHES_KEY)
+                .arg(stale_hashes)
+                .query::<()>(&mut self.conn.lock().unwrap())?;
+        }
+
+        Ok(())
+    }
+
+    fn save_last_used_at(
+        &self,
+        name: &str,
+        last_used_at: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>> {
+        // Set on the field alone so that it cannot undo a concurrent rotation.
+        redis::cmd("JSON.SET")
+            .arg(API_KEYS_KEY)
+            .arg(format!("{}.last_used_at", Self::apikey_path(name)?))
+            .arg(serde_json::to_string(&last_used_at)?)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
 
         Ok(())
     }
 
     fn delete(&self, apikey_name: &str) -> Result<(), Box<dyn Error>> {
+        let path = Self::apikey_path(apikey_name)?;
+
+        if let Some(apikey) = self.get_by_name(apikey_name)? {
+            redis::cmd("HDEL")
+                .arg(API_KEY_HASHES_KEY)
+                .arg(apikey.key_hashes())
+                .query::<()>(&mut self.conn.lock().unwrap())?;
+        }
+
         redis::cmd("JSON.DEL")
             .arg(API_KEYS_KEY)
-            .arg(apikey_name)
-            .query(&mut self.conn.lock().unwrap())?;
+            .arg(path)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
 
         Ok(())
     }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use std::error::Error;
 
 use crate::apikey::apikey::{ApiKey, ApiKeyMap};
@@ -7,5 +8,11 @@ pub trait ApiKeyStorage: Send + Sync {
     fn get_by_name(&self, name: &str) -> Result<Option<ApiKey>, Box<dyn Error>>;
     fn get_by_key(&self, key: &str) -> Result<Option<ApiKey>, Box<dyn Error>>;
     fn save(&self, apikey: ApiKey) -> Result<(), Box<dyn Error>>;
+    /// Records when the API key was last used, leaving the rest of it untouched.
+    fn save_last_used_at(
+        &self,
+        name: &str,
+        last_used_at: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>>;
     fn delete(&self, apikey: &str) -> Result<(), Box<dyn Error>>;
 }
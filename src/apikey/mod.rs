@@ -1,7 +1,11 @@
 pub mod apikey;
+pub mod apikey_storage_memory;
 pub mod apikey_storage_redis;
 pub mod apikey_storage_trait;
+pub mod scope;
 
-pub use apikey::{ApiKey, ApiKeyMap};
+pub use apikey::{is_in_folder, validate_name, ApiKey, ApiKeyMap};
+pub use apikey_storage_memory::MemoryApiKeyStorage;
 pub use apikey_storage_redis::RedisApiKeyStorage;
 pub use apikey_storage_trait::ApiKeyStorage;
+pub use scope::Scope;
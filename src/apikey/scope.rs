@@ -0,0 +1,49 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "media:read")]
+    MediaRead,
+    #[serde(rename = "media:write")]
+    MediaWrite,
+    #[serde(rename = "media:delete")]
+    MediaDelete,
+    #[serde(rename = "transformations:admin")]
+    TransformationsAdmin,
+    #[serde(rename = "keys:admin")]
+    KeysAdmin,
+    #[serde(rename = "webhooks:admin")]
+    WebhooksAdmin,
+}
+
+impl Scope {
+    pub fn all() -> Vec<Scope> {
+        vec![
+            Scope::MediaRead,
+            Scope::MediaWrite,
+            Scope::MediaDelete,
+            Scope::TransformationsAdmin,
+            Scope::KeysAdmin,
+            Scope::WebhooksAdmin,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::MediaRead => "media:read",
+            Scope::MediaWrite => "media:write",
+            Scope::MediaDelete => "media:delete",
+            Scope::TransformationsAdmin => "transformations:admin",
+            Scope::KeysAdmin => "keys:admin",
+            Scope::WebhooksAdmin => "webhooks:admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,4 +1,8 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::project::default_project;
+use crate::scheduler::ScheduledJob;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct RedisAdapterConfig {
@@ -30,6 +34,119 @@ pub struct ServerConfig {
     pub port: u16,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UploadConfig {
+    pub max_request_size: usize,
+    pub max_field_size: usize,
+    pub tmp_dir: Option<String>,
+    /// Seconds an unfinished resumable upload is kept.
+    pub resumable_expiration: i64,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            max_request_size: 256 * 1024 * 1024,
+            max_field_size: 250 * 1024 * 1024,
+            tmp_dir: None,
+            resumable_expiration: 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheControlConfig {
+    pub original: String,
+    pub derived: String,
+    pub metadata: String,
+}
+
+impl Default for CacheControlConfig {
+    fn default() -> Self {
+        Self {
+            original: "public, max-age=86400".to_string(),
+            derived: "public, max-age=31536000, immutable".to_string(),
+            metadata: "no-cache".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SigningConfig {
+    /// Read from the `SIGNING_SECRET` environment variable; signed URLs are
+    /// disabled without it.
+    #[serde(skip)]
+    pub secret: Option<String>,
+    /// Media under these folders are only served with a valid signature or API key.
+    pub required_folders: Vec<String>,
+    /// Transformed media are only served with a valid signature or API key.
+    pub require_for_transformations: bool,
+    /// Lifetime in seconds of a signed URL when none is requested.
+    pub default_ttl: i64,
+    pub max_ttl: i64,
+}
+
+impl Default for SigningConfig {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            required_folders: Vec::new(),
+            require_for_transformations: false,
+            default_ttl: 60 * 60,
+            max_ttl: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl SigningConfig {
+    pub fn is_required(&self, media_path: &str, has_transformations: bool) -> bool {
+        (has_transformations && self.require_for_transformations)
+            || self.required_folders.iter().any(|folder| {
+                let folder = folder.trim_end_matches('/');
+                media_path
+                    .strip_prefix(folder)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArgRangeConfig {
+    /// Template name as written in URLs, e.g. `c_scale`.
+    pub template: String,
+    pub arg: String,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Restricts the transformations anyone can request. In strict mode, requests
+/// without an API key or signature may only use named (`t_`) transformations, plus
+/// the raw templates listed in `allowed_templates` within `arg_ranges`, with the
+/// arguments those templates know.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TransformationPolicyConfig {
+    pub strict: bool,
+    pub allowed_templates: Vec<String>,
+    pub arg_ranges: Vec<ArgRangeConfig>,
+    /// Steps a chain may have, named transformations counting once expanded.
+    pub max_steps: usize,
+}
+
+impl Default for TransformationPolicyConfig {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            allowed_templates: Vec::new(),
+            arg_ranges: Vec::new(),
+            max_steps: 10,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct AdapterConfig {
     pub redis: Option<RedisAdapterConfig>,
@@ -47,6 +164,8 @@ pub struct StorageConfig {
 #[serde(rename_all = "camelCase")]
 pub enum StorageKind {
     Filesystem,
+    /// Kept in the process and lost when it stops, for development and tests.
+    Memory,
     Redis,
     S3,
 }
@@ -54,6 +173,13 @@ pub enum StorageKind {
 #[derive(Debug, Clone, Deserialize)]
 pub struct ApiKeyConfig {
     pub storage_kind: StorageKind,
+    /// Seconds a rotated key keeps working after the rotation.
+    #[serde(default = "default_rotation_grace_period")]
+    pub rotation_grace_period: i64,
+}
+
+fn default_rotation_grace_period() -> i64 {
+    24 * 60 * 60
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -66,6 +192,197 @@ pub struct MetadataConfig {
     pub storage_kind: StorageKind,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct BucketConfig {
+    pub capacity: u32,
+    pub refill_per_second: f64,
+}
+
+impl BucketConfig {
+    fn new(capacity: u32, refill_per_second: f64) -> Self {
+        Self {
+            capacity,
+            refill_per_second,
+        }
+    }
+}
+
+/// Token buckets applied per API key, or per client IP for anonymous requests.
+/// Requests made with the master key are not limited.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub uploads: BucketConfig,
+    /// Only downloads that have to generate a derived media consume this budget.
+    pub transformations: BucketConfig,
+    pub metadata: BucketConfig,
+}
+
+impl RateLimitConfig {
+    /// Buckets that never refill would deny requests for ever once empty.
+    pub fn validate(&self) -> Result<(), String> {
+        let buckets = [
+            ("uploads", &self.uploads),
+            ("transformations", &self.transformations),
+            ("metadata", &self.metadata),
+        ];
+
+        for (name, bucket) in buckets {
+            if !(bucket.refill_per_second > 0.0 && bucket.refill_per_second.is_finite()) {
+                return Err(format!(
+                    "rate_limit.{}.refill_per_second must be a positive number",
+                    name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            uploads: BucketConfig::new(20, 0.5),
+            transformations: BucketConfig::new(50, 2.0),
+            metadata: BucketConfig::new(200, 20.0),
+        }
+    }
+}
+
+/// Hard limits of an account; `None` means unlimited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaConfig {
+    pub max_bytes_stored: Option<u64>,
+    pub max_bytes_uploaded_per_day: Option<u64>,
+    pub max_derivatives_per_day: Option<u64>,
+}
+
+/// Usage is accounted per API key name, or as `master` for the master key.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UsageConfig {
+    /// Days the daily counters are kept.
+    pub retention_days: i64,
+    pub default_quota: QuotaConfig,
+    /// Quotas replacing the default one for the accounts listed.
+    pub quotas: HashMap<String, QuotaConfig>,
+}
+
+impl UsageConfig {
+    pub fn quota_for(&self, account: &str) -> &QuotaConfig {
+        self.quotas.get(account).unwrap_or(&self.default_quota)
+    }
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 400,
+            default_quota: QuotaConfig::default(),
+            quotas: HashMap::new(),
+        }
+    }
+}
+
+/// Seconds to wait after the `attempt`-th failed attempt, starting at 1:
+/// `base` doubled after each attempt, up to `max`.
+fn exponential_backoff(base: i64, max: i64, attempt: u32) -> i64 {
+    let factor = 2i64.saturating_pow(attempt.saturating_sub(1));
+    base.saturating_mul(factor).min(max)
+}
+
+/// A recurring job defined in `config.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleConfig {
+    pub name: String,
+    #[serde(default = "default_project")]
+    pub project: String,
+    pub cron: String,
+    pub job: ScheduledJob,
+}
+
+/// Failed tasks are retried with an exponential backoff, then moved to the
+/// dead-letter queue.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TaskConfig {
+    /// Where the tasks and schedules are stored.
+    pub storage_kind: StorageKind,
+    /// Seconds completed and cancelled tasks can still be looked up.
+    pub retention: i64,
+    pub max_attempts: u32,
+    pub backoff_base: i64,
+    pub backoff_max: i64,
+    /// Seconds a running task is leased to its instance, renewed by heartbeats.
+    /// Tasks of instances that died are run again once their lease expires.
+    pub lease_timeout: i64,
+    /// Tasks an instance runs at once, at least one. It leases no more than that.
+    pub max_concurrent_tasks: usize,
+    pub schedules: Vec<ScheduleConfig>,
+}
+
+impl TaskConfig {
+    pub fn backoff(&self, attempt: u32) -> i64 {
+        exponential_backoff(self.backoff_base, self.backoff_max, attempt)
+    }
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            storage_kind: StorageKind::Redis,
+            retention: 7 * 24 * 3600,
+            max_attempts: 5,
+            backoff_base: 10,
+            backoff_max: 600,
+            lease_timeout: 60,
+            max_concurrent_tasks: 4,
+            schedules: Vec::new(),
+        }
+    }
+}
+
+/// Deliveries of webhook payloads, retried with an exponential backoff.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Attempts made before a delivery is given up.
+    pub max_attempts: u32,
+    /// Seconds waited after the first failed attempt, doubled after each one.
+    pub backoff_base: i64,
+    pub backoff_max: i64,
+    /// Seconds a webhook has to answer.
+    pub timeout: u64,
+    /// Attempts kept in the delivery log of each webhook.
+    pub delivery_log_size: usize,
+    /// Hosts webhooks may reach even though they resolve to a loopback, private or
+    /// link-local address, which are refused otherwise.
+    pub allowed_hosts: Vec<String>,
+}
+
+impl WebhookConfig {
+    pub fn backoff(&self, attempt: u32) -> i64 {
+        exponential_backoff(self.backoff_base, self.backoff_max, attempt)
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            backoff_base: 30,
+            backoff_max: 3600,
+            timeout: 10,
+            delivery_log_size: 100,
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     #[serde(skip)]
@@ -77,4 +394,37 @@ pub struct Config {
     pub apikey: ApiKeyConfig,
     pub named_transformation: NamedTransformationConfig,
     pub metadata: MetadataConfig,
+    #[serde(default)]
+    pub upload: UploadConfig,
+    #[serde(default)]
+    pub cache_control: CacheControlConfig,
+    #[serde(default)]
+    pub signing: SigningConfig,
+    #[serde(default)]
+    pub transformation_policy: TransformationPolicyConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub usage: UsageConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+    #[serde(default)]
+    pub task: TaskConfig,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_rejects_buckets_without_refill() {
+        let mut config = RateLimitConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.uploads.refill_per_second = 0.0;
+        assert!(config.validate().is_err());
+
+        config.uploads.refill_per_second = f64::NAN;
+        assert!(config.validate().is_err());
+    }
 }
@@ -27,11 +27,19 @@ impl ConfigLoader {
         let mut config: Config = toml::from_str(&config_str)
             .map_err(|err| format!("Failed to parse config file: {}, {}", config_path, err))?;
 
+        config
+            .rate_limit
+            .validate()
+            .map_err(|err| format!("Invalid config file: {}, {}", config_path, err))?;
+
         if config.server.port == 0 {
             config.server.port = 8080;
         }
 
         config.master_key = master_key;
+        config.signing.secret = std::env::var("SIGNING_SECRET")
+            .ok()
+            .filter(|secret| !secret.is_empty());
 
         Ok(config)
     }
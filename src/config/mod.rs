@@ -1,5 +1,8 @@
 pub mod config;
 pub mod config_loader;
 
-pub use config::{Config, StorageKind};
+pub use config::{
+    BucketConfig, Config, QuotaConfig, RateLimitConfig, StorageKind, TaskConfig, TransformationPolicyConfig,
+    UploadConfig, UsageConfig, WebhookConfig,
+};
 pub use config_loader::ConfigLoader;
@@ -0,0 +1,262 @@
+use std::error::Error;
+use std::fmt;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use log::error;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    NotFound,
+    Validation,
+    Conflict,
+    PayloadTooLarge,
+    Unauthorized,
+    Forbidden,
+    QuotaExceeded,
+    RateLimited,
+    Storage,
+    Transformation,
+    Upstream,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Validation => "validation_error",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::QuotaExceeded => "quota_exceeded",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::Storage => "storage_error",
+            ErrorCode::Transformation => "transformation_error",
+            ErrorCode::Upstream => "upstream_error",
+            ErrorCode::Internal => "internal_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Validation => StatusCode::BAD_REQUEST,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::QuotaExceeded => StatusCode::FORBIDDEN,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Storage => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::Transformation => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::Upstream => StatusCode::BAD_GATEWAY,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The error type shared by every layer of the crate. Layers that still return
+/// `Box<dyn Error>` box a `MindiaError` when the kind of failure matters, and
+/// `MindiaError::from` recovers it or classifies well-known library errors.
+#[derive(Debug)]
+pub struct MindiaError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl MindiaError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::PayloadTooLarge, message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unauthorized, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Forbidden, message)
+    }
+
+    pub fn quota_exceeded(quota: &str, limit: u64, used: u64) -> Self {
+        Self::new(ErrorCode::QuotaExceeded, format!("The {} quota is exceeded", quota))
+            .with_details(json!({ "quota": quota, "limit": limit, "used": used }))
+    }
+
+    /// `retry_after` is in seconds and is also sent as the `Retry-After` header.
+    pub fn rate_limited(message: impl Into<String>, retry_after: u64) -> Self {
+        Self::new(ErrorCode::RateLimited, message)
+            .with_details(json!({ "retry_after": retry_after }))
+    }
+
+    pub fn storage(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Storage, message)
+    }
+
+    pub fn transformation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Transformation, message)
+    }
+
+    pub fn upstream(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Upstream, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.code.status()
+    }
+}
+
+impl fmt::Display for MindiaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for MindiaError {}
+
+impl From<std::io::Error> for MindiaError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => Self::not_found(e.to_string()),
+            std::io::ErrorKind::AlreadyExists => Self::conflict(e.to_string()),
+            std::io::ErrorKind::InvalidInput => Self::validation(e.to_string()),
+            std::io::ErrorKind::PermissionDenied => Self::forbidden(e.to_string()),
+            _ => Self::storage(e.to_string()),
+        }
+    }
+}
+
+impl From<redis::RedisError> for MindiaError {
+    fn from(e: redis::RedisError) -> Self {
+        Self::storage(e.to_string())
+    }
+}
+
+impl From<image::ImageError> for MindiaError {
+    fn from(e: image::ImageError) -> Self {
+        Self::transformation(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for MindiaError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::upstream(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for MindiaError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::internal(e.to_string())
+    }
+}
+
+impl From<Box<dyn Error>> for MindiaError {
+    fn from(e: Box<dyn Error>) -> Self {
+        let e = match e.downcast::<MindiaError>() {
+            Ok(e) => return *e,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<std::io::Error>() {
+            Ok(e) => return Self::from(*e),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<redis::RedisError>() {
+            Ok(e) => return Self::from(*e),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<image::ImageError>() {
+            Ok(e) => return Self::from(*e),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<reqwest::Error>() {
+            Ok(e) => return Self::from(*e),
+            Err(e) => e,
+        };
+
+        Self::internal(e.to_string())
+    }
+}
+
+impl IntoResponse for MindiaError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+
+        if status.is_server_error() {
+            error!("{}: {}", self.code.as_str(), self.message);
+        }
+
+        let retry_after = self
+            .details
+            .as_ref()
+            .filter(|_| self.code == ErrorCode::RateLimited)
+            .and_then(|details| details.get("retry_after"))
+            .and_then(Value::as_u64);
+
+        let body = json!({
+            "code": self.code.as_str(),
+            "message": self.message,
+            "details": self.details,
+        });
+
+        let mut response = (status, Json(body)).into_response();
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_boxed_error() {
+        let e: Box<dyn Error> = Box::new(MindiaError::conflict("Destination already exists"));
+        assert_eq!(MindiaError::from(e).code, ErrorCode::Conflict);
+
+        let e: Box<dyn Error> = Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "missing"));
+        assert_eq!(MindiaError::from(e).code, ErrorCode::NotFound);
+
+        let e: Box<dyn Error> = "Unexpected".into();
+        let e = MindiaError::from(e);
+        assert_eq!(e.code, ErrorCode::Internal);
+        assert_eq!(e.message, "Unexpected");
+    }
+}
@@ -1,6 +1,7 @@
 use std::error::Error;
 use bytes::BytesMut;
 use crate::metadata::Metadata;
+use crate::utils::sha256_hex;
 
 #[derive(Default)]
 pub struct ContentInfoExtractor {}
@@ -11,13 +12,11 @@ impl ContentInfoExtractor {
         mut metadata: Metadata,
         body: BytesMut,
     ) -> Result<Metadata, Box<dyn Error>> {
-        let content_type = match metadata.path.extension() {
-           "webp" => "image/webp",
-            _ => "image/octet-stream",
-        };
+        let content_type = mime_guess::from_ext(metadata.path.extension()).first_or_octet_stream();
 
         metadata.content_type = Some(content_type.to_string());
         metadata.content_length = body.len();
+        metadata.content_hash = Some(sha256_hex(&body));
 
         Ok(metadata)
     }
@@ -1,7 +1,8 @@
 use bytes::BytesMut;
 use std::collections::HashMap;
 use std::error::Error;
-use std::io::Cursor;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Seek};
 
 use crate::metadata::Metadata;
 
@@ -11,11 +12,27 @@ pub struct ExifExtractor {}
 impl ExifExtractor {
     pub fn extract(
         &self,
-        mut metadata: Metadata,
+        metadata: Metadata,
         body: BytesMut,
     ) -> Result<Metadata, Box<dyn Error>> {
+        self.extract_from(metadata, &mut Cursor::new(&body))
+    }
 
-        match exif::Reader::new().read_from_container(&mut Cursor::new(&body)) {
+    /// Reads the EXIF fields from the file without loading it in memory.
+    pub fn extract_file(
+        &self,
+        metadata: Metadata,
+        path: &std::path::Path,
+    ) -> Result<Metadata, Box<dyn Error>> {
+        self.extract_from(metadata, &mut BufReader::new(File::open(path)?))
+    }
+
+    fn extract_from<R: BufRead + Seek>(
+        &self,
+        mut metadata: Metadata,
+        reader: &mut R,
+    ) -> Result<Metadata, Box<dyn Error>> {
+        match exif::Reader::new().read_from_container(reader) {
             Ok(exif) => {
                 let mut fields_map = HashMap::new();
                 for field in exif.fields() {
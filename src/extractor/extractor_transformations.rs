@@ -1,9 +1,11 @@
 use std::error::Error;
 use std::sync::Arc;
 
+use crate::config::TransformationPolicyConfig;
+use crate::error::MindiaError;
 use crate::transform::{
-    NamedTransformationStorage, TransformationDescriptor, TransformationDescriptorChain,
-    TransformationTemplateRegistry,
+    FormatPreference, NamedTransformationStorage, OutputFormat, TransformationDescriptor,
+    TransformationDescriptorChain, TransformationTemplateRegistry,
 };
 
 const TRANSFORMATION_SEPARATOR: char = '/';
@@ -11,10 +13,13 @@ const TRANSFORMATION_NAME_SEPARATOR: char = ':';
 const ARG_SEPARATOR: char = ',';
 const VALUE_SEPARATOR: char = '_';
 const NAMED_TRANSFORMATION_PREFIX: &str = "t_";
+const FORMAT_PREFIX: &str = "f_";
+const AUTO_FORMAT: &str = "auto";
 
 pub struct TransformationsExtractor {
     named_transformation_storage: Arc<dyn NamedTransformationStorage>,
     transformation_template_registry: Arc<TransformationTemplateRegistry>,
+    policy: Option<TransformationPolicyConfig>,
 }
 
 impl TransformationsExtractor {
@@ -25,9 +30,17 @@ impl TransformationsExtractor {
         Self {
             named_transformation_storage,
             transformation_template_registry,
+            policy: None,
         }
     }
 
+    /// Checks raw transformations against `policy`; named transformations are always
+    /// allowed.
+    pub fn with_policy(mut self, policy: TransformationPolicyConfig) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
     pub fn extract_one(
         &self,
         transformation_chain_str: &str,
@@ -51,20 +64,24 @@ impl TransformationsExtractor {
                 .collect::<Vec<&str>>();
 
             for transformation_str in transformations_str {
-                if transformation_str.starts_with(NAMED_TRANSFORMATION_PREFIX) {
-                    let transformation_name = transformation_str
-                        .strip_prefix(NAMED_TRANSFORMATION_PREFIX)
-                        .unwrap();
-
+                if let Some(format_name) = transformation_str.strip_prefix(FORMAT_PREFIX) {
+                    let format = if format_name == AUTO_FORMAT {
+                        FormatPreference::Auto
+                    } else {
+                        OutputFormat::from_name(format_name)
+                            .filter(|output_format| output_format.is_supported())
+                            .map(FormatPreference::Fixed)
+                            .ok_or_else(|| MindiaError::validation("Unsupported output format"))?
+                    };
+
+                    transformation_chain.set_format(format);
+                } else if let Some(transformation_name) =
+                    transformation_str.strip_prefix(NAMED_TRANSFORMATION_PREFIX)
+                {
                     let named_transformation = self
                         .named_transformation_storage
                         .get_by_name(transformation_name)?
-                        .ok_or_else(|| {
-                            std::io::Error::new(
-                                std::io::ErrorKind::NotFound,
-                                "Named transformation not found",
-                            )
-                        })?;
+                        .ok_or_else(|| MindiaError::validation("Named transformation not found"))?;
 
                     for transformation in named_transformation.transformations {
                         transformation_chain.add(transformation);
@@ -79,7 +96,7 @@ impl TransformationsExtractor {
                     let transformation_template = self
                         .transformation_template_registry
                         .find_one(transformation_name.as_str())
-                        .ok_or("Unknown transformation")?;
+                        .ok_or_else(|| MindiaError::validation("Unknown transformation"))?;
 
                     let mut transformation = TransformationDescriptor::new(transformation_template);
 
@@ -89,22 +106,147 @@ impl TransformationsExtractor {
                             .collect::<Vec<&str>>();
 
                         for arg in args {
-                            let arg_parts = arg.split(VALUE_SEPARATOR).collect::<Vec<&str>>();
+                            let (key, value) = arg
+                                .split_once(VALUE_SEPARATOR)
+                                .ok_or_else(|| {
+                                    MindiaError::validation("Invalid transformation argument")
+                                })?;
 
-                            let key = arg_parts[0].to_string();
-                            let value = arg_parts[1].replace("%", "/").to_string();
+                            let key = key.to_string();
+                            let value = value.replace("%", "/").to_string();
 
                             transformation.add_arg(key, value);
                         }
                     }
 
+                    self.check_policy(&transformation_name, &transformation)?;
+
                     transformation_chain.add(transformation);
                 }
             }
 
+            self.check_steps(&transformation_chain)?;
+
             transformation_chains.push(transformation_chain);
         }
 
         Ok(transformation_chains)
     }
+
+    /// Counted after the named transformations are expanded, so that they cannot be
+    /// used to get past the limit.
+    fn check_steps(
+        &self,
+        transformation_chain: &TransformationDescriptorChain,
+    ) -> Result<(), Box<dyn Error>> {
+        let policy = match &self.policy {
+            Some(policy) => policy,
+            None => return Ok(()),
+        };
+
+        let steps = transformation_chain.get_transformation_descriptors().len();
+        if steps > policy.max_steps {
+            return Err(Box::new(MindiaError::forbidden(format!(
+                "Transformation chains may have at most {} steps",
+                policy.max_steps
+            ))));
+        }
+
+        Ok(())
+    }
+
+    fn check_policy(
+        &self,
+        transformation_name: &str,
+        transformation: &TransformationDescriptor,
+    ) -> Result<(), Box<dyn Error>> {
+        let policy = match &self.policy {
+            Some(policy) => policy,
+            None => return Ok(()),
+        };
+
+        let not_allowed = |reason: String| -> Box<dyn Error> {
+            Box::new(MindiaError::forbidden(reason))
+        };
+
+        if !policy
+            .allowed_templates
+            .iter()
+            .any(|template| template == transformation_name)
+        {
+            return Err(not_allowed(format!(
+                "Transformation {} is not allowed",
+                transformation_name
+            )));
+        }
+
+        if let Some(arg) = transformation
+            .arg_values
+            .keys()
+            .find(|arg| !transformation.transformation_template.args.contains_key(*arg))
+        {
+            return Err(not_allowed(format!(
+                "Unknown argument {} of {}",
+                arg, transformation_name
+            )));
+        }
+
+        for arg_range in policy
+            .arg_ranges
+            .iter()
+            .filter(|arg_range| arg_range.template == transformation_name)
+        {
+            let value = match transformation.arg_values.get(&arg_range.arg) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let in_range = value
+                .parse::<f64>()
+                .is_ok_and(|value| value >= arg_range.min && value <= arg_range.max);
+
+            if !in_range {
+                return Err(not_allowed(format!(
+                    "Argument {} of {} must be between {} and {}",
+                    arg_range.arg, transformation_name, arg_range.min, arg_range.max
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::{MemoryNamedTransformationStorage, NamedTransformation};
+
+    #[test]
+    fn test_policy_bounds_expanded_steps_and_arguments() {
+        let storage = Arc::new(MemoryNamedTransformationStorage::new());
+        let registry = Arc::new(TransformationTemplateRegistry::new());
+
+        let unchecked = TransformationsExtractor::new(storage.clone(), registry.clone());
+        let chain = unchecked
+            .extract_one("c_scale:w_10/c_scale:w_20/c_scale:w_30")
+            .unwrap();
+        storage
+            .save(NamedTransformation {
+                name: "thumb".to_string(),
+                transformations: chain.get_transformation_descriptors().clone(),
+            })
+            .unwrap();
+
+        let policy = TransformationPolicyConfig {
+            allowed_templates: vec!["c_scale".to_string()],
+            max_steps: 2,
+            ..Default::default()
+        };
+        let extractor = TransformationsExtractor::new(storage, registry).with_policy(policy);
+
+        assert!(extractor.extract_one("c_scale:w_100").is_ok());
+        assert!(extractor.extract_one("c_scale:x_1").is_err());
+        assert!(extractor.extract_one("t_thumb").is_err());
+    }
 }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use log::info;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
@@ -39,10 +40,11 @@ impl CacheHandler {
 #[async_trait]
 impl TaskExecutor for CacheHandler {
     async fn run(&self, mut task: Task) -> Result<Task, Box<dyn Error>> {
-        println!("Running task: {:?}", task);
+        info!("Running task: {:?}", task);
 
         let before_date = match task.details {
             Details::ClearCache { before_date } => before_date,
+            _ => return Err("Unexpected task details for a cache task".into()),
         };
 
         let metadatas = self
@@ -52,14 +54,17 @@ impl TaskExecutor for CacheHandler {
             .get_many_before_date(before_date, METADATA_LIMIT)?;
 
         if metadatas.is_empty() {
-            task.status = TaskStatus::Completed;
+            task.set_status(TaskStatus::Completed);
             return Ok(task);
         }
 
+        // Runs again on the next batch until no media has derivatives older than
+        // `before_date` left. The more recent ones are kept.
         for mut metadata in metadatas {
             let derived_media_paths: Vec<_> = metadata
                 .derived_medias
                 .iter()
+                .filter(|derived_media| derived_media.created_at < before_date)
                 .map(|derived_media| derived_media.path.clone())
                 .collect();
 
@@ -76,8 +81,76 @@ impl TaskExecutor for CacheHandler {
                 .lock()
                 .await
                 .save(metadata.path.as_str(), metadata.clone())?;
+            task.progress.processed += 1;
         }
 
         Ok(task)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    use crate::media::Path;
+    use crate::metadata::{MemoryMetadataStorage, Metadata};
+    use crate::project::DEFAULT_PROJECT;
+    use crate::scheduler::TaskKind;
+    use crate::storage::MemoryStorage;
+
+    fn derived(path: &str, created_at: DateTime<Utc>) -> Metadata {
+        let mut metadata = Metadata::new(Path::new(path).unwrap());
+        metadata.created_at = created_at;
+        metadata
+    }
+
+    #[tokio::test]
+    async fn test_clears_only_derivatives_older_than_before_date() {
+        let now = Utc::now();
+        let cache_storage = Arc::new(Mutex::new(MemoryStorage::new()));
+        let metadata_storage = Arc::new(Mutex::new(MemoryMetadataStorage::new()));
+
+        let old = "/_/w_10/bbd2fa99-f35e-4062-92eb-9d26caa943ae.webp";
+        let recent = "/_/w_20/bbd2fa99-f35e-4062-92eb-9d26caa943ae.webp";
+        for path in [old, recent] {
+            let body = bytes::Bytes::from_static(b"webp");
+            cache_storage.lock().await.upload(path, body).await.unwrap();
+        }
+        let path = Path::new("/bbd2fa99-f35e-4062-92eb-9d26caa943ae.webp").unwrap();
+        let mut metadata = Metadata::new(path);
+        metadata.append_derived_media(derived(old, now - Duration::hours(2)));
+        metadata.append_derived_media(derived(recent, now));
+        metadata_storage
+            .lock()
+            .await
+            .save(metadata.path.as_str(), metadata.clone())
+            .unwrap();
+
+        let handler = CacheHandler::new(cache_storage.clone(), metadata_storage.clone());
+        let details = Details::ClearCache {
+            before_date: now - Duration::hours(1),
+        };
+        let mut task = Task::new(DEFAULT_PROJECT.to_string(), TaskKind::ClearCache, details);
+        while task.status != TaskStatus::Completed {
+            task = handler.run(task).await.unwrap();
+        }
+
+        let metadata = metadata_storage
+            .lock()
+            .await
+            .get_by_path(metadata.path.as_str())
+            .unwrap()
+            .unwrap();
+        let paths: Vec<&str> = metadata
+            .derived_medias
+            .iter()
+            .map(|dm| dm.path.as_str())
+            .collect();
+        assert_eq!(paths, vec![recent]);
+
+        let cache_storage = cache_storage.lock().await;
+        assert!(cache_storage.size(old).await.unwrap().is_none());
+        assert!(cache_storage.size(recent).await.unwrap().is_some());
+    }
+}
@@ -1,19 +1,36 @@
 use std::{error::Error, sync::Arc};
 
-use bytes::{Bytes, BytesMut};
+use async_trait::async_trait;
+use bytes::BytesMut;
+use chrono::Utc;
+use serde_json::{json, Value};
 use tokio::sync::Mutex;
 
-use crate::media::Path;
-use crate::metadata::{Metadata, MetadataStorage};
+use super::{PipelineStepsFactory, UploadMediaContext};
+use crate::error::MindiaError;
+use crate::extractor::{ContentInfoExtractor, ExifExtractor};
+use crate::media::{MediaGroupHandle, MediaHandle, MediaSource, Path, TempFile};
+use crate::metadata::{Metadata, MetadataPage, MetadataStorage};
+use crate::pipeline::PipelineStep;
+use crate::scheduler::{BulkItemResult, BulkOperation, Details, Task, TaskExecutor, TaskStatus};
 use crate::storage::FileStorage;
-use crate::transform::TransformationDescriptorChain;
+use crate::transform::{
+    FormatConverter, OutputFormat, PathGenerator, TransformationDescriptorChain,
+};
+use crate::usage::{UsageMetric, UsageReservation, UsageTracker};
+use crate::webhook::{WebhookDispatcher, WebhookEvent};
+
+/// Page size used to expand a bulk prefix into media paths.
+const BULK_LIST_LIMIT: usize = 1000;
 
 #[derive(Clone)]
 pub struct MediaHandler {
     file_storage: Arc<Mutex<dyn FileStorage>>,
     cache_storage: Arc<Mutex<dyn FileStorage>>,
     metadata_storage: Arc<Mutex<dyn MetadataStorage>>,
-    // pipeline_steps_factory: PipelineStepsFactory,
+    pipeline_steps_factory: PipelineStepsFactory,
+    usage: Option<UsageTracker>,
+    webhooks: Option<WebhookDispatcher>,
 }
 
 impl MediaHandler {
@@ -21,13 +38,69 @@ impl MediaHandler {
         file_storage: Arc<Mutex<dyn FileStorage>>,
         cache_storage: Arc<Mutex<dyn FileStorage>>,
         metadata_storage: Arc<Mutex<dyn MetadataStorage>>,
-        // pipeline_steps_factory: PipelineStepsFactory,
+        pipeline_steps_factory: PipelineStepsFactory,
     ) -> Self {
         Self {
             file_storage,
             cache_storage,
             metadata_storage,
-            // pipeline_steps_factory,
+            pipeline_steps_factory,
+            usage: None,
+            webhooks: None,
+        }
+    }
+
+    pub fn with_usage(self, usage: UsageTracker) -> Self {
+        Self {
+            usage: Some(usage),
+            ..self
+        }
+    }
+
+    pub fn with_webhooks(self, webhooks: WebhookDispatcher) -> Self {
+        Self {
+            webhooks: Some(webhooks),
+            ..self
+        }
+    }
+
+    fn notify(&self, event: WebhookEvent, data: Value) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.dispatch(event, data);
+        }
+    }
+
+    pub fn check_upload_quota(
+        &self,
+        owner: Option<&str>,
+        bytes: u64,
+        derivatives: u64,
+    ) -> Result<(), MindiaError> {
+        match (&self.usage, owner) {
+            (Some(usage), Some(owner)) => usage.check_upload_quota(owner, bytes, derivatives),
+            _ => Ok(()),
+        }
+    }
+
+    fn reserve_usage(
+        &self,
+        owner: Option<&str>,
+        reserve: impl FnOnce(&UsageTracker, &str) -> Result<UsageReservation, MindiaError>,
+    ) -> Result<Option<UsageReservation>, MindiaError> {
+        match (&self.usage, owner) {
+            (Some(usage), Some(owner)) => Ok(Some(reserve(usage, owner)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Accounts the bytes of a media sent to a client to its owner.
+    pub fn record_served(&self, media_source: &MediaSource, bytes: u64) {
+        self.record_usage(media_source.owner.as_deref(), UsageMetric::BytesServed, bytes);
+    }
+
+    fn record_usage(&self, owner: Option<&str>, metric: UsageMetric, amount: u64) {
+        if let (Some(usage), Some(owner)) = (&self.usage, owner) {
+            usage.record(owner, metric, amount as i64);
         }
     }
 
@@ -41,173 +114,301 @@ impl MediaHandler {
         Ok(result)
     }
 
+    pub async fn list_folder(
+        &self,
+        folder: &str,
+        cursor: Option<&str>,
+        limit: usize,
+        recursive: bool,
+    ) -> Result<MetadataPage, Box<dyn Error>> {
+        self.metadata_storage
+            .lock()
+            .await
+            .list(folder, cursor, limit, recursive)
+    }
+
     pub async fn upload(
         &self,
         path: Path,
+        original_filename: Option<String>,
         transformation_chains: Vec<TransformationDescriptorChain>,
-        body: BytesMut,
-    ) -> Result<(), Box<dyn Error>> {
-        /*    let transforms: Vec<Box<dyn PipelineStep<UploadMediaContext>>> = vec![
-                    Box::new(ExifExtractor::default()),
-                    Box::new(WebpConverter::default()),
-                    Box::new(PathGenerator::default()),
-                    Box::new(ContentInfoExtractor::default()),
-                ];
-
-                let mut context = UploadMediaContext::default();
-                context.media_handle = MediaHandle::new(body.clone(), Metadata::new(path.clone()));
-
-                for step in transforms {
-                    context = step.execute(context).await?;
-                }
+        file: &TempFile,
+        owner: Option<String>,
+    ) -> Result<Metadata, Box<dyn Error>> {
+        let size = tokio::fs::metadata(file.path()).await?.len();
 
-                self.file_storage
-                    .upload(
-                        context.media_handle.metadata.path.as_str(),
-                        context.media_handle.body.clone().into(),
-                    )
+        let derivatives = transformation_chains.len() as u64;
+        let reservation = self.reserve_usage(owner.as_deref(), |usage, owner| {
+            usage.reserve_upload(owner, size, derivatives)
+        })?;
+
+        let mut metadata = Metadata::new(path);
+        metadata.original_filename = original_filename;
+        metadata.owner = owner;
+
+        let transforms: Vec<Box<dyn PipelineStep<UploadMediaContext>>> = vec![
+            Box::new(ExifExtractor::default()),
+            Box::new(FormatConverter::new(OutputFormat::Webp)),
+            Box::new(PathGenerator::default()),
+            Box::new(ContentInfoExtractor::default()),
+        ];
+
+        // The original stays on disk: only the decoded image and its encoding
+        // are held in memory.
+        let mut context = UploadMediaContext::default();
+        context.media_handle = MediaHandle::new(BytesMut::new(), metadata);
+        context.source = Some(file.path().to_path_buf());
+
+        for step in transforms {
+            context = step.execute(context).await?;
+        }
+
+        self.file_storage
+            .lock()
+            .await
+            .upload(
+                context.media_handle.metadata.path.as_str(),
+                context.media_handle.body.clone().freeze(),
+            )
+            .await?;
+
+        self.metadata_storage.lock().await.save(
+            context.media_handle.metadata.path.as_str(),
+            context.media_handle.metadata.clone(),
+        )?;
+
+        // The original is stored, its usage is kept even if a derivative fails.
+        if let Some(mut reservation) = reservation {
+            reservation.settle(
+                UsageMetric::BytesStored,
+                context.media_handle.metadata.content_length as u64,
+            );
+            reservation.commit();
+        }
+
+        let mut media_group_handle = MediaGroupHandle::new(context.media_handle.clone(), vec![]);
+
+        if !transformation_chains.is_empty() {
+            for transformation_chain in transformation_chains {
+                let derived_media = self
+                    .derive(context.media_handle.clone(), transformation_chain)
                     .await?;
 
-                self.metadata_storage.save(
-                    context.media_handle.metadata.path.as_str(),
-                    context.media_handle.metadata.clone(),
-                )?;
-
-                let mut media_group_handle = MediaGroupHandle::new(context.media_handle.clone(), vec![]);
-        */
-        // if !transformation_chains.is_empty() {
-        //     for transformation_chain in transformation_chains {
-        //         let mut transformation_steps = self
-        //             .pipeline_steps_factory
-        //             .create(transformation_chain.clone())?;
-        //         transformation_steps.push(Box::new(PathGenerator::default()));
-        //         transformation_steps.push(Box::new(ContentInfoExtractor::default()));
-        //
-        //         let mut sub_context = context.clone();
-        //         sub_context.media_handle.metadata.embedded_metadata.clear();
-        //         sub_context.transformations = transformation_chain;
-        //
-        //         for step in transformation_steps {
-        //             sub_context = step.execute(sub_context).await?;
-        //         }
-        //
-        //          self.cache_storage.upload(
-        //              sub_context.media_handle.metadata.path.as_str(),
-        //              sub_context.media_handle.body.clone().into(),
-        //          ).await?;
-        //
-        //         media_group_handle.add_derived_media(sub_context.media_handle);
-        //     }
-        //
-        //     self.metadata_storage.save(
-        //         media_group_handle.media.metadata.path.as_str(),
-        //         media_group_handle.media.metadata.clone(),
-        //     )?;
-        // }
-
-        // Ok(media_group_handle.media.metadata)
-        Ok(())
+                media_group_handle.add_derived_media(derived_media);
+            }
+
+            self.metadata_storage.lock().await.save(
+                media_group_handle.media.metadata.path.as_str(),
+                media_group_handle.media.metadata.clone(),
+            )?;
+        }
+
+        let metadata = media_group_handle.media.metadata;
+        self.notify(WebhookEvent::MediaUploaded, json!({ "media": metadata }));
+        for derived_media in &metadata.derived_medias {
+            self.notify(
+                WebhookEvent::DerivativeCreated,
+                json!({ "path": metadata.path.as_str(), "derivative": derived_media }),
+            );
+        }
+
+        Ok(metadata)
     }
 
+    /// Serves the media, or the derived media for `transformation_chain`.
+    /// `before_derive` runs only when that derived media has to be generated.
     pub async fn download(
         &self,
         path: Path,
         transformation_chain: Option<TransformationDescriptorChain>,
-    ) -> Result<Option<Bytes>, Box<dyn Error + Send>> {
-        Ok(None)
-
-        //     if transformation_chain.is_none() {
-        //         let body = self.file_storage.download(path.as_str()).await?;
-        //         return Ok(body);
-        //     }
-        //
-        //     let transformation_chain = transformation_chain.unwrap();
-        //
-        //     let derived_path =
-        //         PathGenerator::default().transform(&path, &transformation_chain)?;
-        //
-        //     match self.cache_storage.download(derived_path.as_str()).await? {
-        //         Some(body) => Ok(Some(body)),
-        //         None => {
-        //         //     let mut transformation_steps = self
-        //         //         .pipeline_steps_factory
-        //         //         .create(transformation_chain.clone())?;
-        //         //     transformation_steps.push(Box::new(PathGenerator::default()));
-        //         //     transformation_steps.push(Box::new(ContentInfoExtractor::default()));
-        //         //
-        //         //     match self.file_storage.download(path.as_str()).await? {
-        //         //         None => Ok(None),
-        //         //         Some(body) => {
-        //         //             let mut context = UploadMediaContext::default();
-        //         //             context.media_handle = MediaHandle::new(BytesMut::from(&body[..]), Metadata::new(path.clone()));
-        //         //
-        //         //             for step in transformation_steps {
-        //         //                 context = step.execute(context).await?;
-        //         //             }
-        //         //
-        //         //             self.cache_storage.upload(
-        //         //                 context.media_handle.metadata.path.as_str(),
-        //         //                 context.media_handle.body.clone().into(),
-        //         //             ).await?;
-        //         //
-        //         //             self.metadata_storage.save(
-        //         //                 context.media_handle.metadata.path.as_str(),
-        //         //                 context.media_handle.metadata.clone()
-        //         //             )?;
-        //         //
-        //         //             Ok(Some(context.media_handle.body.freeze()))
-        //         //         }
-        //         //     }
-        //         // }
-        //     }
-    }
-
-    pub async fn move_(&self, src: Path, dst: Path) -> Result<(), Box<dyn Error>> {
-        let mut metadata = match self
+        accept: Option<&str>,
+        before_derive: impl FnOnce() -> Result<(), MindiaError>,
+    ) -> Result<Option<MediaSource>, Box<dyn Error>> {
+        let metadata = self
             .metadata_storage
             .lock()
             .await
-            .get_by_path(src.as_str())?
-        {
-            Some(metadata) => metadata,
-            None => {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Metadata not found",
-                )));
+            .get_by_path(path.as_str())?;
+        let owner = metadata.as_ref().and_then(|metadata| metadata.owner.clone());
+
+        let transformation_chain = match transformation_chain {
+            Some(transformation_chain) if !transformation_chain.is_empty() => transformation_chain,
+            _ => {
+                let size = match self.file_storage.lock().await.size(path.as_str()).await? {
+                    Some(size) => size,
+                    None => return Ok(None),
+                };
+
+                let metadata = match metadata {
+                    Some(metadata) => metadata,
+                    None => fallback_metadata(path, size)?,
+                };
+
+                return Ok(Some(
+                    MediaSource::new(metadata, size, self.file_storage.clone()).with_owner(owner),
+                ));
             }
         };
 
-        let mut new_derived_medias: Vec<Metadata> = Vec::new();
+        let mut transformation_chain = transformation_chain;
+        let has_alpha = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.has_alpha)
+            .unwrap_or(true);
+        transformation_chain.resolve_format(accept.unwrap_or(""), has_alpha);
 
-        for mut derived_media in metadata.derived_medias {
-            self.cache_storage
-                .lock()
-                .await
-                .move_(derived_media.path.as_str(), dst.as_str())
-                .await?;
-            derived_media.path = dst.clone().into();
-            new_derived_medias.push(derived_media);
-        }
-
-        metadata.derived_medias = new_derived_medias;
+        let derived_path = PathGenerator::default().transform(&path, &transformation_chain)?;
 
-        self.file_storage
+        let cached_size = self
+            .cache_storage
             .lock()
             .await
-            .move_(src.as_str(), dst.as_str())
+            .size(derived_path.as_str())
             .await?;
-        metadata.path = dst.clone().into();
 
-        self.metadata_storage
+        if let Some(size) = cached_size {
+            let derived_metadata = metadata
+                .and_then(|metadata| {
+                    metadata
+                        .derived_medias
+                        .into_iter()
+                        .find(|derived_media| derived_media.path == derived_path)
+                });
+
+            let derived_metadata = match derived_metadata {
+                Some(derived_metadata) => derived_metadata,
+                None => fallback_metadata(derived_path, size)?,
+            };
+
+            return Ok(Some(
+                MediaSource::new(derived_metadata, size, self.cache_storage.clone())
+                    .with_owner(owner),
+            ));
+        }
+
+        before_derive()?;
+        let reservation = self.reserve_usage(owner.as_deref(), |usage, owner| {
+            usage.reserve_derivative(owner)
+        })?;
+
+        let body = match self.file_storage.lock().await.download(path.as_str()).await? {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+
+        let media_handle = MediaHandle::new(BytesMut::from(&body[..]), Metadata::new(path.clone()));
+        let derived_media = self.derive(media_handle, transformation_chain).await?;
+        if let Some(reservation) = reservation {
+            reservation.commit();
+        }
+
+        let metadata_storage = self.metadata_storage.lock().await;
+        if let Some(mut metadata) = metadata_storage.get_by_path(path.as_str())? {
+            metadata.remove_derived_media(&derived_media.metadata.path);
+            metadata.append_derived_media(derived_media.metadata.clone());
+            metadata_storage.save(path.as_str(), metadata)?;
+        }
+
+        self.notify(
+            WebhookEvent::DerivativeCreated,
+            json!({ "path": path.as_str(), "derivative": derived_media.metadata }),
+        );
+
+        let body = derived_media.body.freeze();
+
+        Ok(Some(
+            MediaSource::new(
+                derived_media.metadata,
+                body.len() as u64,
+                self.cache_storage.clone(),
+            )
+            .with_body(body)
+            .with_owner(owner),
+        ))
+    }
+
+    async fn derive(
+        &self,
+        media_handle: MediaHandle,
+        transformation_chain: TransformationDescriptorChain,
+    ) -> Result<MediaHandle, Box<dyn Error>> {
+        let mut transformation_steps = self
+            .pipeline_steps_factory
+            .create(transformation_chain.clone())?;
+        if transformation_chain.get_transformation_descriptors().is_empty() {
+            transformation_steps.push(Box::new(FormatConverter::new(
+                transformation_chain.output_format(),
+            )));
+        }
+        transformation_steps.push(Box::new(PathGenerator::default()));
+        transformation_steps.push(Box::new(ContentInfoExtractor::default()));
+
+        let mut context = UploadMediaContext::default();
+        context.media_handle = media_handle;
+        context.media_handle.metadata.embedded_metadata.clear();
+        context.media_handle.metadata.derived_medias.clear();
+        context.transformations = transformation_chain;
+
+        for step in transformation_steps {
+            context = step.execute(context).await?;
+        }
+        context.media_handle.metadata.transformations = Some(context.transformations.clone());
+
+        self.cache_storage
             .lock()
             .await
-            .save(dst.as_str(), metadata.clone())?;
+            .upload(
+                context.media_handle.metadata.path.as_str(),
+                context.media_handle.body.clone().freeze(),
+            )
+            .await?;
 
-        Ok(())
+        Ok(context.media_handle)
+    }
+
+    pub async fn move_(&self, src: Path, dst: Path) -> Result<Metadata, Box<dyn Error>> {
+        let metadata = self.relocate(&src, &dst, Relocation::Move).await?;
+
+        self.metadata_storage.lock().await.delete(src.as_str())?;
+
+        self.notify(
+            WebhookEvent::MediaMoved,
+            json!({ "src": src.as_str(), "media": metadata }),
+        );
+
+        Ok(metadata)
     }
 
-    pub async fn copy(&self, src: Path, dst: Path) -> Result<(), Box<dyn Error>> {
+    pub async fn copy(&self, src: Path, dst: Path) -> Result<Metadata, Box<dyn Error>> {
+        // Refused before the bytes are duplicated.
+        let reservation = match self.read(src.clone()).await? {
+            Some(source) => self.reserve_usage(source.owner.as_deref(), |usage, owner| {
+                usage.reserve_storage(owner, source.content_length as u64)
+            })?,
+            None => None,
+        };
+
+        let metadata = self.relocate(&src, &dst, Relocation::Copy).await?;
+
+        if let Some(mut reservation) = reservation {
+            reservation.settle(UsageMetric::BytesStored, metadata.content_length as u64);
+            reservation.commit();
+        }
+
+        self.notify(
+            WebhookEvent::MediaCopied,
+            json!({ "src": src.as_str(), "media": metadata }),
+        );
+
+        Ok(metadata)
+    }
+
+    async fn relocate(
+        &self,
+        src: &Path,
+        dst: &Path,
+        relocation: Relocation,
+    ) -> Result<Metadata, Box<dyn Error>> {
         let mut metadata = match self
             .metadata_storage
             .lock()
@@ -216,51 +417,271 @@ impl MediaHandler {
         {
             Some(metadata) => metadata,
             None => {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Metadata not found",
-                )));
+                return Err(Box::new(MindiaError::not_found("Media not found")));
             }
         };
 
+        if dst.extension() != src.extension() {
+            return Err(Box::new(MindiaError::validation(
+                "Destination must keep the extension of the source",
+            )));
+        }
+
+        let dst_exists = self
+            .metadata_storage
+            .lock()
+            .await
+            .get_by_path(dst.as_str())?
+            .is_some()
+            || self.file_storage.lock().await.size(dst.as_str()).await?.is_some();
+
+        if dst_exists {
+            return Err(Box::new(MindiaError::conflict("Destination already exists")));
+        }
+
         let mut new_derived_medias: Vec<Metadata> = Vec::new();
 
         for mut derived_media in metadata.derived_medias {
-            self.cache_storage
-                .lock()
-                .await
-                .copy(derived_media.path.as_str(), dst.as_str())
-                .await?;
-            derived_media.path = dst.clone().into();
+            let derived_path = match &derived_media.transformations {
+                Some(transformation_chain) => {
+                    PathGenerator::default().transform(dst, transformation_chain)?
+                }
+                None => rebase_derived_path(src, dst, &derived_media.path)?,
+            };
+
+            let cache_storage = self.cache_storage.lock().await;
+            if cache_storage.size(derived_media.path.as_str()).await?.is_some() {
+                match relocation {
+                    Relocation::Move => {
+                        cache_storage
+                            .move_(derived_media.path.as_str(), derived_path.as_str())
+                            .await?
+                    }
+                    Relocation::Copy => {
+                        cache_storage
+                            .copy(derived_media.path.as_str(), derived_path.as_str())
+                            .await?
+                    }
+                }
+            }
+
+            derived_media.path = derived_path;
             new_derived_medias.push(derived_media);
         }
 
         metadata.derived_medias = new_derived_medias;
 
-        self.file_storage
-            .lock()
-            .await
-            .copy(src.as_str(), dst.as_str())
-            .await?;
-        metadata.path = dst.clone().into();
+        match relocation {
+            Relocation::Move => {
+                self.file_storage
+                    .lock()
+                    .await
+                    .move_(src.as_str(), dst.as_str())
+                    .await?
+            }
+            Relocation::Copy => {
+                self.file_storage
+                    .lock()
+                    .await
+                    .copy(src.as_str(), dst.as_str())
+                    .await?
+            }
+        }
+
+        metadata.path = dst.clone();
+        metadata.updated_at = Some(Utc::now());
 
         self.metadata_storage
             .lock()
             .await
             .save(dst.as_str(), metadata.clone())?;
 
-        Ok(())
+        Ok(metadata)
     }
 
     pub async fn delete(&self, path: Path) -> Result<(), Box<dyn Error>> {
-        self.file_storage.lock().await.delete(path.as_str()).await?;
-        self.cache_storage
+        let metadata = self
+            .metadata_storage
             .lock()
             .await
-            .delete(path.as_str())
-            .await?;
+            .get_by_path(path.as_str())?;
+
+        if let Some(metadata) = &metadata {
+            let cache_storage = self.cache_storage.lock().await;
+            for derived_media in &metadata.derived_medias {
+                if cache_storage.size(derived_media.path.as_str()).await?.is_some() {
+                    cache_storage.delete(derived_media.path.as_str()).await?;
+                }
+            }
+        }
+
+        self.file_storage.lock().await.delete(path.as_str()).await?;
         self.metadata_storage.lock().await.delete(path.as_str())?;
 
+        if let Some(metadata) = metadata {
+            if let (Some(usage), Some(owner)) = (&self.usage, metadata.owner.as_deref()) {
+                usage.record(owner, UsageMetric::BytesStored, -(metadata.content_length as i64));
+            }
+        }
+
+        self.notify(WebhookEvent::MediaDeleted, json!({ "path": path.as_str() }));
+
         Ok(())
     }
+
+    async fn bulk_targets(
+        &self,
+        paths: Vec<String>,
+        prefix: Option<&str>,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let prefix = match prefix {
+            Some(prefix) if paths.is_empty() => prefix,
+            _ => return Ok(paths),
+        };
+
+        // Collect every path before touching anything so moves cannot shift the listing.
+        let mut targets = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = self
+                .list_folder(prefix, cursor.as_deref(), BULK_LIST_LIMIT, true)
+                .await?;
+
+            targets.extend(page.media.into_iter().map(|metadata| metadata.path.as_str().to_string()));
+
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        Ok(targets)
+    }
+
+    async fn run_bulk_item(
+        &self,
+        operation: BulkOperation,
+        path: &str,
+        prefix: Option<&str>,
+        destination: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        let src = Path::new(path)?;
+
+        if operation == BulkOperation::Delete {
+            return self.delete(src).await;
+        }
+
+        let destination = destination
+            .ok_or_else(|| MindiaError::validation("A destination is required to move or copy media"))?
+            .trim_end_matches('/');
+
+        // Media selected by prefix keep their position relative to it.
+        let relative_path = match prefix {
+            Some(prefix) if path.starts_with(prefix) => &path[prefix.trim_end_matches('/').len()..],
+            _ => &path[path.rfind('/').unwrap_or(0)..],
+        };
+        let dst = Path::new(&format!("{}{}", destination, relative_path))?;
+
+        match operation {
+            BulkOperation::Move => self.move_(src, dst).await.map(|_| ()),
+            _ => self.copy(src, dst).await.map(|_| ()),
+        }
+    }
+}
+
+#[async_trait]
+impl TaskExecutor for MediaHandler {
+    async fn run(&self, mut task: Task) -> Result<Task, Box<dyn Error>> {
+        let (operation, paths, prefix, destination) = match &task.details {
+            Details::BulkMedia {
+                operation,
+                paths,
+                prefix,
+                destination,
+                ..
+            } => (*operation, paths.clone(), prefix.clone(), destination.clone()),
+            _ => return Err("Unexpected task details for a bulk media task".into()),
+        };
+
+        let targets = self.bulk_targets(paths, prefix.as_deref()).await?;
+        task.progress.total = Some(targets.len() as u64);
+
+        let mut item_results = Vec::with_capacity(targets.len());
+        for path in targets {
+            let result = self
+                .run_bulk_item(operation, &path, prefix.as_deref(), destination.as_deref())
+                .await
+                .map_err(|e| e.to_string());
+
+            item_results.push(BulkItemResult {
+                path,
+                success: result.is_ok(),
+                error: result.err(),
+            });
+        }
+
+        task.progress.processed = item_results.len() as u64;
+        if let Details::BulkMedia { results, .. } = &mut task.details {
+            *results = item_results;
+        }
+        task.set_status(TaskStatus::Completed);
+
+        Ok(task)
+    }
+}
+
+enum Relocation {
+    Move,
+    Copy,
+}
+
+/// Moves a derived media path recorded before transformation chains were stored in
+/// the metadata next to `dst`, keeping the suffix generated from its chain.
+fn rebase_derived_path(src: &Path, dst: &Path, derived_path: &Path) -> Result<Path, Box<dyn Error>> {
+    let src_prefix = format!("{}/{}-", src.folder(), src.basename());
+    let suffix = derived_path
+        .as_str()
+        .strip_prefix(src_prefix.as_str())
+        .ok_or("Derived media does not belong to the source media")?;
+    let (suffix, extension) = suffix.rsplit_once('.').unwrap_or((suffix, ""));
+
+    // Built like `PathGenerator` does, as derived basenames are not UUIDs.
+    let mut path = dst.clone();
+    path.set_extension(extension);
+    path.add_suffix_to_filename(suffix)
+}
+
+fn fallback_metadata(path: Path, size: u64) -> Result<Metadata, Box<dyn Error>> {
+    let mut metadata = ContentInfoExtractor::default().extract(Metadata::new(path), BytesMut::new())?;
+    metadata.content_length = size as usize;
+    metadata.content_hash = None;
+
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_derived_path() {
+        let src = Path::new("/a/bbd2fa99-f35e-4062-92eb-9d26caa943ae.jpg").unwrap();
+        let dst = Path::new("/b/0e5f8f5c-4b1e-4a53-9c1a-7c2d0f6f6a11.jpg").unwrap();
+
+        let mut derived_path = src.add_suffix_to_filename("w100").unwrap();
+        derived_path.set_extension("webp");
+        assert_eq!(
+            derived_path.as_str(),
+            "/a/bbd2fa99-f35e-4062-92eb-9d26caa943ae-w100.webp"
+        );
+
+        let rebased = rebase_derived_path(&src, &dst, &derived_path).unwrap();
+        assert_eq!(
+            rebased.as_str(),
+            "/b/0e5f8f5c-4b1e-4a53-9c1a-7c2d0f6f6a11-w100.webp"
+        );
+
+        let other = Path::new("/c/bbd2fa99-f35e-4062-92eb-9d26caa943ae.jpg").unwrap();
+        assert!(rebase_derived_path(&other, &dst, &derived_path).is_err());
+    }
 }
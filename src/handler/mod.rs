@@ -1,7 +1,14 @@
 pub mod cache_handler;
 pub mod media_handler;
+pub mod project_registry;
+pub mod resumable_upload_handler;
+pub mod webhook_handler;
 mod upload;
 
 pub use cache_handler::CacheHandler;
 pub use media_handler::MediaHandler;
-use upload::{PipelineStepsFactory, UploadMediaContext};
+pub use project_registry::{ProjectContext, ProjectRegistry};
+pub use resumable_upload_handler::ResumableUploadHandler;
+pub use webhook_handler::WebhookHandler;
+pub use upload::PipelineStepsFactory;
+use upload::UploadMediaContext;
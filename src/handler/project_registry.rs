@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::Duration;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::{
+    CacheHandler, MediaHandler, PipelineStepsFactory, ResumableUploadHandler, WebhookHandler,
+};
+use crate::config::{UploadConfig, WebhookConfig};
+use crate::metadata::MetadataStorage;
+use crate::project::{Project, ProjectStorage, DEFAULT_PROJECT};
+use crate::resumable::ResumableUploadStorage;
+use crate::scheduler::{Task, TaskExecutor, TaskKind, TaskStatus, TaskStorage};
+use crate::storage::{FileStorage, PrefixedFileStorage};
+use crate::transform::NamedTransformationStorage;
+use crate::usage::UsageTracker;
+use crate::webhook::{WebhookDispatcher, WebhookEvent, WebhookStorage};
+
+/// The handlers of one project, working on its part of the shared storages.
+pub struct ProjectContext {
+    pub name: String,
+    pub media_handler: MediaHandler,
+    pub cache_handler: CacheHandler,
+    pub resumable_upload_handler: ResumableUploadHandler,
+    pub named_transformation_storage: Arc<dyn NamedTransformationStorage>,
+    pub webhook_storage: Option<Arc<dyn WebhookStorage>>,
+    webhook_handler: Option<WebhookHandler>,
+    webhook_dispatcher: Option<WebhookDispatcher>,
+}
+
+impl ProjectContext {
+    pub fn url_prefix(&self) -> String {
+        Project::url_prefix(&self.name)
+    }
+
+    fn notify(&self, event: WebhookEvent, task: &Task) {
+        if let Some(webhook_dispatcher) = &self.webhook_dispatcher {
+            webhook_dispatcher.dispatch(event, json!({ "task": task }));
+        }
+    }
+}
+
+/// What the projects need to deliver webhooks.
+struct WebhookSettings {
+    webhook_storage: Arc<dyn WebhookStorage>,
+    task_storage: Arc<dyn TaskStorage>,
+    config: WebhookConfig,
+}
+
+/// Builds the context of each project over the storages of the default one
+/// and keeps it for the next requests.
+pub struct ProjectRegistry {
+    project_storage: Arc<dyn ProjectStorage>,
+    file_storage: Arc<Mutex<dyn FileStorage>>,
+    cache_storage: Arc<Mutex<dyn FileStorage>>,
+    metadata_storage: Arc<Mutex<dyn MetadataStorage>>,
+    named_transformation_storage: Arc<dyn NamedTransformationStorage>,
+    resumable_upload_storage: Arc<dyn ResumableUploadStorage>,
+    usage_tracker: UsageTracker,
+    upload_config: UploadConfig,
+    webhooks: Option<WebhookSettings>,
+    contexts: RwLock<HashMap<String, Arc<ProjectContext>>>,
+}
+
+impl ProjectRegistry {
+    pub fn new(
+        project_storage: Arc<dyn ProjectStorage>,
+        file_storage: Arc<Mutex<dyn FileStorage>>,
+        cache_storage: Arc<Mutex<dyn FileStorage>>,
+        metadata_storage: Arc<Mutex<dyn MetadataStorage>>,
+        named_transformation_storage: Arc<dyn NamedTransformationStorage>,
+        resumable_upload_storage: Arc<dyn ResumableUploadStorage>,
+        usage_tracker: UsageTracker,
+        upload_config: UploadConfig,
+    ) -> Self {
+        Self {
+            project_storage,
+            file_storage,
+            cache_storage,
+            metadata_storage,
+            named_transformation_storage,
+            resumable_upload_storage,
+            usage_tracker,
+            upload_config,
+            webhooks: None,
+            contexts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Delivers the events of each project to its webhooks through tasks pushed
+    /// to `task_storage`.
+    pub fn with_webhooks(
+        self,
+        webhook_storage: Arc<dyn WebhookStorage>,
+        task_storage: Arc<dyn TaskStorage>,
+        config: WebhookConfig,
+    ) -> Self {
+        Self {
+            webhooks: Some(WebhookSettings {
+                webhook_storage,
+                task_storage,
+                config,
+            }),
+            ..self
+        }
+    }
+
+    pub fn storage(&self) -> &Arc<dyn ProjectStorage> {
+        &self.project_storage
+    }
+
+    /// Returns the context of `name`, or `None` if no such project exists. The
+    /// default project always exists.
+    pub async fn get(&self, name: &str) -> Result<Option<Arc<ProjectContext>>, Box<dyn Error>> {
+        if name != DEFAULT_PROJECT && self.project_storage.get_by_name(name)?.is_none() {
+            // Deleted by another instance since it was cached.
+            self.contexts.write().unwrap().remove(name);
+            return Ok(None);
+        }
+
+        if let Some(context) = self.contexts.read().unwrap().get(name) {
+            return Ok(Some(context.clone()));
+        }
+
+        let context = Arc::new(self.build(name).await?);
+        self.contexts
+            .write()
+            .unwrap()
+            .insert(name.to_string(), context.clone());
+
+        Ok(Some(context))
+    }
+
+    /// Deletes the project. Its media stay in the storages.
+    pub fn delete(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        self.project_storage.delete(name)?;
+        self.contexts.write().unwrap().remove(name);
+
+        Ok(())
+    }
+
+    async fn build(&self, name: &str) -> Result<ProjectContext, Box<dyn Error>> {
+        let (file_storage, cache_storage, metadata_storage, named_transformation_storage, resumable_upload_storage) =
+            if name == DEFAULT_PROJECT {
+                (
+                    self.file_storage.clone(),
+                    self.cache_storage.clone(),
+                    self.metadata_storage.clone(),
+                    self.named_transformation_storage.clone(),
+                    self.resumable_upload_storage.clone(),
+                )
+            } else {
+                let file_storage: Arc<Mutex<dyn FileStorage>> = Arc::new(Mutex::new(
+                    PrefixedFileStorage::new(self.file_storage.clone(), Project::storage_prefix(name)),
+                ));
+                let cache_storage: Arc<Mutex<dyn FileStorage>> = Arc::new(Mutex::new(
+                    PrefixedFileStorage::new(self.cache_storage.clone(), Project::storage_prefix(name)),
+                ));
+
+                (
+                    file_storage,
+                    cache_storage,
+                    self.metadata_storage.lock().await.for_project(name)?,
+                    self.named_transformation_storage.for_project(name)?,
+                    self.resumable_upload_storage.for_project(name),
+                )
+            };
+
+        let mut media_handler = MediaHandler::new(
+            file_storage.clone(),
+            cache_storage.clone(),
+            metadata_storage.clone(),
+            PipelineStepsFactory::new(file_storage.clone()),
+        )
+        .with_usage(self.usage_tracker.clone());
+
+        let (webhook_storage, webhook_handler, webhook_dispatcher) = match &self.webhooks {
+            Some(webhooks) => {
+                let webhook_storage = if name == DEFAULT_PROJECT {
+                    webhooks.webhook_storage.clone()
+                } else {
+                    webhooks.webhook_storage.for_project(name)?
+                };
+                let webhook_dispatcher = WebhookDispatcher::new(
+                    name.to_string(),
+                    webhook_storage.clone(),
+                    webhooks.task_storage.clone(),
+                );
+                media_handler = media_handler.with_webhooks(webhook_dispatcher.clone());
+
+                (
+                    Some(webhook_storage.clone()),
+                    Some(WebhookHandler::new(webhook_storage, webhooks.config.clone())),
+                    Some(webhook_dispatcher),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        Ok(ProjectContext {
+            name: name.to_string(),
+            media_handler: media_handler.clone(),
+            cache_handler: CacheHandler::new(cache_storage, metadata_storage),
+            resumable_upload_handler: ResumableUploadHandler::new(
+                resumable_upload_storage,
+                file_storage,
+                media_handler,
+                self.upload_config.tmp_dir.as_deref(),
+                Duration::seconds(self.upload_config.resumable_expiration),
+            ),
+            named_transformation_storage,
+            webhook_storage,
+            webhook_handler,
+            webhook_dispatcher,
+        })
+    }
+}
+
+/// Runs each task with the handlers of the project it was pushed for.
+#[async_trait]
+impl TaskExecutor for ProjectRegistry {
+    async fn run(&self, task: Task) -> Result<Task, Box<dyn Error>> {
+        let context = self
+            .get(&task.project)
+            .await?
+            .ok_or_else(|| format!("Project {} not found", task.project))?;
+
+        let result = match task.kind {
+            TaskKind::ClearCache => context.cache_handler.run(task).await?,
+            TaskKind::BulkMedia => context.media_handler.run(task).await?,
+            TaskKind::WebhookDelivery => {
+                return match &context.webhook_handler {
+                    Some(webhook_handler) => webhook_handler.run(task).await,
+                    None => Err("Webhooks are not enabled".into()),
+                };
+            }
+        };
+
+        if result.status == TaskStatus::Completed {
+            context.notify(WebhookEvent::TaskCompleted, &result);
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,260 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use chrono::{Duration, Utc};
+use futures_util::{Stream, StreamExt};
+use log::error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use super::MediaHandler;
+use crate::error::MindiaError;
+use crate::media::{Path, TempFile};
+use crate::metadata::Metadata;
+use crate::resumable::{ResumableUpload, ResumableUploadStorage};
+use crate::storage::FileStorage;
+use crate::transform::TransformationDescriptorChain;
+
+/// Folder of the file storage holding the parts of the uploads in progress.
+const PARTS_FOLDER: &str = "/_resumable";
+/// Seconds a request holds an upload. A lock left by a stopped instance is
+/// released after it.
+const LOCK_LEASE: i64 = 15 * 60;
+
+/// Keeps tus uploads between requests and hands the assembled file to the
+/// `MediaHandler` once every byte has been received. The parts received are kept
+/// in the file storage and the lock in the upload storage, so that any instance
+/// can serve the next request.
+#[derive(Clone)]
+pub struct ResumableUploadHandler {
+    upload_storage: Arc<dyn ResumableUploadStorage>,
+    file_storage: Arc<Mutex<dyn FileStorage>>,
+    media_handler: MediaHandler,
+    tmp_dir: Option<String>,
+    expiration: Duration,
+}
+
+/// Exclusive access to an upload while a request writes to it.
+pub struct ResumableUploadLock {
+    id: String,
+    upload_storage: Arc<dyn ResumableUploadStorage>,
+}
+
+impl Drop for ResumableUploadLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.upload_storage.unlock(&self.id) {
+            error!("Error: {}", e);
+        }
+    }
+}
+
+impl ResumableUploadHandler {
+    pub fn new(
+        upload_storage: Arc<dyn ResumableUploadStorage>,
+        file_storage: Arc<Mutex<dyn FileStorage>>,
+        media_handler: MediaHandler,
+        tmp_dir: Option<&str>,
+        expiration: Duration,
+    ) -> Self {
+        Self {
+            upload_storage,
+            file_storage,
+            media_handler,
+            tmp_dir: tmp_dir.map(str::to_string),
+            expiration,
+        }
+    }
+
+    pub async fn create(
+        &self,
+        length: u64,
+        folder: String,
+        filename: Option<String>,
+        transformations: Vec<String>,
+        owner: Option<String>,
+    ) -> Result<ResumableUpload, Box<dyn Error>> {
+        // Refuse before the client sends anything rather than once it is done.
+        self.media_handler.check_upload_quota(
+            owner.as_deref(),
+            length,
+            transformations.len() as u64,
+        )?;
+
+        self.remove_expired_uploads().await?;
+
+        let upload = ResumableUpload::new(
+            length,
+            folder,
+            filename,
+            transformations,
+            owner,
+            Utc::now() + self.expiration,
+        );
+
+        self.upload_storage.save(&upload)?;
+
+        Ok(upload)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<ResumableUpload>, Box<dyn Error>> {
+        let upload = self.upload_storage.get(id)?;
+
+        match upload {
+            Some(upload) if upload.is_expired() => {
+                self.terminate(&upload).await?;
+                Ok(None)
+            }
+            upload => Ok(upload),
+        }
+    }
+
+    pub fn lock(&self, id: &str) -> Result<Option<ResumableUploadLock>, Box<dyn Error>> {
+        if !self.upload_storage.lock(id, LOCK_LEASE)? {
+            return Ok(None);
+        }
+
+        Ok(Some(ResumableUploadLock {
+            id: id.to_string(),
+            upload_storage: self.upload_storage.clone(),
+        }))
+    }
+
+    /// Stores the request body as the part starting at the current offset. Bytes
+    /// received before the body fails are kept so the client can resume from them.
+    pub async fn append<S, E>(
+        &self,
+        upload: &mut ResumableUpload,
+        mut body: S,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        // Spooled to disk, the part is only read back to be stored.
+        let part = TempFile::new(self.tmp_dir.as_deref())?;
+        let mut file = tokio::fs::File::create(part.path()).await?;
+        let mut received: u64 = 0;
+
+        let result: Result<(), Box<dyn Error + Send + Sync>> = loop {
+            let chunk = match body.next().await {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(e)) => break Err(e.into()),
+                None => break Ok(()),
+            };
+
+            if upload.offset + received + chunk.len() as u64 > upload.length {
+                break Err(Box::new(MindiaError::payload_too_large(
+                    "Request body exceeds the upload length",
+                )));
+            }
+
+            if let Err(e) = file.write_all(&chunk).await {
+                break Err(e.into());
+            }
+            received += chunk.len() as u64;
+        };
+
+        file.flush().await?;
+
+        if received > 0 {
+            let data = read_file(&part).await?;
+            self.file_storage
+                .lock()
+                .await
+                .upload(&part_path(&upload.id, upload.offset), data.freeze())
+                .await?;
+
+            upload.parts.push(upload.offset);
+            upload.offset += received;
+            self.upload_storage.save(upload)?;
+        }
+
+        result.map_err(|e| e as Box<dyn Error>)
+    }
+
+    /// Runs the assembled file through the regular upload pipeline.
+    pub async fn complete(
+        &self,
+        upload: &mut ResumableUpload,
+        path: Path,
+        transformation_chains: Vec<TransformationDescriptorChain>,
+    ) -> Result<Metadata, Box<dyn Error>> {
+        let assembled = TempFile::new(self.tmp_dir.as_deref())?;
+        let mut file = tokio::fs::File::create(assembled.path()).await?;
+
+        for offset in &upload.parts {
+            let part_path = part_path(&upload.id, *offset);
+            let data = self
+                .file_storage
+                .lock()
+                .await
+                .download(&part_path)
+                .await?
+                .ok_or_else(|| format!("Part {} of the upload is missing", part_path))?;
+
+            file.write_all(&data).await?;
+        }
+
+        file.flush().await?;
+
+        let metadata = self
+            .media_handler
+            .upload(
+                path,
+                upload.filename.clone(),
+                transformation_chains,
+                &assembled,
+                upload.owner.clone(),
+            )
+            .await?;
+
+        self.delete_parts(upload).await;
+
+        upload.media_path = Some(metadata.path.as_str().to_string());
+        upload.parts.clear();
+        self.upload_storage.save(upload)?;
+
+        Ok(metadata)
+    }
+
+    pub async fn terminate(&self, upload: &ResumableUpload) -> Result<(), Box<dyn Error>> {
+        self.delete_parts(upload).await;
+
+        self.upload_storage.delete(&upload.id)
+    }
+
+    async fn delete_parts(&self, upload: &ResumableUpload) {
+        let file_storage = self.file_storage.lock().await;
+
+        for offset in &upload.parts {
+            if let Err(e) = file_storage.delete(&part_path(&upload.id, *offset)).await {
+                error!("Error: {}", e);
+            }
+        }
+    }
+
+    async fn remove_expired_uploads(&self) -> Result<(), Box<dyn Error>> {
+        let expired = self.upload_storage.take_expired(Utc::now())?;
+
+        for upload in expired {
+            self.delete_parts(&upload).await;
+        }
+
+        Ok(())
+    }
+}
+
+fn part_path(id: &str, offset: u64) -> String {
+    format!("{}/{}/{}.part", PARTS_FOLDER, id, offset)
+}
+
+async fn read_file(file: &TempFile) -> Result<BytesMut, Box<dyn Error>> {
+    let mut file = tokio::fs::File::open(file.path()).await?;
+    let size = file.metadata().await?.len();
+
+    let mut data = BytesMut::with_capacity(size as usize);
+    while file.read_buf(&mut data).await? > 0 {}
+
+    Ok(data)
+}
@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 use crate::handler::upload::PipelineStepFactory;
 use crate::handler::UploadMediaContext;
 use crate::pipeline::PipelineStep;
@@ -7,12 +8,12 @@ use crate::storage::FileStorage;
 use crate::transform::{Colorizer, TransformationDescriptor};
 
 pub struct ColorizerFactory {
-    file_storage: Arc<dyn FileStorage>,
+    file_storage: Arc<Mutex<dyn FileStorage>>,
 }
 
 impl ColorizerFactory {
     pub fn new(
-        file_storage: Arc<dyn FileStorage>,
+        file_storage: Arc<Mutex<dyn FileStorage>>,
     ) -> Self {
         Self {
             file_storage,
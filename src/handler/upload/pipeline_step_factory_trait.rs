@@ -4,7 +4,7 @@ use super::UploadMediaContext;
 use crate::pipeline::PipelineStep;
 use crate::transform::TransformationDescriptor;
 
-pub trait PipelineStepFactory {
+pub trait PipelineStepFactory: Send + Sync {
     fn create(
         &self,
         transformation_descriptor: TransformationDescriptor,
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::sync::{Arc, Mutex};
+use tokio::sync::Mutex as AsyncMutex;
 use crate::handler::upload::colorizer_factory::ColorizerFactory;
 use crate::handler::upload::watermarker_factory::WatermarkerFactory;
 
 use super::{PipelineStepFactory, ScalerFactory, UploadMediaContext};
+use crate::error::MindiaError;
 use crate::pipeline::PipelineStep;
 use crate::storage::FileStorage;
 use crate::transform::{TransformationDescriptorChain, TransformationName};
@@ -16,7 +18,7 @@ pub struct PipelineStepsFactory {
 
 impl PipelineStepsFactory {
     pub fn new(
-        file_storage: Arc<dyn FileStorage>,
+        file_storage: Arc<AsyncMutex<dyn FileStorage>>,
     ) -> Self {
         let factories: Arc<Mutex<HashMap<TransformationName, Box<dyn PipelineStepFactory>>>> =
             Arc::new(Mutex::new(HashMap::new()));
@@ -60,8 +62,9 @@ impl PipelineStepsFactory {
                 .lock()
                 .unwrap()
                 .get(transformation_descriptor.name())
-                .ok_or("Factory not found")?
-                .create(transformation_descriptor.clone())?;
+                .ok_or_else(|| MindiaError::validation("Unsupported transformation"))?
+                .create(transformation_descriptor.clone())
+                .map_err(|e| MindiaError::validation(e.to_string()))?;
 
             pipeline_steps.push(pipeline_step);
         }
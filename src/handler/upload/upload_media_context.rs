@@ -1,14 +1,18 @@
 use std::error::Error;
+use std::path::PathBuf;
 use async_trait::async_trait;
 use crate::extractor::{ContentInfoExtractor, ExifExtractor};
 use crate::media::MediaHandle;
 use crate::pipeline::PipelineStep;
-use crate::transform::{PathGenerator, Scaler, TransformationDescriptorChain, Watermarker, WebpConverter};
+use crate::transform::{FormatConverter, PathGenerator, Scaler, TransformationDescriptorChain, Watermarker};
 
 #[derive(Default, Clone)]
 pub struct UploadMediaContext {
     pub media_handle: MediaHandle,
     pub transformations: TransformationDescriptorChain,
+    /// The uploaded file, read from disk in place of the body until the format
+    /// conversion fills the body with the encoded image.
+    pub source: Option<PathBuf>,
 }
 
 #[async_trait]
@@ -17,10 +21,10 @@ impl PipelineStep<UploadMediaContext> for ExifExtractor {
         &self,
         mut ctx: UploadMediaContext,
     ) -> Result<UploadMediaContext, Box<dyn Error>> {
-        ctx.media_handle.metadata = self.extract(
-            ctx.media_handle.metadata,
-            ctx.media_handle.body.clone(),
-        )?;
+        ctx.media_handle.metadata = match &ctx.source {
+            Some(source) => self.extract_file(ctx.media_handle.metadata, source)?,
+            None => self.extract(ctx.media_handle.metadata, ctx.media_handle.body.clone())?,
+        };
 
         Ok(ctx)
     }
@@ -42,18 +46,23 @@ impl PipelineStep<UploadMediaContext> for ContentInfoExtractor {
 }
 
 #[async_trait]
-impl PipelineStep<UploadMediaContext> for WebpConverter {
+impl PipelineStep<UploadMediaContext> for FormatConverter {
     async fn execute(
         &self,
         mut ctx: UploadMediaContext,
     ) -> Result<UploadMediaContext, Box<dyn Error>> {
-        let (path, body) = self.transform(
-            ctx.media_handle.metadata.path,
-            ctx.media_handle.body.clone(),
-        )?;
+        let (path, body, has_alpha) = match ctx.source.take() {
+            Some(source) => self.transform_file(ctx.media_handle.metadata.path, &source)?,
+            None => self.transform(
+                ctx.media_handle.metadata.path,
+                ctx.media_handle.body.clone(),
+            )?,
+        };
 
         ctx.media_handle.metadata.path = path;
-        ctx.media_handle.metadata.content_type = Some("image/webp".to_string());
+        ctx.media_handle.metadata.content_type =
+            Some(self.output_format().content_type().to_string());
+        ctx.media_handle.metadata.has_alpha = Some(has_alpha);
         ctx.media_handle.body = body;
 
         Ok(ctx)
@@ -67,8 +76,8 @@ impl PipelineStep<UploadMediaContext> for Scaler {
         mut ctx: UploadMediaContext,
     ) -> Result<UploadMediaContext, Box<dyn Error>> {
         let body = self.transform(
-            ctx.media_handle.metadata.path.clone(),
             ctx.media_handle.body.clone(),
+            ctx.transformations.output_format(),
         )?;
 
         ctx.media_handle.body = body;
@@ -83,7 +92,12 @@ impl PipelineStep<UploadMediaContext> for Watermarker {
         &self,
         mut ctx: UploadMediaContext,
     ) -> Result<UploadMediaContext, Box<dyn Error>> {
-        let body = self.transform(ctx.media_handle.body.clone()).await?;
+        let body = self
+            .transform(
+                ctx.media_handle.body.clone(),
+                ctx.transformations.output_format(),
+            )
+            .await?;
 
         ctx.media_handle.body = body;
 
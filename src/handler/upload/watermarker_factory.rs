@@ -1,6 +1,7 @@
 use std::error::Error;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 use crate::handler::upload::pipeline_step_factory_trait::PipelineStepFactory;
 use crate::handler::upload::UploadMediaContext;
 use crate::media::Path;
@@ -11,11 +12,11 @@ use crate::transform::watermarker::Anchor;
 use crate::types::Size;
 
 pub struct WatermarkerFactory {
-    pub file_storage: Arc<dyn FileStorage>,
+    pub file_storage: Arc<Mutex<dyn FileStorage>>,
 }
 
 impl WatermarkerFactory {
-    pub fn new(file_storage: Arc<dyn FileStorage>) -> Self {
+    pub fn new(file_storage: Arc<Mutex<dyn FileStorage>>) -> Self {
         Self { file_storage }
     }
 }
@@ -0,0 +1,156 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use log::error;
+
+use crate::config::WebhookConfig;
+use crate::scheduler::{Details, Task, TaskExecutor, TaskStatus};
+use crate::webhook::{resolve_webhook_url, sign_payload, DeliveryAttempt, WebhookStorage};
+
+/// Posts the payloads of the webhook delivery tasks of a project.
+#[derive(Clone)]
+pub struct WebhookHandler {
+    webhook_storage: Arc<dyn WebhookStorage>,
+    config: WebhookConfig,
+}
+
+impl WebhookHandler {
+    pub fn new(webhook_storage: Arc<dyn WebhookStorage>, config: WebhookConfig) -> Self {
+        Self {
+            webhook_storage,
+            config,
+        }
+    }
+
+    /// A client bound to the address the host resolved to when it was checked, so
+    /// that a second resolution cannot point the delivery elsewhere. Redirects are
+    /// not followed for the same reason.
+    async fn client(&self, url: &str) -> Result<reqwest::Client, String> {
+        let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+        let address = resolve_webhook_url(&url, &self.config.allowed_hosts).await?;
+
+        let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        if let Some(host) = url.host_str() {
+            builder = builder.resolve(host, address);
+        }
+
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    /// Returns the status code answered, or why the webhook could not be reached.
+    async fn post(
+        &self,
+        url: &str,
+        secret: &str,
+        event: &str,
+        delivery_id: &str,
+        payload: &str,
+    ) -> (Option<u16>, Option<String>) {
+        let client = match self.client(url).await {
+            Ok(client) => client,
+            Err(e) => return (None, Some(e)),
+        };
+
+        let timestamp = Utc::now().timestamp();
+        let signature = sign_payload(secret, timestamp, payload);
+
+        let result = client
+            .post(url)
+            .timeout(StdDuration::from_secs(self.config.timeout))
+            .header("Content-Type", "application/json")
+            .header("Mindia-Event", event)
+            .header("Mindia-Delivery", delivery_id)
+            .header(
+                "Mindia-Signature",
+                format!("t={},v1={}", timestamp, signature),
+            )
+            .body(payload.to_string())
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None)
+            }
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                Some(format!("Webhook answered {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl TaskExecutor for WebhookHandler {
+    async fn run(&self, mut task: Task) -> Result<Task, Box<dyn Error>> {
+        let (webhook, delivery_id, event, payload, attempt) = match &mut task.details {
+            Details::WebhookDelivery {
+                webhook,
+                delivery_id,
+                event,
+                payload,
+                attempt,
+            } => (webhook, delivery_id, event, payload, attempt),
+            _ => return Err("Unexpected task details".into()),
+        };
+
+        let target = match self.webhook_storage.get_by_name(webhook)? {
+            Some(target) => target,
+            None => {
+                task.error = Some(format!("Webhook {} not found", webhook));
+                task.set_status(TaskStatus::Completed);
+                return Ok(task);
+            }
+        };
+
+        let (status_code, delivery_error) = self
+            .post(
+                &target.url,
+                &target.secret,
+                event.as_str(),
+                delivery_id,
+                payload,
+            )
+            .await;
+        *attempt += 1;
+
+        let logged = DeliveryAttempt {
+            delivery_id: delivery_id.clone(),
+            event: *event,
+            attempt: *attempt,
+            attempted_at: Utc::now(),
+            status_code,
+            error: delivery_error.clone(),
+            success: delivery_error.is_none(),
+        };
+        if let Err(e) =
+            self.webhook_storage
+                .log_attempt(webhook, &logged, self.config.delivery_log_size)
+        {
+            error!("Error: {}", e);
+        }
+
+        match delivery_error {
+            Some(e) if *attempt < self.config.max_attempts => {
+                // Delayed in the queue until the next attempt.
+                task.run_at = Some(Utc::now() + Duration::seconds(self.config.backoff(*attempt)));
+                task.error = Some(e);
+                task.set_status(TaskStatus::Queued);
+            }
+            Some(e) => {
+                task.error = Some(e);
+                task.set_status(TaskStatus::Failed);
+            }
+            None => {
+                task.error = None;
+                task.set_status(TaskStatus::Completed);
+            }
+        }
+
+        Ok(task)
+    }
+}
@@ -11,28 +11,47 @@ use tokio::sync::Mutex;
 
 use crate::adapter::S3;
 use crate::api::server::run_server;
-use crate::apikey::{ApiKeyStorage, RedisApiKeyStorage};
+use crate::apikey::{ApiKeyStorage, MemoryApiKeyStorage, RedisApiKeyStorage};
 use crate::config::{ConfigLoader, StorageKind};
-use crate::metadata::{MetadataStorage, RedisMetadataStorage};
+use crate::handler::ProjectRegistry;
+use crate::metadata::{MemoryMetadataStorage, MetadataStorage, RedisMetadataStorage};
+use crate::project::{MemoryProjectStorage, ProjectStorage, RedisProjectStorage};
+use crate::ratelimit::{MemoryRateLimiter, RateLimiter, RedisRateLimiter};
+use crate::resumable::{
+    MemoryResumableUploadStorage, RedisResumableUploadStorage, ResumableUploadStorage,
+};
 use crate::scheduler::task_scheduler::run_scheduler;
-use crate::scheduler::{RedisTaskStorage, TaskExecutor, TaskStorage};
-use crate::storage::{FileStorage, FilesystemStorage, S3Storage};
-use crate::transform::{NamedTransformationStorage, RedisNamedTransformationStorage};
+use crate::scheduler::{
+    MemoryScheduleStorage, MemoryTaskStorage, RedisScheduleStorage, RedisTaskStorage,
+    ScheduleStorage, TaskExecutor, TaskStorage,
+};
+use crate::storage::{FileStorage, FilesystemStorage, MemoryStorage, S3Storage};
+use crate::transform::{
+    MemoryNamedTransformationStorage, NamedTransformationStorage, RedisNamedTransformationStorage,
+};
+use crate::usage::{MemoryUsageStorage, RedisUsageStorage, UsageStorage, UsageTracker};
+use crate::webhook::{MemoryWebhookStorage, RedisWebhookStorage, WebhookStorage};
 
 mod adapter;
 mod api;
 mod apikey;
 mod config;
+mod error;
 mod extractor;
 mod handler;
 mod media;
 mod metadata;
 mod pipeline;
+mod project;
+mod ratelimit;
+mod resumable;
 mod scheduler;
 mod storage;
 mod transform;
 mod types;
+mod usage;
 mod utils;
+mod webhook;
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
@@ -76,16 +95,11 @@ async fn main() -> std::io::Result<()> {
 
     let apikey_storage: Arc<dyn ApiKeyStorage> = match config.apikey.storage_kind {
         StorageKind::Filesystem => panic!("Filesystem storage for apikeys is not supported yet"),
-        StorageKind::Redis => {
-            let redis_conn = redis_client
-                .as_ref()
-                .unwrap()
-                .get_connection()
-                .expect("Error connecting to Redis");
-            Arc::new(
-                RedisApiKeyStorage::new(redis_conn).expect("Error creating RedisApiKeyStorage"),
-            )
-        }
+        StorageKind::Memory => Arc::new(MemoryApiKeyStorage::new()),
+        StorageKind::Redis => Arc::new(
+            RedisApiKeyStorage::new(redis_connection(&redis_client))
+                .expect("Error creating RedisApiKeyStorage"),
+        ),
         StorageKind::S3 => panic!("S3 storage for apikeys is not supported yet"),
     };
 
@@ -94,17 +108,11 @@ async fn main() -> std::io::Result<()> {
             StorageKind::Filesystem => {
                 panic!("Filesystem storage for named transformations is not supported yet")
             }
-            StorageKind::Redis => {
-                let redis_conn = redis_client
-                    .as_ref()
-                    .unwrap()
-                    .get_connection()
-                    .expect("Error connecting to Redis");
-                Arc::new(
-                    RedisNamedTransformationStorage::new(redis_conn)
-                        .expect("Error creating RedisNamedTransformationStorage"),
-                )
-            }
+            StorageKind::Memory => Arc::new(MemoryNamedTransformationStorage::new()),
+            StorageKind::Redis => Arc::new(
+                RedisNamedTransformationStorage::new(redis_connection(&redis_client))
+                    .expect("Error creating RedisNamedTransformationStorage"),
+            ),
             StorageKind::S3 => panic!("S3 storage for named transformations is not supported yet"),
         };
 
@@ -112,29 +120,68 @@ async fn main() -> std::io::Result<()> {
         StorageKind::Filesystem => {
             panic!("Filesystem storage for metadata is not supported yet")
         }
-        StorageKind::Redis => {
-            let redis_conn = redis_client
-                .as_ref()
-                .unwrap()
-                .get_connection()
-                .expect("Error connecting to Redis");
-            Arc::new(Mutex::new(RedisMetadataStorage::new(redis_conn)))
-        }
+        StorageKind::Memory => Arc::new(Mutex::new(MemoryMetadataStorage::new())),
+        StorageKind::Redis => Arc::new(Mutex::new(
+            RedisMetadataStorage::new(redis_connection(&redis_client))
+                .expect("Error creating RedisMetadataStorage"),
+        )),
         StorageKind::S3 => panic!("S3 storage for metadata is not supported yet"),
     };
 
-    let task_storage: Arc<dyn TaskStorage> = Arc::new(RedisTaskStorage::new(
-        redis_client
-            .as_ref()
-            .unwrap()
-            .get_connection()
-            .expect("Error connecting to Redis"),
-    ));
+    let (task_storage, schedule_storage): (Arc<dyn TaskStorage>, Arc<dyn ScheduleStorage>) =
+        match config.task.storage_kind {
+            StorageKind::Filesystem => panic!("Filesystem storage for tasks is not supported yet"),
+            StorageKind::Memory => (
+                Arc::new(MemoryTaskStorage::new(config.task.retention)),
+                Arc::new(MemoryScheduleStorage::new()),
+            ),
+            StorageKind::Redis => (
+                Arc::new(
+                    RedisTaskStorage::new(redis_connection(&redis_client), config.task.retention)
+                        .expect("Error creating RedisTaskStorage"),
+                ),
+                Arc::new(RedisScheduleStorage::new(redis_connection(&redis_client))),
+            ),
+            StorageKind::S3 => panic!("S3 storage for tasks is not supported yet"),
+        };
+
+    // The other stores live in Redis when it is configured, in memory otherwise.
+    let project_storage: Arc<dyn ProjectStorage>;
+    let resumable_upload_storage: Arc<dyn ResumableUploadStorage>;
+    let rate_limiter: Arc<dyn RateLimiter>;
+    let usage_storage: Arc<dyn UsageStorage>;
+    let webhook_storage: Arc<dyn WebhookStorage>;
+    if redis_client.is_some() {
+        project_storage = Arc::new(
+            RedisProjectStorage::new(redis_connection(&redis_client))
+                .expect("Error creating RedisProjectStorage"),
+        );
+        resumable_upload_storage = Arc::new(RedisResumableUploadStorage::new(redis_connection(
+            &redis_client,
+        )));
+        rate_limiter = Arc::new(RedisRateLimiter::new(redis_connection(&redis_client)));
+        usage_storage = Arc::new(RedisUsageStorage::new(
+            redis_connection(&redis_client),
+            config.usage.retention_days,
+        ));
+        webhook_storage = Arc::new(
+            RedisWebhookStorage::new(redis_connection(&redis_client))
+                .expect("Error creating RedisWebhookStorage"),
+        );
+    } else {
+        project_storage = Arc::new(MemoryProjectStorage::new());
+        resumable_upload_storage = Arc::new(MemoryResumableUploadStorage::new());
+        rate_limiter = Arc::new(MemoryRateLimiter::new());
+        usage_storage = Arc::new(MemoryUsageStorage::new(config.usage.retention_days));
+        webhook_storage = Arc::new(MemoryWebhookStorage::new());
+    }
+    let usage_tracker = UsageTracker::new(usage_storage, config.usage.clone());
 
     let file_storage: Arc<Mutex<dyn FileStorage>> = match config.file_storage.storage_kind {
         StorageKind::Filesystem => Arc::new(Mutex::new(FilesystemStorage::new(
             config.file_storage.filesystem.clone().unwrap().mount_dir,
         ))),
+        StorageKind::Memory => Arc::new(Mutex::new(MemoryStorage::new())),
         StorageKind::S3 => Arc::new(Mutex::new(S3Storage::new(S3::new(
             s3_client.clone().unwrap(),
             config.file_storage.s3.clone().unwrap().bucket_name,
@@ -146,6 +193,7 @@ async fn main() -> std::io::Result<()> {
         StorageKind::Filesystem => Arc::new(Mutex::new(FilesystemStorage::new(
             config.cache_storage.filesystem.clone().unwrap().mount_dir,
         ))),
+        StorageKind::Memory => Arc::new(Mutex::new(MemoryStorage::new())),
         StorageKind::S3 => Arc::new(Mutex::new(S3Storage::new(S3::new(
             s3_client.clone().unwrap(),
             config.cache_storage.s3.clone().unwrap().bucket_name,
@@ -153,24 +201,50 @@ async fn main() -> std::io::Result<()> {
         StorageKind::Redis => panic!("Redis storage for cache is not supported yet"),
     };
 
-    let clear_cache_task: Arc<dyn TaskExecutor> = Arc::new(handler::CacheHandler::new(
-        cache_storage.clone(),
-        metadata_storage.clone(),
-    ));
-
-    let task_executors = vec![(scheduler::TaskKind::ClearCache, clear_cache_task.clone())]
-        .into_iter()
-        .collect();
-    let task_scheduler = run_scheduler(task_storage, task_executors);
-
-    run_server(
-        config,
+    let projects = Arc::new(ProjectRegistry::new(
+        project_storage,
         file_storage,
         cache_storage,
         metadata_storage,
         named_transformation_storage,
+        resumable_upload_storage,
+        usage_tracker.clone(),
+        config.upload.clone(),
+    )
+    .with_webhooks(webhook_storage, task_storage.clone(), config.webhook.clone()));
+
+    // Tasks run with the handlers of their project.
+    let project_task_executor: Arc<dyn TaskExecutor> = projects.clone();
+
+    let task_executors = vec![
+        (scheduler::TaskKind::ClearCache, project_task_executor.clone()),
+        (scheduler::TaskKind::BulkMedia, project_task_executor.clone()),
+        (scheduler::TaskKind::WebhookDelivery, project_task_executor),
+    ]
+    .into_iter()
+    .collect();
+    let task_scheduler = run_scheduler(
+        task_storage,
+        schedule_storage,
+        task_executors,
+        config.task.clone(),
+    );
+
+    run_server(
+        config,
+        projects,
         apikey_storage,
         task_scheduler,
+        rate_limiter,
+        usage_tracker,
     )
     .await
 }
+
+fn redis_connection(redis_client: &Option<redis::Client>) -> redis::Connection {
+    redis_client
+        .as_ref()
+        .expect("Redis storage requires the [adapter.redis] configuration")
+        .get_connection()
+        .expect("Error connecting to Redis")
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use tokio::sync::Mutex;
+
+use crate::error::MindiaError;
+use crate::metadata::Metadata;
+use crate::storage::FileStorage;
+
+/// A stored media ready to be served, read lazily from the storage that holds it.
+pub struct MediaSource {
+    pub metadata: Metadata,
+    pub size: u64,
+    /// The account serving the media is accounted to.
+    pub owner: Option<String>,
+    storage: Arc<Mutex<dyn FileStorage>>,
+    body: Option<Bytes>,
+}
+
+impl MediaSource {
+    pub fn new(metadata: Metadata, size: u64, storage: Arc<Mutex<dyn FileStorage>>) -> Self {
+        Self {
+            metadata,
+            size,
+            owner: None,
+            storage,
+            body: None,
+        }
+    }
+
+    pub fn with_body(self, body: Bytes) -> Self {
+        Self {
+            body: Some(body),
+            ..self
+        }
+    }
+
+    pub fn with_owner(self, owner: Option<String>) -> Self {
+        Self { owner, ..self }
+    }
+
+    pub async fn read(&self) -> Result<Bytes, Box<dyn Error>> {
+        if let Some(body) = &self.body {
+            return Ok(body.clone());
+        }
+
+        self.storage
+            .lock()
+            .await
+            .download(self.metadata.path.as_str())
+            .await?
+            .ok_or_else(|| MindiaError::not_found("Media not found").into())
+    }
+
+    pub async fn read_range(&self, start: u64, end: u64) -> Result<Bytes, Box<dyn Error>> {
+        if let Some(body) = &self.body {
+            return Ok(body.slice(start as usize..=end as usize));
+        }
+
+        self.storage
+            .lock()
+            .await
+            .download_range(self.metadata.path.as_str(), start, end)
+            .await?
+            .ok_or_else(|| MindiaError::not_found("Media not found").into())
+    }
+}
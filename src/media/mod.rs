@@ -1,7 +1,11 @@
 pub mod media_group_handle;
 pub mod media_handle;
+pub mod media_source;
 pub mod path;
+pub mod temp_file;
 
 pub use media_group_handle::MediaGroupHandle;
 pub use media_handle::MediaHandle;
+pub use media_source::MediaSource;
 pub use path::Path;
+pub use temp_file::TempFile;
@@ -4,6 +4,8 @@ use std::error::Error;
 use std::path::PathBuf;
 use uuid::Uuid;
 
+use crate::project::PROJECTS_FOLDER;
+
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct Path {
     raw_path: String,
@@ -15,6 +17,8 @@ impl Path {
             return Err("Invalid path");
         }
 
+        validate_segments(raw_path)?;
+
         let path = Self{  raw_path: raw_path.to_string() };
 
         Uuid::parse_str(path.basename()).map_err(|_| "Invalid path")?;
@@ -80,12 +84,47 @@ impl Path {
     }
 }
 
+/// Rejects the empty, `.` and `..` segments of `path`, which starts with `/`, so
+/// that it cannot escape its folder once joined to a storage root.
+fn validate_segments(path: &str) -> Result<(), &'static str> {
+    let invalid_segment = path[1..].split('/').any(|segment| {
+        segment.is_empty() || segment == "." || segment == ".." || segment.contains('\\')
+    });
+    if invalid_segment {
+        return Err("Invalid path");
+    }
+
+    // The storages keep the media of the other projects there.
+    if path
+        .strip_prefix(PROJECTS_FOLDER)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    {
+        return Err("Invalid path");
+    }
+
+    Ok(())
+}
+
+/// Validates a folder sent by a client and returns it as `/a/b`, or `/` for the root.
+pub fn normalize_folder(folder: &str) -> Result<String, &'static str> {
+    let folder = folder.trim_matches('/');
+    if folder.is_empty() {
+        return Ok("/".to_string());
+    }
+
+    let folder = format!("/{}", folder);
+    validate_segments(&folder)?;
+
+    Ok(folder)
+}
+
 pub fn generate_path(path: &str) -> Result<Path, &str> {
     let mut path = PathBuf::from(path);
     let ext = path.extension().and_then(std::ffi::OsStr::to_str);
     let new_name = format!("{}.{}", Uuid::new_v4(), ext.unwrap_or(""));
     path.set_file_name(new_name);
-    let path = "/".to_string() + path.to_str().unwrap().replace("\\", "/").as_str();
+    let path = path.to_str().ok_or("Path is not valid UTF-8")?;
+    let path = "/".to_string() + path.replace("\\", "/").trim_start_matches('/');
     Path::new(path.as_str())
 }
 
@@ -121,7 +160,7 @@ impl<'de> Deserialize<'de> for Path {
 
 #[cfg(test)]
 mod tests {
-    use super::{generate_path, Path};
+    use super::{generate_path, normalize_folder, Path};
     use super::Uuid;
 
     #[test]
@@ -133,6 +172,30 @@ mod tests {
         assert!(invalid_path.is_err());
     }
 
+    #[test]
+    fn test_new_rejects_traversal() {
+        let uuid = "bbd2fa99-f35e-4062-92eb-9d26caa943ae";
+        for raw_path in [
+            format!("/x/../_projects/other/{}.webp", uuid),
+            format!("/x/./{}.webp", uuid),
+            format!("/x//{}.webp", uuid),
+            format!("/..\\_projects/{}.webp", uuid),
+            format!("/_projects/other/{}.webp", uuid),
+        ] {
+            assert!(Path::new(&raw_path).is_err(), "{}", raw_path);
+        }
+    }
+
+    #[test]
+    fn test_normalize_folder() {
+        assert_eq!(normalize_folder("/products/shoes/").unwrap(), "/products/shoes");
+        assert_eq!(normalize_folder("products").unwrap(), "/products");
+        assert_eq!(normalize_folder("/").unwrap(), "/");
+        assert!(normalize_folder("products/../secret").is_err());
+        assert!(normalize_folder("products//shoes").is_err());
+        assert!(normalize_folder("_projects/other").is_err());
+    }
+
     #[test]
     fn test_add_suffix_to_filename() {
         let path = Path::new("/folder/bbd2fa99-f35e-4062-92eb-9d26caa943ae.txt").unwrap();
@@ -0,0 +1,33 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    pub fn new(tmp_dir: Option<&str>) -> io::Result<Self> {
+        let dir = match tmp_dir {
+            Some(tmp_dir) => PathBuf::from(tmp_dir),
+            None => std::env::temp_dir(),
+        };
+
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            path: dir.join(format!("mindia-{}.part", Uuid::new_v4())),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
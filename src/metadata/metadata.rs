@@ -5,14 +5,26 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 
 use crate::media::Path;
+use crate::transform::TransformationDescriptorChain;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metadata {
     pub path: Path,
+    #[serde(default)]
+    pub original_filename: Option<String>,
     pub content_type: Option<String>,
     pub content_length: usize,
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    #[serde(default)]
+    pub has_alpha: Option<bool>,
     pub embedded_metadata: HashMap<String, String>,
     pub derived_medias: Vec<Metadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transformations: Option<TransformationDescriptorChain>,
+    /// The account the media is accounted to, the API key that uploaded it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
 }
@@ -30,10 +42,15 @@ impl Default for Metadata {
     fn default() -> Self {
         Self {
             path: Path::default(),
+            original_filename: None,
             content_type: None,
             content_length: 0,
+            content_hash: None,
+            has_alpha: None,
             embedded_metadata: HashMap::new(),
             derived_medias: Vec::new(),
+            transformations: None,
+            owner: None,
             created_at: Utc::now(),
             updated_at: None,
         }
@@ -41,6 +58,10 @@ impl Default for Metadata {
 }
 
 impl Metadata {
+    pub fn last_modified(&self) -> DateTime<Utc> {
+        self.updated_at.unwrap_or(self.created_at)
+    }
+
     pub fn append_derived_media(&mut self, metadata: Metadata) {
         self.derived_medias.push(metadata);
     }
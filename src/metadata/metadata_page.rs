@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+use crate::metadata::Metadata;
+
+/// One page of a folder listing. `next_cursor` is opaque and resumes the listing
+/// right after the last entry of this page.
+#[derive(Debug, Default, Serialize)]
+pub struct MetadataPage {
+    pub media: Vec<Metadata>,
+    pub folders: Vec<String>,
+    pub next_cursor: Option<String>,
+}
@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::ops::Bound;
+use std::sync::{Arc, RwLock};
+
+use crate::error::MindiaError;
+use crate::metadata::{Metadata, MetadataPage, MetadataStorage};
+use crate::project::DEFAULT_PROJECT;
+
+/// The metadata of every project, by path in lexicographic order.
+type Projects = Arc<RwLock<HashMap<String, BTreeMap<String, Metadata>>>>;
+
+/// Keeps the metadata in memory, for development and tests. It is lost when the
+/// server stops.
+pub struct MemoryMetadataStorage {
+    projects: Projects,
+    project: String,
+}
+
+impl MemoryMetadataStorage {
+    pub fn new() -> Self {
+        Self {
+            projects: Projects::default(),
+            project: DEFAULT_PROJECT.to_string(),
+        }
+    }
+}
+
+impl Default for MemoryMetadataStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid_cursor() -> Box<dyn Error> {
+    Box::new(MindiaError::validation("Invalid cursor"))
+}
+
+/// The cursor is the hex encoded path after which the listing resumes.
+fn decode_cursor(cursor: &str, prefix: &str) -> Result<String, Box<dyn Error>> {
+    let after = hex::decode(cursor).map_err(|_| invalid_cursor())?;
+    let after = String::from_utf8(after).map_err(|_| invalid_cursor())?;
+
+    if !after.starts_with(prefix) {
+        return Err(invalid_cursor());
+    }
+
+    Ok(after)
+}
+
+impl MetadataStorage for MemoryMetadataStorage {
+    fn get_by_path(&self, path: &str) -> Result<Option<Metadata>, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+
+        Ok(projects
+            .get(&self.project)
+            .and_then(|documents| documents.get(path))
+            .cloned())
+    }
+
+    fn get_many_before_date(
+        &self,
+        before_date: DateTime<Utc>,
+        _limit: u32,
+    ) -> Result<Vec<Metadata>, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+        let documents = match projects.get(&self.project) {
+            Some(documents) => documents,
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(documents
+            .values()
+            .filter(|metadata| {
+                metadata
+                    .derived_medias
+                    .iter()
+                    .any(|dm| dm.created_at < before_date)
+            })
+            .cloned()
+            .collect())
+    }
+
+    fn list(
+        &self,
+        folder: &str,
+        cursor: Option<&str>,
+        limit: usize,
+        recursive: bool,
+    ) -> Result<MetadataPage, Box<dyn Error>> {
+        let prefix = format!("{}/", folder.trim_end_matches('/'));
+        let mut after = match cursor {
+            Some(cursor) => Some(decode_cursor(cursor, &prefix)?),
+            None => None,
+        };
+
+        let mut page = MetadataPage::default();
+
+        let projects = self.projects.read().unwrap();
+        let documents = match projects.get(&self.project) {
+            Some(documents) => documents,
+            None => return Ok(page),
+        };
+
+        let start = match &after {
+            Some(after) => Bound::Excluded(after.clone()),
+            None => Bound::Included(prefix.clone()),
+        };
+
+        for (path, metadata) in documents.range((start, Bound::Unbounded)) {
+            if !path.starts_with(&prefix) {
+                break;
+            }
+
+            // Paths of a subfolder already listed.
+            if after.as_ref().map_or(false, |after| path <= after) {
+                continue;
+            }
+
+            if page.media.len() + page.folders.len() >= limit {
+                page.next_cursor = after.map(hex::encode);
+                break;
+            }
+
+            let relative_path = &path[prefix.len()..];
+
+            if !recursive {
+                if let Some(end) = relative_path.find('/') {
+                    let subfolder = format!("{}{}", prefix, &relative_path[..end]);
+                    // `0` is the character following `/`.
+                    after = Some(format!("{}0", subfolder));
+                    page.folders.push(subfolder);
+                    continue;
+                }
+            }
+
+            after = Some(path.clone());
+            page.media.push(metadata.clone());
+        }
+
+        Ok(page)
+    }
+
+    fn save(&self, path: &str, metadata: Metadata) -> Result<(), Box<dyn Error>> {
+        self.projects
+            .write()
+            .unwrap()
+            .entry(self.project.clone())
+            .or_default()
+            .insert(path.to_string(), metadata);
+
+        Ok(())
+    }
+
+    fn delete(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(documents) = self.projects.write().unwrap().get_mut(&self.project) {
+            documents.remove(path);
+        }
+
+        Ok(())
+    }
+
+    fn for_project(
+        &self,
+        project: &str,
+    ) -> Result<Arc<tokio::sync::Mutex<dyn MetadataStorage>>, Box<dyn Error>> {
+        Ok(Arc::new(tokio::sync::Mutex::new(Self {
+            projects: self.projects.clone(),
+            project: project.to_string(),
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::Path;
+
+    fn save(storage: &MemoryMetadataStorage, path: &str) {
+        let media_path = Path::new("/bbd2fa99-f35e-4062-92eb-9d26caa943ae.png").unwrap();
+        let metadata = Metadata::new(media_path);
+        storage.save(path, metadata).unwrap();
+    }
+
+    #[test]
+    fn test_list_pages_through_media_and_folders() {
+        let storage = MemoryMetadataStorage::new();
+        for path in ["/a/1.png", "/a/b/2.png", "/a/b/3.png", "/a/c.png", "/d.png"] {
+            save(&storage, path);
+        }
+
+        let first = storage.list("/a", None, 2, false).unwrap();
+        assert_eq!(first.media.len(), 1);
+        assert_eq!(first.folders, vec!["/a/b".to_string()]);
+
+        let cursor = first.next_cursor.unwrap();
+        let second = storage.list("/a", Some(&cursor), 2, false).unwrap();
+        assert_eq!(second.media.len(), 1);
+        assert!(second.folders.is_empty());
+        assert!(second.next_cursor.is_none());
+
+        let recursive = storage.list("/a", None, 10, true).unwrap();
+        assert_eq!(recursive.media.len(), 4);
+    }
+}
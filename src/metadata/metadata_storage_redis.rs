@@ -3,25 +3,116 @@ use redis::Connection;
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 
-use crate::metadata::{Metadata, MetadataStorage};
+use crate::error::MindiaError;
+use crate::metadata::{Metadata, MetadataPage, MetadataStorage};
+use crate::project::{namespaced_key, DEFAULT_PROJECT};
 
 const METADATA_PREFIX_KEY: &str = "metadata:";
+/// Sorted set of every media path (all scored 0) so folders can be listed in
+/// lexicographic order with `ZRANGEBYLEX`.
+const METADATA_INDEX_KEY: &str = "metadata_index";
 
 pub struct RedisMetadataStorage {
     conn: Arc<Mutex<Connection>>,
+    prefix_key: String,
+    index_key: String,
 }
 
 impl RedisMetadataStorage {
-    pub fn new(conn: Connection) -> Self {
+    pub fn new(conn: Connection) -> Result<Self, Box<dyn Error>> {
+        let storage = Self::with_project(Arc::new(Mutex::new(conn)), DEFAULT_PROJECT);
+        storage.init()?;
+        Ok(storage)
+    }
+
+    fn with_project(conn: Arc<Mutex<Connection>>, project: &str) -> Self {
         Self {
-            conn: Arc::new(Mutex::new(conn)),
+            conn,
+            prefix_key: namespaced_key(project, METADATA_PREFIX_KEY),
+            index_key: namespaced_key(project, METADATA_INDEX_KEY),
+        }
+    }
+
+    /// Builds the path index from the existing metadata keys the first time the
+    /// storage runs against a database that predates it.
+    fn init(&self) -> Result<(), Box<dyn Error>> {
+        let exists: bool = redis::cmd("EXISTS")
+            .arg(&self.index_key)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        if exists {
+            return Ok(());
+        }
+
+        let mut cursor = 0;
+        loop {
+            let scan: (i64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(format!("{}*", self.prefix_key))
+                .arg("COUNT")
+                .arg(1000)
+                .query(&mut self.conn.lock().unwrap())?;
+
+            cursor = scan.0;
+            for key in scan.1 {
+                if let Some(path) = key.strip_prefix(self.prefix_key.as_str()) {
+                    redis::cmd("ZADD")
+                        .arg(&self.index_key)
+                        .arg(0)
+                        .arg(path)
+                        .query::<()>(&mut self.conn.lock().unwrap())?;
+                }
+            }
+
+            if cursor == 0 {
+                break;
+            }
         }
+
+        Ok(())
+    }
+
+    fn range_by_lex(&self, min: &str, max: &str, count: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        let paths: Vec<String> = redis::cmd("ZRANGEBYLEX")
+            .arg(&self.index_key)
+            .arg(min)
+            .arg(max)
+            .arg("LIMIT")
+            .arg(0)
+            .arg(count)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        Ok(paths)
+    }
+}
+
+/// Returns the `ZRANGEBYLEX` bound that sorts right after every path starting with
+/// `prefix`. `prefix` ends with `/`, and `0` is the character following `/`.
+fn exclusive_upper_bound(prefix: &str) -> String {
+    format!("({}0", &prefix[..prefix.len() - 1])
+}
+
+fn invalid_cursor() -> Box<dyn Error> {
+    Box::new(MindiaError::validation("Invalid cursor"))
+}
+
+fn decode_cursor(cursor: &str, prefix: &str) -> Result<String, Box<dyn Error>> {
+    let bound = hex::decode(cursor).map_err(|_| invalid_cursor())?;
+    let bound = String::from_utf8(bound).map_err(|_| invalid_cursor())?;
+
+    let valid = (bound.starts_with('(') || bound.starts_with('['))
+        && bound[1..].starts_with(prefix);
+    if !valid {
+        return Err(invalid_cursor());
     }
+
+    Ok(bound)
 }
 
 impl MetadataStorage for RedisMetadataStorage {
     fn get_by_path(&self, path: &str) -> Result<Option<Metadata>, Box<dyn Error>> {
-        let key = format!("{}{}", METADATA_PREFIX_KEY, path);
+        let key = format!("{}{}", self.prefix_key, path);
 
         let result: Result<String, _> = redis::cmd("JSON.GET")
             .arg(key)
@@ -47,7 +138,7 @@ impl MetadataStorage for RedisMetadataStorage {
             let scan: (i64, Vec<String>) = redis::cmd("SCAN")
                 .arg(cursor)
                 .arg("MATCH")
-                .arg(format!("{}*", METADATA_PREFIX_KEY))
+                .arg(format!("{}*", self.prefix_key))
                 .arg("COUNT")
                 .arg(limit)
                 .query(&mut self.conn.lock().unwrap())?;
@@ -77,27 +168,101 @@ impl MetadataStorage for RedisMetadataStorage {
         Ok(metadata_before_instant)
     }
 
+    fn list(
+        &self,
+        folder: &str,
+        cursor: Option<&str>,
+        limit: usize,
+        recursive: bool,
+    ) -> Result<MetadataPage, Box<dyn Error>> {
+        let prefix = format!("{}/", folder.trim_end_matches('/'));
+        let max = exclusive_upper_bound(&prefix);
+        let mut min = match cursor {
+            Some(cursor) => decode_cursor(cursor, &prefix)?,
+            None => format!("[{}", prefix),
+        };
+
+        let mut page = MetadataPage::default();
+
+        'pages: while page.media.len() + page.folders.len() < limit {
+            let remaining = limit - page.media.len() - page.folders.len();
+            let paths = self.range_by_lex(&min, &max, remaining)?;
+
+            if paths.is_empty() {
+                return Ok(page);
+            }
+
+            for path in paths {
+                let relative_path = &path[prefix.len()..];
+
+                if !recursive {
+                    if let Some(end) = relative_path.find('/') {
+                        let subfolder = format!("{}{}/", prefix, &relative_path[..end]);
+                        min = exclusive_upper_bound(&subfolder);
+                        page.folders.push(subfolder[..subfolder.len() - 1].to_string());
+                        // Skip the rest of the subfolder in a fresh range query.
+                        continue 'pages;
+                    }
+                }
+
+                min = format!("({}", path);
+                if let Some(metadata) = self.get_by_path(&path)? {
+                    page.media.push(metadata);
+                }
+            }
+        }
+
+        if !self.range_by_lex(&min, &max, 1)?.is_empty() {
+            page.next_cursor = Some(hex::encode(&min));
+        }
+
+        Ok(page)
+    }
+
     fn save(&self, path: &str, metadata: Metadata) -> Result<(), Box<dyn Error>> {
         let metadata_str = serde_json::to_string(&metadata)?;
 
-        let key = format!("{}{}", METADATA_PREFIX_KEY, path);
+        let key = format!("{}{}", self.prefix_key, path);
 
         redis::cmd("JSON.SET")
             .arg(key)
             .arg(".")
             .arg(metadata_str)
-            .query(&mut self.conn.lock().unwrap())?;
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        redis::cmd("ZADD")
+            .arg(&self.index_key)
+            .arg(0)
+            .arg(path)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
 
         Ok(())
     }
 
     fn delete(&self, path: &str) -> Result<(), Box<dyn Error>> {
-        let key = format!("{}{}", METADATA_PREFIX_KEY, path);
+        let key = format!("{}{}", self.prefix_key, path);
 
         redis::cmd("DEL")
             .arg(key)
-            .query(&mut self.conn.lock().unwrap())?;
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        redis::cmd("ZREM")
+            .arg(&self.index_key)
+            .arg(path)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
 
         Ok(())
     }
+
+    fn for_project(
+        &self,
+        project: &str,
+    ) -> Result<Arc<tokio::sync::Mutex<dyn MetadataStorage>>, Box<dyn Error>> {
+        // Projects never held metadata before the index, so there is nothing to
+        // rebuild with `init`.
+        Ok(Arc::new(tokio::sync::Mutex::new(Self::with_project(
+            self.conn.clone(),
+            project,
+        ))))
+    }
 }
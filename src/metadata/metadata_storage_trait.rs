@@ -1,7 +1,9 @@
 use chrono::{DateTime, Utc};
 use std::error::Error;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
-use crate::metadata::Metadata;
+use crate::metadata::{Metadata, MetadataPage};
 
 pub trait MetadataStorage: Send + Sync {
     fn get_by_path(&self, path: &str) -> Result<Option<Metadata>, Box<dyn Error>>;
@@ -10,6 +12,17 @@ pub trait MetadataStorage: Send + Sync {
         before_date: DateTime<Utc>,
         limit: u32,
     ) -> Result<Vec<Metadata>, Box<dyn Error>>;
+    /// Lists the media stored under `folder` (e.g. `/products`), and its immediate
+    /// subfolders unless `recursive` is set, in lexicographic path order.
+    fn list(
+        &self,
+        folder: &str,
+        cursor: Option<&str>,
+        limit: usize,
+        recursive: bool,
+    ) -> Result<MetadataPage, Box<dyn Error>>;
     fn save(&self, path: &str, metadata: Metadata) -> Result<(), Box<dyn Error>>;
     fn delete(&self, path: &str) -> Result<(), Box<dyn Error>>;
+    /// The storage of the metadata of `project`, kept apart from this one.
+    fn for_project(&self, project: &str) -> Result<Arc<Mutex<dyn MetadataStorage>>, Box<dyn Error>>;
 }
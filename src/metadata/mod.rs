@@ -1,7 +1,11 @@
 pub mod metadata;
+pub mod metadata_page;
+pub mod metadata_storage_memory;
 pub mod metadata_storage_redis;
 pub mod metadata_storage_trait;
 
 pub use metadata::Metadata;
+pub use metadata_page::MetadataPage;
+pub use metadata_storage_memory::MemoryMetadataStorage;
 pub use metadata_storage_redis::RedisMetadataStorage;
 pub use metadata_storage_trait::MetadataStorage;
@@ -3,6 +3,6 @@ use async_trait::async_trait;
 
 
 #[async_trait]
-pub trait PipelineStep<T: Send> {
+pub trait PipelineStep<T: Send>: Send + Sync {
     async fn execute(&self, context: T) -> Result<T, Box<dyn Error>>;
 }
@@ -0,0 +1,9 @@
+pub mod project;
+pub mod project_storage_memory;
+pub mod project_storage_redis;
+pub mod project_storage_trait;
+
+pub use project::{default_project, namespaced_key, Project, ProjectMap, DEFAULT_PROJECT, PROJECTS_FOLDER};
+pub use project_storage_memory::MemoryProjectStorage;
+pub use project_storage_redis::RedisProjectStorage;
+pub use project_storage_trait::ProjectStorage;
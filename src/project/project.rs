@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The project of API keys and data that predate projects. Its keys and paths
+/// are not namespaced, so existing deployments keep working unchanged.
+pub const DEFAULT_PROJECT: &str = "default";
+
+/// Folder of the file and cache storages holding the media of every project but
+/// the default one. Media paths cannot start with it.
+pub const PROJECTS_FOLDER: &str = "/_projects";
+
+pub type ProjectMap = HashMap<String, Project>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Project {
+    pub fn new(name: String, description: Option<String>) -> Self {
+        Self {
+            name,
+            description,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Names end up in Redis keys, storage paths and URLs.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= 64
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    }
+
+    /// Prefix of the media paths of `project` in the file and cache storages.
+    pub fn storage_prefix(project: &str) -> String {
+        if project == DEFAULT_PROJECT {
+            return String::new();
+        }
+
+        format!("{}/{}", PROJECTS_FOLDER, project)
+    }
+
+    /// Prefix of the routes addressing `project` explicitly, after `/api/v0`.
+    pub fn url_prefix(project: &str) -> String {
+        if project == DEFAULT_PROJECT {
+            return String::new();
+        }
+
+        format!("/projects/{}", project)
+    }
+}
+
+/// Serde default of the `project` of records that predate projects.
+pub fn default_project() -> String {
+    DEFAULT_PROJECT.to_string()
+}
+
+/// Returns the Redis `key` of `project`.
+pub fn namespaced_key(project: &str, key: &str) -> String {
+    if project == DEFAULT_PROJECT {
+        return key.to_string();
+    }
+
+    format!("project:{}:{}", project, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaced_key() {
+        assert_eq!(namespaced_key(DEFAULT_PROJECT, "metadata:/a.jpg"), "metadata:/a.jpg");
+        assert_eq!(namespaced_key("shop", "metadata:/a.jpg"), "project:shop:metadata:/a.jpg");
+    }
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(Project::is_valid_name("shop-2_eu"));
+        assert!(!Project::is_valid_name(""));
+        assert!(!Project::is_valid_name("Shop"));
+        assert!(!Project::is_valid_name("shop/eu"));
+        assert!(!Project::is_valid_name("shop:eu"));
+    }
+}
@@ -0,0 +1,42 @@
+use std::error::Error;
+use std::sync::RwLock;
+
+use super::{Project, ProjectMap, ProjectStorage};
+
+/// Keeps the projects in memory, for development and tests. They are lost when the
+/// server stops.
+#[derive(Default)]
+pub struct MemoryProjectStorage {
+    projects: RwLock<ProjectMap>,
+}
+
+impl MemoryProjectStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ProjectStorage for MemoryProjectStorage {
+    fn get_all(&self) -> Result<ProjectMap, Box<dyn Error>> {
+        Ok(self.projects.read().unwrap().clone())
+    }
+
+    fn get_by_name(&self, name: &str) -> Result<Option<Project>, Box<dyn Error>> {
+        Ok(self.projects.read().unwrap().get(name).cloned())
+    }
+
+    fn save(&self, project: Project) -> Result<(), Box<dyn Error>> {
+        self.projects
+            .write()
+            .unwrap()
+            .insert(project.name.clone(), project);
+
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        self.projects.write().unwrap().remove(name);
+
+        Ok(())
+    }
+}
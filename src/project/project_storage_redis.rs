@@ -0,0 +1,82 @@
+use redis::{Connection, ErrorKind, RedisError};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+const PROJECTS_KEY: &str = "internal:configuration:projects";
+
+use super::{Project, ProjectMap, ProjectStorage};
+
+pub struct RedisProjectStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl RedisProjectStorage {
+    pub fn new(conn: Connection) -> Result<Self, Box<dyn Error>> {
+        let storage = RedisProjectStorage {
+            conn: Arc::new(Mutex::new(conn)),
+        };
+        storage.init()?;
+        Ok(storage)
+    }
+
+    fn init(&self) -> Result<(), Box<dyn Error>> {
+        let exists: bool = redis::cmd("EXISTS")
+            .arg(PROJECTS_KEY)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        if !exists {
+            redis::cmd("JSON.SET")
+                .arg(PROJECTS_KEY)
+                .arg(".")
+                .arg("{}")
+                .query::<()>(&mut self.conn.lock().unwrap())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ProjectStorage for RedisProjectStorage {
+    fn get_all(&self) -> Result<ProjectMap, Box<dyn Error>> {
+        let result: String = redis::cmd("JSON.GET")
+            .arg(PROJECTS_KEY)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        serde_json::from_str(&result).map_err(|e| e.into())
+    }
+
+    fn get_by_name(&self, name: &str) -> Result<Option<Project>, Box<dyn Error>> {
+        let result: Result<String, RedisError> = redis::cmd("JSON.GET")
+            .arg(PROJECTS_KEY)
+            .arg(name)
+            .query(&mut self.conn.lock().unwrap());
+
+        match result {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            // RedisJSON answers an error for a path that does not exist.
+            Err(e) if e.kind() == ErrorKind::ResponseError => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, project: Project) -> Result<(), Box<dyn Error>> {
+        let project_json = serde_json::to_string(&project)?;
+
+        redis::cmd("JSON.SET")
+            .arg(PROJECTS_KEY)
+            .arg(project.name)
+            .arg(project_json)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        redis::cmd("JSON.DEL")
+            .arg(PROJECTS_KEY)
+            .arg(name)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        Ok(())
+    }
+}
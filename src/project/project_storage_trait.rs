@@ -0,0 +1,10 @@
+use std::error::Error;
+
+use super::{Project, ProjectMap};
+
+pub trait ProjectStorage: Send + Sync {
+    fn get_all(&self) -> Result<ProjectMap, Box<dyn Error>>;
+    fn get_by_name(&self, name: &str) -> Result<Option<Project>, Box<dyn Error>>;
+    fn save(&self, project: Project) -> Result<(), Box<dyn Error>>;
+    fn delete(&self, name: &str) -> Result<(), Box<dyn Error>>;
+}
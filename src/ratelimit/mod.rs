@@ -0,0 +1,9 @@
+pub mod rate_limit;
+pub mod rate_limiter_memory;
+pub mod rate_limiter_redis;
+pub mod rate_limiter_trait;
+
+pub use rate_limit::{RateLimitBudget, RateLimitDecision};
+pub use rate_limiter_memory::MemoryRateLimiter;
+pub use rate_limiter_redis::RedisRateLimiter;
+pub use rate_limiter_trait::RateLimiter;
@@ -0,0 +1,34 @@
+use crate::config::{BucketConfig, RateLimitConfig};
+
+/// The independent token buckets every client has.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitBudget {
+    Upload,
+    Transformation,
+    Metadata,
+}
+
+impl RateLimitBudget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitBudget::Upload => "upload",
+            RateLimitBudget::Transformation => "transformation",
+            RateLimitBudget::Metadata => "metadata",
+        }
+    }
+
+    pub fn bucket<'a>(&self, config: &'a RateLimitConfig) -> &'a BucketConfig {
+        match self {
+            RateLimitBudget::Upload => &config.uploads,
+            RateLimitBudget::Transformation => &config.transformations,
+            RateLimitBudget::Metadata => &config.metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    /// Seconds until a token is available again, 0 when allowed.
+    pub retry_after: u64,
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+
+use crate::config::BucketConfig;
+use crate::ratelimit::{RateLimitDecision, RateLimiter};
+
+struct Bucket {
+    tokens: f64,
+    updated_at: DateTime<Utc>,
+}
+
+/// Keeps the token buckets in memory, so the limits only hold for this instance.
+#[derive(Default)]
+pub struct MemoryRateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl MemoryRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RateLimiter for MemoryRateLimiter {
+    fn acquire(
+        &self,
+        key: &str,
+        bucket: &BucketConfig,
+    ) -> Result<RateLimitDecision, Box<dyn Error>> {
+        let now = Utc::now();
+        let capacity = bucket.capacity as f64;
+        let refill_per_ms = bucket.refill_per_second / 1000.0;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let stored = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = (now - stored.updated_at).num_milliseconds().max(0) as f64;
+        stored.tokens = capacity.min(stored.tokens + elapsed * refill_per_ms);
+        stored.updated_at = now;
+
+        if stored.tokens >= 1.0 {
+            stored.tokens -= 1.0;
+            return Ok(RateLimitDecision {
+                allowed: true,
+                retry_after: 0,
+            });
+        }
+
+        Ok(RateLimitDecision {
+            allowed: false,
+            retry_after: ((1.0 - stored.tokens) / refill_per_ms / 1000.0).ceil() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire_empties_the_bucket() {
+        let limiter = MemoryRateLimiter::new();
+        let bucket = BucketConfig {
+            capacity: 2,
+            refill_per_second: 0.5,
+        };
+
+        assert!(limiter.acquire("key", &bucket).unwrap().allowed);
+        assert!(limiter.acquire("key", &bucket).unwrap().allowed);
+
+        let decision = limiter.acquire("key", &bucket).unwrap();
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, 2);
+        assert!(limiter.acquire("other", &bucket).unwrap().allowed);
+    }
+}
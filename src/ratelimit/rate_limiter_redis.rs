@@ -0,0 +1,68 @@
+use chrono::Utc;
+use redis::{Connection, Script};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use crate::config::BucketConfig;
+use crate::ratelimit::{RateLimitDecision, RateLimiter};
+
+const RATE_LIMIT_PREFIX_KEY: &str = "internal:rate_limit:";
+
+/// Refills the bucket for the time elapsed since its last update, then takes a
+/// token. Running it as a script keeps concurrent instances consistent.
+const TOKEN_BUCKET_SCRIPT: &str = r"
+local capacity = tonumber(ARGV[1])
+local refill_per_ms = tonumber(ARGV[2]) / 1000
+local now = tonumber(ARGV[3])
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(bucket[1]) or capacity
+local updated_at = tonumber(bucket[2]) or now
+
+tokens = math.min(capacity, tokens + math.max(0, now - updated_at) * refill_per_ms)
+
+local allowed = 0
+local retry_after = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    retry_after = math.ceil((1 - tokens) / refill_per_ms / 1000)
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / refill_per_ms))
+
+return {allowed, retry_after}
+";
+
+pub struct RedisRateLimiter {
+    conn: Arc<Mutex<Connection>>,
+    script: Script,
+}
+
+impl RedisRateLimiter {
+    pub fn new(conn: Connection) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+            script: Script::new(TOKEN_BUCKET_SCRIPT),
+        }
+    }
+}
+
+impl RateLimiter for RedisRateLimiter {
+    fn acquire(&self, key: &str, bucket: &BucketConfig) -> Result<RateLimitDecision, Box<dyn Error>> {
+        let (allowed, retry_after): (u8, u64) = self
+            .script
+            .key(format!("{}{}", RATE_LIMIT_PREFIX_KEY, key))
+            .arg(bucket.capacity)
+            .arg(bucket.refill_per_second)
+            .arg(Utc::now().timestamp_millis())
+            .invoke(&mut *self.conn.lock().unwrap())?;
+
+        Ok(RateLimitDecision {
+            allowed: allowed == 1,
+            retry_after,
+        })
+    }
+}
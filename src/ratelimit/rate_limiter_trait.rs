@@ -0,0 +1,9 @@
+use std::error::Error;
+
+use crate::config::BucketConfig;
+use crate::ratelimit::RateLimitDecision;
+
+pub trait RateLimiter: Send + Sync {
+    /// Takes one token from the bucket stored under `key`, created full.
+    fn acquire(&self, key: &str, bucket: &BucketConfig) -> Result<RateLimitDecision, Box<dyn Error>>;
+}
@@ -0,0 +1,9 @@
+pub mod resumable_upload;
+pub mod resumable_upload_storage_memory;
+pub mod resumable_upload_storage_redis;
+pub mod resumable_upload_storage_trait;
+
+pub use resumable_upload::ResumableUpload;
+pub use resumable_upload_storage_memory::MemoryResumableUploadStorage;
+pub use resumable_upload_storage_redis::RedisResumableUploadStorage;
+pub use resumable_upload_storage_trait::ResumableUploadStorage;
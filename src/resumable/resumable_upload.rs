@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// State of a tus upload. The received bytes live in a data file on the local disk;
+/// only this state is kept in the storage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumableUpload {
+    pub id: String,
+    pub length: u64,
+    pub offset: u64,
+    pub folder: String,
+    pub filename: Option<String>,
+    pub transformations: Vec<String>,
+    /// Path of the ingested media once the upload is complete.
+    pub media_path: Option<String>,
+    /// Offsets of the parts received so far, each kept in the file storage.
+    #[serde(default)]
+    pub parts: Vec<u64>,
+    /// The account the media will be accounted to.
+    #[serde(default)]
+    pub owner: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ResumableUpload {
+    pub fn new(
+        length: u64,
+        folder: String,
+        filename: Option<String>,
+        transformations: Vec<String>,
+        owner: Option<String>,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            length,
+            offset: 0,
+            folder,
+            filename,
+            transformations,
+            media_path: None,
+            parts: Vec::new(),
+            owner,
+            created_at: Utc::now(),
+            expires_at,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.offset == self.length
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use crate::project::DEFAULT_PROJECT;
+use crate::resumable::{ResumableUpload, ResumableUploadStorage};
+
+#[derive(Default)]
+struct ProjectUploads {
+    uploads: HashMap<String, ResumableUpload>,
+    /// When the lock of each locked upload expires.
+    locks: HashMap<String, DateTime<Utc>>,
+}
+
+/// Keeps the state of the uploads in memory, for development and tests. It is
+/// lost when the server stops.
+pub struct MemoryResumableUploadStorage {
+    projects: Arc<RwLock<HashMap<String, ProjectUploads>>>,
+    project: String,
+}
+
+impl MemoryResumableUploadStorage {
+    pub fn new() -> Self {
+        Self {
+            projects: Arc::default(),
+            project: DEFAULT_PROJECT.to_string(),
+        }
+    }
+}
+
+impl Default for MemoryResumableUploadStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResumableUploadStorage for MemoryResumableUploadStorage {
+    fn get(&self, id: &str) -> Result<Option<ResumableUpload>, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+
+        Ok(projects
+            .get(&self.project)
+            .and_then(|project| project.uploads.get(id))
+            .cloned())
+    }
+
+    fn save(&self, upload: &ResumableUpload) -> Result<(), Box<dyn Error>> {
+        self.projects
+            .write()
+            .unwrap()
+            .entry(self.project.clone())
+            .or_default()
+            .uploads
+            .insert(upload.id.clone(), upload.clone());
+
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(project) = self.projects.write().unwrap().get_mut(&self.project) {
+            project.uploads.remove(id);
+        }
+
+        Ok(())
+    }
+
+    fn take_expired(&self, now: DateTime<Utc>) -> Result<Vec<ResumableUpload>, Box<dyn Error>> {
+        let mut projects = self.projects.write().unwrap();
+        let project = match projects.get_mut(&self.project) {
+            Some(project) => project,
+            None => return Ok(Vec::new()),
+        };
+
+        let expired: Vec<String> = project
+            .uploads
+            .values()
+            .filter(|upload| upload.expires_at <= now)
+            .map(|upload| upload.id.clone())
+            .collect();
+
+        Ok(expired
+            .iter()
+            .filter_map(|id| project.uploads.remove(id))
+            .collect())
+    }
+
+    fn lock(&self, id: &str, lease: i64) -> Result<bool, Box<dyn Error>> {
+        let now = Utc::now();
+        let mut projects = self.projects.write().unwrap();
+        let locks = &mut projects.entry(self.project.clone()).or_default().locks;
+
+        if locks.get(id).is_some_and(|expiry| *expiry > now) {
+            return Ok(false);
+        }
+        locks.insert(id.to_string(), now + Duration::seconds(lease));
+
+        Ok(true)
+    }
+
+    fn unlock(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(project) = self.projects.write().unwrap().get_mut(&self.project) {
+            project.locks.remove(id);
+        }
+
+        Ok(())
+    }
+
+    fn for_project(&self, project: &str) -> Arc<dyn ResumableUploadStorage> {
+        Arc::new(Self {
+            projects: self.projects.clone(),
+            project: project.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upload(expires_at: DateTime<Utc>) -> ResumableUpload {
+        ResumableUpload::new(10, "/a".to_string(), None, Vec::new(), None, expires_at)
+    }
+
+    #[test]
+    fn test_locks_and_takes_expired_uploads() {
+        let storage = MemoryResumableUploadStorage::new();
+        let now = Utc::now();
+
+        let expired = upload(now - Duration::minutes(1));
+        let pending = upload(now + Duration::minutes(1));
+        storage.save(&expired).unwrap();
+        storage.save(&pending).unwrap();
+
+        let taken = storage.take_expired(now).unwrap();
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].id, expired.id);
+        assert!(storage.get(&expired.id).unwrap().is_none());
+        assert!(storage.take_expired(now).unwrap().is_empty());
+
+        assert!(storage.lock(&pending.id, 60).unwrap());
+        assert!(!storage.lock(&pending.id, 60).unwrap());
+        assert!(storage.for_project("other").get(&pending.id).unwrap().is_none());
+        storage.unlock(&pending.id).unwrap();
+        assert!(storage.lock(&pending.id, 60).unwrap());
+
+        // A lock left behind is released after its lease.
+        assert!(storage.lock(&expired.id, -1).unwrap());
+        assert!(storage.lock(&expired.id, 60).unwrap());
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Utc};
+use redis::Connection;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use crate::project::{namespaced_key, DEFAULT_PROJECT};
+use crate::resumable::{ResumableUpload, ResumableUploadStorage};
+
+const RESUMABLE_UPLOAD_PREFIX_KEY: &str = "internal:resumable_upload:";
+/// Ids of the uploads, scored by their expiry.
+const RESUMABLE_UPLOADS_INDEX_KEY: &str = "internal:resumable_uploads";
+const RESUMABLE_UPLOAD_LOCK_PREFIX_KEY: &str = "internal:resumable_upload_lock:";
+/// Seconds the state outlives the upload, so the sweep still finds its parts.
+const EXPIRED_UPLOAD_GRACE: i64 = 24 * 60 * 60;
+
+pub struct RedisResumableUploadStorage {
+    conn: Arc<Mutex<Connection>>,
+    prefix_key: String,
+    index_key: String,
+    lock_prefix_key: String,
+}
+
+impl RedisResumableUploadStorage {
+    pub fn new(conn: Connection) -> Self {
+        Self::with_project(Arc::new(Mutex::new(conn)), DEFAULT_PROJECT)
+    }
+
+    fn with_project(conn: Arc<Mutex<Connection>>, project: &str) -> Self {
+        Self {
+            conn,
+            prefix_key: namespaced_key(project, RESUMABLE_UPLOAD_PREFIX_KEY),
+            index_key: namespaced_key(project, RESUMABLE_UPLOADS_INDEX_KEY),
+            lock_prefix_key: namespaced_key(project, RESUMABLE_UPLOAD_LOCK_PREFIX_KEY),
+        }
+    }
+}
+
+impl ResumableUploadStorage for RedisResumableUploadStorage {
+    fn get(&self, id: &str) -> Result<Option<ResumableUpload>, Box<dyn Error>> {
+        let key = format!("{}{}", self.prefix_key, id);
+
+        let result: Option<String> = redis::cmd("JSON.GET")
+            .arg(key)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        result
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| e.into())
+    }
+
+    fn save(&self, upload: &ResumableUpload) -> Result<(), Box<dyn Error>> {
+        let key = format!("{}{}", self.prefix_key, upload.id);
+        let mut conn = self.conn.lock().unwrap();
+
+        redis::cmd("JSON.SET")
+            .arg(&key)
+            .arg(".")
+            .arg(serde_json::to_string(upload)?)
+            .query::<()>(&mut *conn)?;
+
+        // Redis drops the state on its own should the sweep never run.
+        redis::cmd("EXPIREAT")
+            .arg(&key)
+            .arg(upload.expires_at.timestamp() + EXPIRED_UPLOAD_GRACE)
+            .query::<()>(&mut *conn)?;
+
+        redis::cmd("ZADD")
+            .arg(&self.index_key)
+            .arg(upload.expires_at.timestamp_millis())
+            .arg(&upload.id)
+            .query::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        let key = format!("{}{}", self.prefix_key, id);
+        let mut conn = self.conn.lock().unwrap();
+
+        redis::cmd("DEL").arg(key).query::<()>(&mut *conn)?;
+        redis::cmd("ZREM")
+            .arg(&self.index_key)
+            .arg(id)
+            .query::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    fn take_expired(&self, now: DateTime<Utc>) -> Result<Vec<ResumableUpload>, Box<dyn Error>> {
+        let ids: Vec<String> = redis::cmd("ZRANGEBYSCORE")
+            .arg(&self.index_key)
+            .arg("-inf")
+            .arg(now.timestamp_millis())
+            .query(&mut self.conn.lock().unwrap())?;
+
+        let mut uploads = Vec::new();
+        for id in ids {
+            // Another instance took it first.
+            let removed: u8 = redis::cmd("ZREM")
+                .arg(&self.index_key)
+                .arg(&id)
+                .query(&mut self.conn.lock().unwrap())?;
+            if removed == 0 {
+                continue;
+            }
+
+            if let Some(upload) = self.get(&id)? {
+                uploads.push(upload);
+            }
+
+            redis::cmd("DEL")
+                .arg(format!("{}{}", self.prefix_key, id))
+                .query::<()>(&mut self.conn.lock().unwrap())?;
+        }
+
+        Ok(uploads)
+    }
+
+    fn lock(&self, id: &str, lease: i64) -> Result<bool, Box<dyn Error>> {
+        let result: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{}", self.lock_prefix_key, id))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(lease)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        Ok(result.is_some())
+    }
+
+    fn unlock(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        redis::cmd("DEL")
+            .arg(format!("{}{}", self.lock_prefix_key, id))
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        Ok(())
+    }
+
+    fn for_project(&self, project: &str) -> Arc<dyn ResumableUploadStorage> {
+        Arc::new(Self::with_project(self.conn.clone(), project))
+    }
+}
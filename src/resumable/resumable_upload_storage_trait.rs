@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::resumable::ResumableUpload;
+
+pub trait ResumableUploadStorage: Send + Sync {
+    fn get(&self, id: &str) -> Result<Option<ResumableUpload>, Box<dyn Error>>;
+    fn save(&self, upload: &ResumableUpload) -> Result<(), Box<dyn Error>>;
+    fn delete(&self, id: &str) -> Result<(), Box<dyn Error>>;
+    /// Takes the uploads expired at `now` out of the storage, so that a single
+    /// caller removes the data of each of them.
+    fn take_expired(&self, now: DateTime<Utc>) -> Result<Vec<ResumableUpload>, Box<dyn Error>>;
+    /// Locks the upload for `lease` seconds. Returns false if it is already locked.
+    fn lock(&self, id: &str, lease: i64) -> Result<bool, Box<dyn Error>>;
+    fn unlock(&self, id: &str) -> Result<(), Box<dyn Error>>;
+    /// The storage of the uploads of `project`, kept apart from this one.
+    fn for_project(&self, project: &str) -> Arc<dyn ResumableUploadStorage>;
+}
@@ -1,11 +1,23 @@
+pub mod schedule;
+pub mod schedule_storage_memory;
+pub mod schedule_storage_redis;
+pub mod schedule_storage_trait;
 pub mod task;
 pub mod task_scheduler;
+pub mod task_storage_memory;
 pub mod task_storage_redis;
 pub mod task_storage_trait;
 pub mod thread_pool;
 
-pub use task::{Details, Task, TaskExecutor, TaskKind, TaskStatus};
+pub use schedule::{Schedule, ScheduledJob};
+pub use schedule_storage_memory::MemoryScheduleStorage;
+pub use schedule_storage_redis::RedisScheduleStorage;
+pub use schedule_storage_trait::ScheduleStorage;
+pub use task::{
+    BulkItemResult, BulkOperation, Details, Task, TaskExecutor, TaskFilter, TaskKind, TaskStatus,
+};
 pub use task_scheduler::TaskScheduler;
+pub use task_storage_memory::MemoryTaskStorage;
 pub use task_storage_redis::RedisTaskStorage;
 pub use task_storage_trait::TaskStorage;
 pub use thread_pool::ThreadPool;
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule as CronSchedule;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::{BulkOperation, Details, Task, TaskKind};
+use crate::media::path::normalize_folder;
+use crate::media::Path;
+use crate::project::default_project;
+
+/// What a schedule runs, with dates relative to each run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ScheduledJob {
+    ClearCache {
+        /// Keeps the derivatives created less than this many seconds before the
+        /// run, the older ones are cleared.
+        #[serde(default)]
+        older_than: i64,
+    },
+    BulkMedia {
+        operation: BulkOperation,
+        #[serde(default)]
+        paths: Vec<String>,
+        prefix: Option<String>,
+        destination: Option<String>,
+    },
+}
+
+impl ScheduledJob {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ScheduledJob::ClearCache { older_than } if *older_than < 0 => {
+                Err("older_than must not be negative".to_string())
+            }
+            ScheduledJob::ClearCache { .. } => Ok(()),
+            ScheduledJob::BulkMedia {
+                operation,
+                paths,
+                prefix,
+                destination,
+            } => {
+                if paths.is_empty() == prefix.is_none() {
+                    return Err("Either paths or prefix must be provided".to_string());
+                }
+                if *operation != BulkOperation::Delete && destination.is_none() {
+                    return Err("A destination is required to move or copy media".to_string());
+                }
+                for path in paths {
+                    Path::new(path).map_err(|e| format!("{}: {}", path, e))?;
+                }
+                for folder in prefix.iter().chain(destination.iter()) {
+                    normalize_folder(folder).map_err(|e| format!("{}: {}", folder, e))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn to_task(&self, project: String, now: DateTime<Utc>) -> Task {
+        match self {
+            ScheduledJob::ClearCache { older_than } => Task::new(
+                project,
+                TaskKind::ClearCache,
+                Details::ClearCache {
+                    before_date: now - Duration::seconds(*older_than),
+                },
+            ),
+            ScheduledJob::BulkMedia {
+                operation,
+                paths,
+                prefix,
+                destination,
+            } => Task::new(
+                project,
+                TaskKind::BulkMedia,
+                Details::BulkMedia {
+                    operation: *operation,
+                    paths: paths.clone(),
+                    prefix: prefix.clone(),
+                    destination: destination.clone(),
+                    results: Vec::new(),
+                },
+            ),
+        }
+    }
+}
+
+/// A job pushed as a task each time its cron expression matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub name: String,
+    #[serde(default = "default_project")]
+    pub project: String,
+    /// Either `min hour day month weekday` or the same prefixed with seconds,
+    /// evaluated in UTC.
+    pub cron: String,
+    pub job: ScheduledJob,
+    /// `None` once the expression matches no future date.
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_task_id: Option<String>,
+    /// Schedules of `config.toml` are synced at startup and cannot be changed
+    /// through the API.
+    #[serde(default)]
+    pub from_config: bool,
+}
+
+impl Schedule {
+    pub fn new(
+        name: String,
+        project: String,
+        cron: String,
+        job: ScheduledJob,
+        from_config: bool,
+    ) -> Result<Self, String> {
+        job.validate()?;
+        let next_run_at = next_run_after(&cron, Utc::now())?;
+
+        Ok(Self {
+            name,
+            project,
+            cron,
+            job,
+            next_run_at,
+            last_run_at: None,
+            last_task_id: None,
+            from_config,
+        })
+    }
+
+    /// Builds the task of the run due at `now` and moves the schedule to its next run.
+    pub fn run(&mut self, now: DateTime<Utc>) -> Result<Task, String> {
+        let task = self.job.to_task(self.project.clone(), now);
+
+        self.last_run_at = Some(now);
+        self.last_task_id = Some(task.id.clone());
+        self.next_run_at = next_run_after(&self.cron, now)?;
+
+        Ok(task)
+    }
+}
+
+/// The first date after `after` matching the cron expression.
+pub fn next_run_after(cron: &str, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+    // The cron crate expects seconds first.
+    let expression = match cron.split_whitespace().count() {
+        5 => format!("0 {}", cron),
+        _ => cron.to_string(),
+    };
+
+    let schedule = CronSchedule::from_str(&expression)
+        .map_err(|e| format!("Invalid cron expression {}: {}", cron, e))?;
+
+    Ok(schedule.after(&after).next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next_run_after() {
+        let after = Utc.with_ymd_and_hms(2024, 3, 10, 12, 30, 0).unwrap();
+
+        assert_eq!(
+            next_run_after("0 3 * * *", after).unwrap(),
+            Some(Utc.with_ymd_and_hms(2024, 3, 11, 3, 0, 0).unwrap())
+        );
+        assert_eq!(
+            next_run_after("0 */15 * * * *", after).unwrap(),
+            Some(Utc.with_ymd_and_hms(2024, 3, 10, 12, 45, 0).unwrap())
+        );
+        assert!(next_run_after("not a cron", after).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_traversal() {
+        let job = |prefix: &str, destination: &str| ScheduledJob::BulkMedia {
+            operation: BulkOperation::Move,
+            paths: Vec::new(),
+            prefix: Some(prefix.to_string()),
+            destination: Some(destination.to_string()),
+        };
+
+        assert!(job("/incoming/", "/archive").validate().is_ok());
+        assert!(job("/incoming/../secret", "/archive").validate().is_err());
+        assert!(job("/incoming", "/archive/../_projects/other").validate().is_err());
+    }
+
+    #[test]
+    fn test_clear_cache_keeps_derivatives_younger_than_older_than() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 12, 30, 0).unwrap();
+        let job = ScheduledJob::ClearCache { older_than: 3600 };
+
+        match job.to_task("default".to_string(), now).details {
+            Details::ClearCache { before_date } => {
+                assert_eq!(before_date, Utc.with_ymd_and_hms(2024, 3, 10, 11, 30, 0).unwrap())
+            }
+            details => panic!("Unexpected details {:?}", details),
+        }
+        assert!(ScheduledJob::ClearCache { older_than: -1 }.validate().is_err());
+    }
+}
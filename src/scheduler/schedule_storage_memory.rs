@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+
+use super::{Schedule, ScheduleStorage};
+
+#[derive(Default)]
+struct Schedules {
+    schedules: HashMap<(String, String), Schedule>,
+    /// When each schedule is due next, pushed back while it is claimed.
+    due: HashMap<(String, String), DateTime<Utc>>,
+}
+
+/// Keeps the schedules in memory, for development and tests. They are lost when
+/// the server stops.
+#[derive(Default)]
+pub struct MemoryScheduleStorage {
+    schedules: Mutex<Schedules>,
+}
+
+impl MemoryScheduleStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ScheduleStorage for MemoryScheduleStorage {
+    fn get_all(&self, project: Option<&str>) -> Result<Vec<Schedule>, Box<dyn Error>> {
+        let mut schedules: Vec<Schedule> = self
+            .schedules
+            .lock()
+            .unwrap()
+            .schedules
+            .values()
+            .filter(|schedule| project.map_or(true, |project| schedule.project == project))
+            .cloned()
+            .collect();
+        schedules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(schedules)
+    }
+
+    fn get(&self, project: &str, name: &str) -> Result<Option<Schedule>, Box<dyn Error>> {
+        let schedules = self.schedules.lock().unwrap();
+
+        Ok(schedules
+            .schedules
+            .get(&(project.to_string(), name.to_string()))
+            .cloned())
+    }
+
+    fn save(&self, schedule: &Schedule) -> Result<(), Box<dyn Error>> {
+        let id = (schedule.project.clone(), schedule.name.clone());
+        let mut schedules = self.schedules.lock().unwrap();
+
+        match schedule.next_run_at {
+            Some(next_run_at) => schedules.due.insert(id.clone(), next_run_at),
+            None => schedules.due.remove(&id),
+        };
+        schedules.schedules.insert(id, schedule.clone());
+
+        Ok(())
+    }
+
+    fn delete(&self, project: &str, name: &str) -> Result<(), Box<dyn Error>> {
+        let id = (project.to_string(), name.to_string());
+        let mut schedules = self.schedules.lock().unwrap();
+
+        schedules.schedules.remove(&id);
+        schedules.due.remove(&id);
+
+        Ok(())
+    }
+
+    fn claim_due(&self, now: DateTime<Utc>, lease: i64) -> Result<Vec<Schedule>, Box<dyn Error>> {
+        let mut schedules = self.schedules.lock().unwrap();
+        let Schedules { schedules, due } = &mut *schedules;
+
+        let mut claimed = Vec::new();
+        for (id, next_run_at) in due.iter_mut() {
+            if *next_run_at > now {
+                continue;
+            }
+
+            *next_run_at = now + Duration::seconds(lease);
+            if let Some(schedule) = schedules.get(id) {
+                claimed.push(schedule.clone());
+            }
+        }
+
+        Ok(claimed)
+    }
+}
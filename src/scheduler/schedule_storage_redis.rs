@@ -0,0 +1,142 @@
+use chrono::{DateTime, Duration, Utc};
+use redis::{Connection, Script};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use super::{Schedule, ScheduleStorage};
+
+const SCHEDULES_KEY: &str = "internal:schedules";
+/// Ids of the schedules, scored by their next run.
+const DUE_SCHEDULES_KEY: &str = "internal:schedules:due";
+
+/// Pushes the due schedules back by the lease, atomically so that concurrent
+/// instances cannot claim the same run.
+const CLAIM_DUE_SCRIPT: &str = r"
+local ids = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1])
+for _, id in ipairs(ids) do
+    redis.call('ZADD', KEYS[1], 'XX', ARGV[2], id)
+end
+return ids
+";
+
+pub struct RedisScheduleStorage {
+    conn: Arc<Mutex<Connection>>,
+    claim_due_script: Script,
+}
+
+impl RedisScheduleStorage {
+    pub fn new(conn: Connection) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+            claim_due_script: Script::new(CLAIM_DUE_SCRIPT),
+        }
+    }
+
+    /// Names are unique within a project, which cannot contain a colon.
+    fn schedule_id(project: &str, name: &str) -> String {
+        format!("{}:{}", project, name)
+    }
+}
+
+impl ScheduleStorage for RedisScheduleStorage {
+    fn get_all(&self, project: Option<&str>) -> Result<Vec<Schedule>, Box<dyn Error>> {
+        let schedules: Vec<String> = redis::cmd("HVALS")
+            .arg(SCHEDULES_KEY)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        let mut schedules = schedules
+            .iter()
+            .map(|schedule| serde_json::from_str(schedule))
+            .collect::<Result<Vec<Schedule>, _>>()?;
+        schedules.retain(|schedule| project.map_or(true, |project| schedule.project == project));
+        schedules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(schedules)
+    }
+
+    fn get(&self, project: &str, name: &str) -> Result<Option<Schedule>, Box<dyn Error>> {
+        let result: Option<String> = redis::cmd("HGET")
+            .arg(SCHEDULES_KEY)
+            .arg(Self::schedule_id(project, name))
+            .query(&mut self.conn.lock().unwrap())?;
+
+        match result {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, schedule: &Schedule) -> Result<(), Box<dyn Error>> {
+        let id = Self::schedule_id(&schedule.project, &schedule.name);
+        let mut conn = self.conn.lock().unwrap();
+
+        redis::cmd("HSET")
+            .arg(SCHEDULES_KEY)
+            .arg(&id)
+            .arg(serde_json::to_string(schedule)?)
+            .query::<()>(&mut *conn)?;
+
+        match schedule.next_run_at {
+            Some(next_run_at) => redis::cmd("ZADD")
+                .arg(DUE_SCHEDULES_KEY)
+                .arg(next_run_at.timestamp_millis())
+                .arg(&id)
+                .query(&mut *conn)?,
+            None => redis::cmd("ZREM")
+                .arg(DUE_SCHEDULES_KEY)
+                .arg(&id)
+                .query(&mut *conn)?,
+        }
+
+        Ok(())
+    }
+
+    fn delete(&self, project: &str, name: &str) -> Result<(), Box<dyn Error>> {
+        let id = Self::schedule_id(project, name);
+        let mut conn = self.conn.lock().unwrap();
+
+        redis::cmd("HDEL")
+            .arg(SCHEDULES_KEY)
+            .arg(&id)
+            .query::<()>(&mut *conn)?;
+        redis::cmd("ZREM")
+            .arg(DUE_SCHEDULES_KEY)
+            .arg(&id)
+            .query::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    fn claim_due(&self, now: DateTime<Utc>, lease: i64) -> Result<Vec<Schedule>, Box<dyn Error>> {
+        let mut conn = self.conn.lock().unwrap();
+
+        let ids: Vec<String> = self
+            .claim_due_script
+            .key(DUE_SCHEDULES_KEY)
+            .arg(now.timestamp_millis())
+            .arg((now + Duration::seconds(lease)).timestamp_millis())
+            .invoke(&mut *conn)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let results: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(SCHEDULES_KEY)
+            .arg(&ids)
+            .query(&mut *conn)?;
+
+        let mut schedules = Vec::new();
+        for (id, result) in ids.iter().zip(results) {
+            match result {
+                Some(json) => schedules.push(serde_json::from_str(&json)?),
+                // Deleted between the two commands.
+                None => redis::cmd("ZREM")
+                    .arg(DUE_SCHEDULES_KEY)
+                    .arg(id)
+                    .query(&mut *conn)?,
+            }
+        }
+
+        Ok(schedules)
+    }
+}
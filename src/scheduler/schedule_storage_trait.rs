@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use std::error::Error;
+
+use super::Schedule;
+
+pub trait ScheduleStorage: Send + Sync {
+    /// The schedules of `project`, or of every project.
+    fn get_all(&self, project: Option<&str>) -> Result<Vec<Schedule>, Box<dyn Error>>;
+    fn get(&self, project: &str, name: &str) -> Result<Option<Schedule>, Box<dyn Error>>;
+    fn save(&self, schedule: &Schedule) -> Result<(), Box<dyn Error>>;
+    fn delete(&self, project: &str, name: &str) -> Result<(), Box<dyn Error>>;
+    /// Returns the schedules due at `now`, which no other caller gets for
+    /// `lease` seconds. Saving a schedule releases it.
+    fn claim_due(&self, now: DateTime<Utc>, lease: i64) -> Result<Vec<Schedule>, Box<dyn Error>>;
+}
@@ -3,44 +3,206 @@ use serde::{Deserialize, Serialize};
 use std::error::Error;
 use async_trait::async_trait;
 
+use crate::project::default_project;
+use crate::webhook::WebhookEvent;
+
 #[async_trait]
 pub trait TaskExecutor: Send + Sync {
     async fn run(&self, task: Task) -> Result<Task, Box<dyn Error>>;
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum TaskStatus {
     Queued,
+    Running,
     Completed,
+    /// Out of attempts, the task waits in the dead-letter queue to be requeued.
+    Failed,
+    Cancelled,
+}
+
+impl TaskStatus {
+    /// Finished tasks are no longer run.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
+        )
+    }
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Hash, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Hash, Eq)]
 pub enum TaskKind {
     ClearCache,
+    BulkMedia,
+    WebhookDelivery,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Details {
     ClearCache { before_date: DateTime<Utc> },
+    BulkMedia {
+        operation: BulkOperation,
+        /// Explicit media paths, or every media under `prefix` when empty.
+        paths: Vec<String>,
+        prefix: Option<String>,
+        /// Destination folder of a move or copy.
+        destination: Option<String>,
+        #[serde(default)]
+        results: Vec<BulkItemResult>,
+    },
+    WebhookDelivery {
+        webhook: String,
+        delivery_id: String,
+        event: WebhookEvent,
+        /// The signed JSON body, identical across attempts.
+        payload: String,
+        /// Failed attempts so far.
+        attempt: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOperation {
+    Delete,
+    Move,
+    Copy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkItemResult {
+    pub path: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStatusChange {
+    pub status: TaskStatus,
+    pub at: DateTime<Utc>,
+}
+
+/// Counters reported by long-running executors.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub processed: u64,
+    /// Unknown until the executor has listed everything it has to process.
+    pub total: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
+    #[serde(default = "default_project")]
+    pub project: String,
     pub status: TaskStatus,
     pub details: Details,
     pub kind: TaskKind,
     pub error: Option<String>,
+    /// Runs of the executor since the task last made progress.
+    #[serde(default)]
+    pub attempts: u32,
+    /// The task waits in the delayed queue until then.
+    #[serde(default)]
+    pub run_at: Option<DateTime<Utc>>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub history: Vec<TaskStatusChange>,
+    #[serde(default)]
+    pub progress: TaskProgress,
 }
 
 impl Task {
-    pub fn new(kind: TaskKind, details: Details) -> Self {
+    pub fn new(project: String, kind: TaskKind, details: Details) -> Self {
+        let now = Utc::now();
+
         Self {
             id: uuid::Uuid::new_v4().to_string(),
+            project,
             status: TaskStatus::Queued,
             details,
             kind,
             error: None,
+            attempts: 0,
+            run_at: None,
+            created_at: now,
+            updated_at: now,
+            history: vec![TaskStatusChange {
+                status: TaskStatus::Queued,
+                at: now,
+            }],
+            progress: TaskProgress::default(),
         }
     }
+
+    /// Delays the task until `run_at`, if any.
+    pub fn with_run_at(self, run_at: Option<DateTime<Utc>>) -> Self {
+        Self { run_at, ..self }
+    }
+
+    /// Records the change in the history of the task, unless it already has `status`.
+    pub fn set_status(&mut self, status: TaskStatus) {
+        self.updated_at = Utc::now();
+
+        if self.status != status {
+            self.status = status;
+            self.history.push(TaskStatusChange {
+                status,
+                at: self.updated_at,
+            });
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.run_at.map_or(true, |at| at <= Utc::now())
+    }
+}
+
+/// Criteria of a task listing, `None` matching anything.
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    pub project: Option<String>,
+    pub status: Option<TaskStatus>,
+    pub kind: Option<TaskKind>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        self.project.as_ref().map_or(true, |project| *project == task.project)
+            && self.status.map_or(true, |status| status == task.status)
+            && self.kind.map_or(true, |kind| kind == task.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_status_records_changes_only() {
+        let mut task = Task::new(
+            "default".to_string(),
+            TaskKind::ClearCache,
+            Details::ClearCache {
+                before_date: Utc::now(),
+            },
+        );
+
+        task.set_status(TaskStatus::Queued);
+        task.set_status(TaskStatus::Cancelled);
+
+        let statuses: Vec<TaskStatus> = task.history.iter().map(|change| change.status).collect();
+        assert_eq!(statuses, vec![TaskStatus::Queued, TaskStatus::Cancelled]);
+        assert_eq!(task.status, TaskStatus::Cancelled);
+    }
 }
@@ -1,24 +1,42 @@
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
 use std::collections::HashMap;
 use std::error::Error;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::time::sleep;
-use super::{Task, TaskExecutor, TaskKind, TaskStorage};
+use super::{Schedule, ScheduleStorage, Task, TaskExecutor, TaskKind, TaskStatus, TaskStorage};
+use crate::config::TaskConfig;
+
+/// Seconds a due schedule is held by the instance pushing its task.
+const SCHEDULE_LEASE: i64 = 60;
 
 pub struct TaskScheduler {
     should_stop: Arc<AtomicBool>,
     task_storage: Arc<dyn TaskStorage>,
+    schedule_storage: Arc<dyn ScheduleStorage>,
     task_executors: HashMap<TaskKind, Arc<dyn TaskExecutor>>,
+    /// One per task running on this instance.
+    permits: Arc<Semaphore>,
+    config: TaskConfig,
 }
 
 impl TaskScheduler {
-    pub fn new(task_storage: Arc<dyn TaskStorage>) -> Self {
+    pub fn new(
+        task_storage: Arc<dyn TaskStorage>,
+        schedule_storage: Arc<dyn ScheduleStorage>,
+        config: TaskConfig,
+    ) -> Self {
         Self {
             should_stop: Arc::new(AtomicBool::new(false)),
             task_storage,
+            schedule_storage,
             task_executors: HashMap::new(),
+            permits: Arc::new(Semaphore::new(config.max_concurrent_tasks.max(1))),
+            config,
         }
     }
 
@@ -34,42 +52,253 @@ impl TaskScheduler {
         self.task_storage.push(task)
     }
 
+    pub fn storage(&self) -> &Arc<dyn TaskStorage> {
+        &self.task_storage
+    }
+
+    pub fn schedules(&self) -> &Arc<dyn ScheduleStorage> {
+        &self.schedule_storage
+    }
+
+    /// Replaces the schedules defined in `config.toml`, keeping the next run of
+    /// those left unchanged, and deletes the ones removed from it.
+    pub fn sync_config_schedules(&self) -> Result<(), Box<dyn Error>> {
+        for schedule_config in &self.config.schedules {
+            let existing = self
+                .schedule_storage
+                .get(&schedule_config.project, &schedule_config.name)?;
+
+            let unchanged = existing.as_ref().is_some_and(|existing| {
+                existing.from_config
+                    && existing.cron == schedule_config.cron
+                    && serde_json::to_value(&existing.job).ok()
+                        == serde_json::to_value(&schedule_config.job).ok()
+            });
+            if unchanged {
+                continue;
+            }
+
+            let schedule = Schedule::new(
+                schedule_config.name.clone(),
+                schedule_config.project.clone(),
+                schedule_config.cron.clone(),
+                schedule_config.job.clone(),
+                true,
+            )
+            .map_err(|e| format!("Schedule {}: {}", schedule_config.name, e))?;
+            self.schedule_storage.save(&schedule)?;
+        }
+
+        for schedule in self.schedule_storage.get_all(None)? {
+            let removed = schedule.from_config
+                && !self.config.schedules.iter().any(|schedule_config| {
+                    schedule_config.project == schedule.project
+                        && schedule_config.name == schedule.name
+                });
+            if removed {
+                self.schedule_storage.delete(&schedule.project, &schedule.name)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs again the tasks of instances that stopped sending heartbeats, as a
+    /// failed attempt.
+    fn reclaim_expired_leases(&self) -> Result<(), Box<dyn Error>> {
+        let tasks = self
+            .task_storage
+            .claim_expired_leases(Utc::now(), self.config.lease_timeout)?;
+
+        for task in tasks {
+            if task.status.is_finished() {
+                self.task_storage.release(&task.id)?;
+                continue;
+            }
+
+            warn!("Reclaiming task {} whose lease expired", task.id);
+            let task = retry_or_fail(task, "Task lease expired".to_string(), &self.config);
+            self.task_storage.push(task)?;
+        }
+
+        Ok(())
+    }
+
+    /// Queues the delayed tasks and the runs of the schedules that are due.
+    fn promote_due(&self) -> Result<(), Box<dyn Error>> {
+        let now = Utc::now();
+
+        self.task_storage.promote_due(now)?;
+
+        for mut schedule in self.schedule_storage.claim_due(now, SCHEDULE_LEASE)? {
+            match schedule.run(now) {
+                Ok(task) => self.task_storage.push(task)?,
+                Err(e) => {
+                    error!("Failed to run schedule {}: {}", schedule.name, e);
+                    schedule.next_run_at = None;
+                }
+            }
+
+            self.schedule_storage.save(&schedule)?;
+        }
+
+        Ok(())
+    }
+
     pub fn stop(&self) {
-        println!("Shutting down scheduler");
+        info!("Shutting down scheduler");
         self.should_stop.store(true, Ordering::Relaxed);
     }
 
     pub async fn run(&self) {
         while !self.should_stop.load(Ordering::Relaxed) {
-            match self.task_storage.pop_queued().unwrap() {
-                Some(task) => {
-                    let task_executor = self.task_executors.get(&task.kind).unwrap().clone();
-                    let task_storage = self.task_storage.clone();
-
-                    tokio::spawn(async move {
-                        match task_executor.run(task).await {
-                            Ok(result) => {
-                                if let Err(e) = task_storage.push(result) {
-                                    println!("Failed to push task result: {}", e);
-                                }
-                            }
-                            Err(e) => {
-                                println!("Failed to run task: {}", e);
-                            }
-                        }
-                    });
+            if let Err(e) = self.promote_due() {
+                error!("Failed to promote due tasks: {}", e);
+            }
+
+            if let Err(e) = self.reclaim_expired_leases() {
+                error!("Failed to reclaim expired leases: {}", e);
+            }
+
+            // Taken before leasing, so that no leased task waits for a free slot.
+            let permit = match self.permits.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+
+            let popped = self
+                .task_storage
+                .pop_queued(self.config.lease_timeout)
+                .map_err(|e| e.to_string());
+
+            match popped {
+                Ok(Some(task)) => {
+                    if let Err(e) = self.dispatch(task, permit) {
+                        error!("Failed to dispatch task: {}", e);
+                    }
                 }
-                None => {
+                Ok(None) => {
                     // No more tasks to run, sleep for a while before checking again
                     sleep(std::time::Duration::from_secs(1)).await;
                 }
+                Err(e) => {
+                    error!("Failed to pop queued task: {}", e);
+                    sleep(std::time::Duration::from_secs(1)).await;
+                }
             }
+        }
+    }
 
-            sleep(std::time::Duration::from_secs(1)).await;
+    /// `permit` is held until the task is done.
+    fn dispatch(&self, mut task: Task, permit: OwnedSemaphorePermit) -> Result<(), Box<dyn Error>> {
+        if !task.is_due() {
+            return self.task_storage.push(task);
         }
+
+        let task_executor = match self.task_executors.get(&task.kind) {
+            Some(task_executor) => task_executor.clone(),
+            None => {
+                task.error = Some(format!("No executor registered for {:?} tasks", task.kind));
+                task.set_status(TaskStatus::Failed);
+                return self.task_storage.push(task);
+            }
+        };
+
+        task.attempts += 1;
+        task.run_at = None;
+        task.error = None;
+        task.set_status(TaskStatus::Running);
+        self.task_storage.save(&task)?;
+
+        let task_storage = self.task_storage.clone();
+        let config = self.config.clone();
+
+        tokio::spawn(async move {
+            // Run apart so that a panicking executor fails the task like an error.
+            let running = task.clone();
+            let mut run = tokio::spawn(async move {
+                task_executor.run(running).await.map_err(|e| e.to_string())
+            });
+
+            // Keeps the lease while the executor runs, past it the task is reclaimed.
+            let mut heartbeat = tokio::time::interval(std::time::Duration::from_secs(
+                (config.lease_timeout / 3).max(1) as u64,
+            ));
+            heartbeat.tick().await;
+
+            let result = loop {
+                tokio::select! {
+                    result = &mut run => {
+                        break result
+                            .unwrap_or_else(|e| Err(format!("Task executor panicked: {}", e)));
+                    }
+                    _ = heartbeat.tick() => {
+                        let extended = task_storage
+                            .extend_lease(&task.id, config.lease_timeout)
+                            .map_err(|e| e.to_string());
+                        match extended {
+                            Ok(true) => {}
+                            Ok(false) => warn!("Lost the lease of task {}", task.id),
+                            Err(e) => {
+                                error!("Failed to extend the lease of task {}: {}", task.id, e)
+                            }
+                        }
+                    }
+                }
+            };
+
+            let result = match result {
+                Ok(result) => made_progress(result),
+                Err(e) => retry_or_fail(task, e, &config),
+            };
+
+            if let Err(e) = push_result(task_storage.as_ref(), result) {
+                error!("Failed to push task result: {}", e);
+            }
+
+            drop(permit);
+        });
+
+        Ok(())
     }
 }
 
+/// A task returned unfinished runs again from the queue with its attempts reset.
+fn made_progress(mut result: Task) -> Task {
+    if !result.status.is_finished() {
+        result.attempts = 0;
+        result.set_status(TaskStatus::Queued);
+    }
+
+    result
+}
+
+/// Queues the task again after a backoff, or fails it once out of attempts.
+fn retry_or_fail(mut task: Task, error: String, config: &TaskConfig) -> Task {
+    task.error = Some(error);
+
+    if task.attempts < config.max_attempts {
+        task.run_at = Some(Utc::now() + Duration::seconds(config.backoff(task.attempts)));
+        task.set_status(TaskStatus::Queued);
+    } else {
+        task.set_status(TaskStatus::Failed);
+    }
+
+    task
+}
+
+/// Pushes what the executor returned, unless the task was cancelled while it ran.
+fn push_result(task_storage: &dyn TaskStorage, result: Task) -> Result<(), Box<dyn Error>> {
+    let cancelled = task_storage
+        .get(&result.id)?
+        .is_some_and(|task| task.status == TaskStatus::Cancelled);
+    if cancelled {
+        return task_storage.release(&result.id);
+    }
+
+    task_storage.push(result)
+}
+
 impl Drop for TaskScheduler {
     fn drop(&mut self) {
         self.stop();
@@ -78,9 +307,15 @@ impl Drop for TaskScheduler {
 
 pub fn run_scheduler(
     task_storage: Arc<dyn TaskStorage>,
+    schedule_storage: Arc<dyn ScheduleStorage>,
     task_executors: HashMap<TaskKind, Arc<dyn TaskExecutor>>,
+    config: TaskConfig,
 ) -> Arc<TaskScheduler> {
-    let mut task_scheduler = TaskScheduler::new(Arc::clone(&task_storage));
+    let mut task_scheduler =
+        TaskScheduler::new(Arc::clone(&task_storage), schedule_storage, config);
+    task_scheduler
+        .sync_config_schedules()
+        .expect("Error syncing the schedules of config.toml");
 
     for (task_kind, task_executor) in task_executors {
         task_scheduler.register_task_executor(task_kind, task_executor);
@@ -95,3 +330,61 @@ pub fn run_scheduler(
 
     task_scheduler
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::{Details, MemoryScheduleStorage, MemoryTaskStorage};
+
+    #[test]
+    fn test_retry_or_fail() {
+        let config = TaskConfig::default();
+        let mut task = Task::new(
+            "default".to_string(),
+            TaskKind::ClearCache,
+            Details::ClearCache {
+                before_date: Utc::now(),
+            },
+        );
+
+        task.attempts = 1;
+        let task = retry_or_fail(task, "S3 unavailable".to_string(), &config);
+        assert_eq!(task.status, TaskStatus::Queued);
+        assert_eq!(task.error.as_deref(), Some("S3 unavailable"));
+        assert!(!task.is_due());
+
+        let mut task = task;
+        task.attempts = config.max_attempts;
+        let task = retry_or_fail(task, "S3 unavailable".to_string(), &config);
+        assert_eq!(task.status, TaskStatus::Failed);
+    }
+
+    #[test]
+    fn test_reclaims_tasks_whose_lease_expired() {
+        let task_storage = Arc::new(MemoryTaskStorage::new(60));
+        let scheduler = TaskScheduler::new(
+            task_storage.clone(),
+            Arc::new(MemoryScheduleStorage::new()),
+            TaskConfig::default(),
+        );
+
+        let task = Task::new(
+            "default".to_string(),
+            TaskKind::ClearCache,
+            Details::ClearCache {
+                before_date: Utc::now(),
+            },
+        );
+        scheduler.push(task).unwrap();
+
+        // Leased by an instance that stopped before running it.
+        let task = task_storage.pop_queued(-1).unwrap().unwrap();
+        scheduler.reclaim_expired_leases().unwrap();
+
+        let reclaimed = task_storage.get(&task.id).unwrap().unwrap();
+        assert_eq!(reclaimed.status, TaskStatus::Queued);
+        assert_eq!(reclaimed.error.as_deref(), Some("Task lease expired"));
+        assert!(!reclaimed.is_due());
+        assert!(!task_storage.extend_lease(&task.id, 60).unwrap());
+    }
+}
@@ -0,0 +1,266 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::sync::Mutex;
+
+use super::{Task, TaskFilter, TaskStatus, TaskStorage};
+
+#[derive(Default)]
+struct Queues {
+    tasks: HashMap<String, Task>,
+    /// When the completed and cancelled tasks expire.
+    expiries: HashMap<String, DateTime<Utc>>,
+    queued: VecDeque<String>,
+    dead_letter: VecDeque<String>,
+    /// Ids of the tasks waiting for their `run_at`.
+    delayed: HashMap<String, DateTime<Utc>>,
+    /// Ids of the tasks being run, with the expiry of their lease.
+    leased: HashMap<String, DateTime<Utc>>,
+}
+
+impl Queues {
+    fn get(&self, id: &str, now: DateTime<Utc>) -> Option<&Task> {
+        match self.expiries.get(id) {
+            Some(expiry) if *expiry <= now => None,
+            _ => self.tasks.get(id),
+        }
+    }
+
+    fn remove_expired(&mut self, now: DateTime<Utc>) {
+        let expired: Vec<String> = self
+            .expiries
+            .iter()
+            .filter(|(_, expiry)| **expiry <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in expired {
+            self.expiries.remove(&id);
+            self.tasks.remove(&id);
+        }
+    }
+}
+
+/// Keeps the tasks and their queues in memory, for development and tests. They
+/// are lost when the server stops.
+pub struct MemoryTaskStorage {
+    queues: Mutex<Queues>,
+    /// Seconds completed and cancelled tasks are kept. Failed tasks stay until
+    /// they are requeued.
+    retention: i64,
+}
+
+impl MemoryTaskStorage {
+    pub fn new(retention: i64) -> Self {
+        Self {
+            queues: Mutex::default(),
+            retention,
+        }
+    }
+}
+
+impl TaskStorage for MemoryTaskStorage {
+    fn push(&self, task: Task) -> Result<(), Box<dyn Error>> {
+        self.save(&task)?;
+
+        let mut queues = self.queues.lock().unwrap();
+        queues.leased.remove(&task.id);
+
+        if task.status == TaskStatus::Queued && !task.is_due() {
+            if let Some(run_at) = task.run_at {
+                queues.delayed.insert(task.id, run_at);
+                return Ok(());
+            }
+        }
+
+        match task.status {
+            TaskStatus::Queued => queues.queued.push_back(task.id),
+            TaskStatus::Failed => queues.dead_letter.push_back(task.id),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn promote_due(&self, now: DateTime<Utc>) -> Result<usize, Box<dyn Error>> {
+        let mut queues = self.queues.lock().unwrap();
+
+        let mut due: Vec<(String, DateTime<Utc>)> = queues
+            .delayed
+            .iter()
+            .filter(|(_, run_at)| **run_at <= now)
+            .map(|(id, run_at)| (id.clone(), *run_at))
+            .collect();
+        due.sort_by_key(|(_, run_at)| *run_at);
+
+        for (id, _) in &due {
+            queues.delayed.remove(id);
+            queues.queued.push_back(id.clone());
+        }
+
+        Ok(due.len())
+    }
+
+    fn save(&self, task: &Task) -> Result<(), Box<dyn Error>> {
+        let now = Utc::now();
+        let mut queues = self.queues.lock().unwrap();
+        queues.remove_expired(now);
+
+        if matches!(task.status, TaskStatus::Completed | TaskStatus::Cancelled) {
+            let expiry = now + Duration::seconds(self.retention);
+            queues.expiries.insert(task.id.clone(), expiry);
+        } else {
+            queues.expiries.remove(&task.id);
+        }
+        queues.tasks.insert(task.id.clone(), task.clone());
+
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Task>, Box<dyn Error>> {
+        let queues = self.queues.lock().unwrap();
+
+        Ok(queues.get(id, Utc::now()).cloned())
+    }
+
+    fn list(&self, filter: &TaskFilter, limit: usize) -> Result<Vec<Task>, Box<dyn Error>> {
+        let now = Utc::now();
+        let queues = self.queues.lock().unwrap();
+
+        let mut tasks: Vec<&Task> = queues
+            .tasks
+            .keys()
+            .filter_map(|id| queues.get(id, now))
+            .filter(|task| filter.matches(task))
+            .collect();
+        tasks.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        Ok(tasks.into_iter().take(limit).cloned().collect())
+    }
+
+    fn pop_queued(&self, lease: i64) -> Result<Option<Task>, Box<dyn Error>> {
+        let now = Utc::now();
+        let mut queues = self.queues.lock().unwrap();
+
+        while let Some(id) = queues.queued.pop_front() {
+            // Cancelled tasks are skipped.
+            let task = match queues.get(&id, now) {
+                Some(task) if task.status == TaskStatus::Queued => task.clone(),
+                _ => continue,
+            };
+
+            queues.leased.insert(id, now + Duration::seconds(lease));
+            return Ok(Some(task));
+        }
+
+        Ok(None)
+    }
+
+    fn extend_lease(&self, id: &str, lease: i64) -> Result<bool, Box<dyn Error>> {
+        let mut queues = self.queues.lock().unwrap();
+
+        match queues.leased.get_mut(id) {
+            Some(expiry) => {
+                *expiry = Utc::now() + Duration::seconds(lease);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn release(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        self.queues.lock().unwrap().leased.remove(id);
+
+        Ok(())
+    }
+
+    fn claim_expired_leases(
+        &self,
+        now: DateTime<Utc>,
+        lease: i64,
+    ) -> Result<Vec<Task>, Box<dyn Error>> {
+        let mut queues = self.queues.lock().unwrap();
+
+        let expired: Vec<String> = queues
+            .leased
+            .iter()
+            .filter(|(_, expiry)| **expiry <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut tasks = Vec::new();
+        for id in expired {
+            match queues.get(&id, now).cloned() {
+                Some(task) => {
+                    queues.leased.insert(id, now + Duration::seconds(lease));
+                    tasks.push(task);
+                }
+                // Expired after the retention period.
+                None => {
+                    queues.leased.remove(&id);
+                }
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    fn list_dead_letters(
+        &self,
+        filter: &TaskFilter,
+        limit: usize,
+    ) -> Result<Vec<Task>, Box<dyn Error>> {
+        let now = Utc::now();
+        let queues = self.queues.lock().unwrap();
+
+        Ok(queues
+            .dead_letter
+            .iter()
+            .filter_map(|id| queues.get(id, now))
+            .filter(|task| filter.matches(task))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn requeue(&self, task: Task) -> Result<(), Box<dyn Error>> {
+        self.queues
+            .lock()
+            .unwrap()
+            .dead_letter
+            .retain(|id| *id != task.id);
+
+        self.push(task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::DEFAULT_PROJECT;
+    use crate::scheduler::{Details, TaskKind};
+
+    #[test]
+    fn test_leases_and_delays_queued_tasks() {
+        let storage = MemoryTaskStorage::new(60);
+        let now = Utc::now();
+
+        let details = Details::ClearCache { before_date: now };
+        let delayed = Task::new(DEFAULT_PROJECT.to_string(), TaskKind::ClearCache, details)
+            .with_run_at(Some(now + Duration::hours(1)));
+        storage.push(delayed).unwrap();
+        assert!(storage.pop_queued(60).unwrap().is_none());
+        assert_eq!(storage.promote_due(now + Duration::hours(2)).unwrap(), 1);
+
+        let task = storage.pop_queued(-1).unwrap().unwrap();
+        assert!(storage.pop_queued(60).unwrap().is_none());
+
+        let reclaimed = storage.claim_expired_leases(Utc::now(), 60).unwrap();
+        assert_eq!(reclaimed.len(), 1);
+        assert_eq!(reclaimed[0].id, task.id);
+        assert!(storage.extend_lease(&task.id, 60).unwrap());
+
+        storage.release(&task.id).unwrap();
+        assert!(!storage.extend_lease(&task.id, 60).unwrap());
+    }
+}
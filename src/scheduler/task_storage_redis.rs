@@ -1,50 +1,433 @@
-use redis::Connection;
+use chrono::{DateTime, Duration, Utc};
+use log::error;
+use redis::{Connection, Script};
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 
-use super::{Task, TaskStatus, TaskStorage};
+use super::{Task, TaskFilter, TaskStatus, TaskStorage};
 
 const QUEUED_TASKS_QUEUE_KEY: &str = "internal:queue:tasks:queued";
-const COMPLETED_TASKS_QUEUE_KEY: &str = "internal:queue:tasks:completed";
+const DEAD_LETTER_QUEUE_KEY: &str = "internal:queue:tasks:dead_letter";
+/// Ids of the tasks waiting for their `run_at`, scored by it.
+const DELAYED_TASKS_QUEUE_KEY: &str = "internal:queue:tasks:delayed";
+/// Ids of the tasks being run, scored by the expiry of their lease.
+const LEASED_TASKS_KEY: &str = "internal:queue:tasks:leased";
+const TASK_PREFIX_KEY: &str = "internal:task:";
+/// Ids of the tasks, scored by creation date.
+const TASKS_INDEX_KEY: &str = "internal:tasks";
+const LIST_PAGE_SIZE: isize = 100;
+
+/// Queues a task, saves it and drops its lease in one step, so that a task saved
+/// as queued is never missing from its queue. `KEYS[4]` is the queue, absent for
+/// the finished tasks, and a delayed task is scored by its `run_at` in `ARGV[4]`.
+const PUSH_SCRIPT: &str = r"
+if KEYS[4] then
+    if ARGV[4] ~= '' then
+        redis.call('ZADD', KEYS[4], ARGV[4], ARGV[5])
+    else
+        redis.call('RPUSH', KEYS[4], ARGV[5])
+    end
+end
+if ARGV[3] ~= '0' then
+    redis.call('SET', KEYS[1], ARGV[1], 'EX', ARGV[3])
+else
+    redis.call('SET', KEYS[1], ARGV[1])
+end
+redis.call('ZADD', KEYS[2], ARGV[2], ARGV[5])
+redis.call('ZREM', KEYS[3], ARGV[5])
+return 1
+";
+
+/// Replaces an entry of a list in place, keeping its position.
+const REPLACE_ENTRY_SCRIPT: &str = r"
+local entries = redis.call('LRANGE', KEYS[1], 0, -1)
+for i, entry in ipairs(entries) do
+    if entry == ARGV[1] then
+        redis.call('LSET', KEYS[1], i - 1, ARGV[2])
+        return 1
+    end
+end
+return 0
+";
+
+/// Moves the due delayed tasks to the queue, atomically so that concurrent
+/// instances cannot queue a task twice.
+const PROMOTE_DUE_SCRIPT: &str = r"
+local ids = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1])
+for _, id in ipairs(ids) do
+    redis.call('ZREM', KEYS[1], id)
+    redis.call('RPUSH', KEYS[2], id)
+end
+return #ids
+";
+
+/// Pops the next queued task and leases it in one step, so that a task is never
+/// out of both the queue and the leases.
+const POP_AND_LEASE_SCRIPT: &str = r"
+local id = redis.call('LPOP', KEYS[1])
+if id then
+    redis.call('ZADD', KEYS[2], ARGV[1], id)
+end
+return id
+";
+
+/// Extends a lease only if it is still held.
+const EXTEND_LEASE_SCRIPT: &str = r"
+if redis.call('ZSCORE', KEYS[1], ARGV[1]) then
+    redis.call('ZADD', KEYS[1], ARGV[2], ARGV[1])
+    return 1
+end
+return 0
+";
+
+/// Leases the expired leases again, atomically so that a single instance
+/// reclaims each task.
+const CLAIM_EXPIRED_LEASES_SCRIPT: &str = r"
+local ids = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1])
+for _, id in ipairs(ids) do
+    redis.call('ZADD', KEYS[1], 'XX', ARGV[2], id)
+end
+return ids
+";
 
 pub struct RedisTaskStorage {
     conn: Arc<Mutex<Connection>>,
+    /// Seconds completed and cancelled tasks are kept. Failed tasks stay until
+    /// they are requeued.
+    retention: i64,
+    push_script: Script,
+    replace_entry_script: Script,
+    promote_due_script: Script,
+    pop_and_lease_script: Script,
+    extend_lease_script: Script,
+    claim_expired_leases_script: Script,
 }
 
 impl RedisTaskStorage {
-    pub fn new(conn: Connection) -> Self {
-        Self {
+    pub fn new(conn: Connection, retention: i64) -> Result<Self, Box<dyn Error>> {
+        let storage = Self {
             conn: Arc::new(Mutex::new(conn)),
+            retention,
+            push_script: Script::new(PUSH_SCRIPT),
+            replace_entry_script: Script::new(REPLACE_ENTRY_SCRIPT),
+            promote_due_script: Script::new(PROMOTE_DUE_SCRIPT),
+            pop_and_lease_script: Script::new(POP_AND_LEASE_SCRIPT),
+            extend_lease_script: Script::new(EXTEND_LEASE_SCRIPT),
+            claim_expired_leases_script: Script::new(CLAIM_EXPIRED_LEASES_SCRIPT),
+        };
+        storage.init()?;
+        Ok(storage)
+    }
+
+    /// Replaces the whole tasks that databases predating the task keys kept in
+    /// the queue by their ids, saving each task under its key.
+    fn init(&self) -> Result<(), Box<dyn Error>> {
+        let entries: Vec<String> = redis::cmd("LRANGE")
+            .arg(QUEUED_TASKS_QUEUE_KEY)
+            .arg(0)
+            .arg(-1)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        for entry in entries.iter().filter(|entry| is_legacy_entry(entry)) {
+            let task: Task = match serde_json::from_str(entry) {
+                Ok(task) => task,
+                Err(e) => {
+                    error!("Error: skipping a legacy queued task: {}", e);
+                    redis::cmd("LREM")
+                        .arg(QUEUED_TASKS_QUEUE_KEY)
+                        .arg(0)
+                        .arg(entry)
+                        .query::<()>(&mut self.conn.lock().unwrap())?;
+                    continue;
+                }
+            };
+
+            self.save(&task)?;
+            self.replace_entry_script
+                .key(QUEUED_TASKS_QUEUE_KEY)
+                .arg(entry)
+                .arg(&task.id)
+                .invoke::<()>(&mut *self.conn.lock().unwrap())?;
         }
+
+        Ok(())
+    }
+
+    fn lease_expiry(lease: i64) -> i64 {
+        (Utc::now() + Duration::seconds(lease)).timestamp_millis()
+    }
+
+    /// Seconds the task is kept, `0` for ever.
+    fn expiry(&self, task: &Task) -> i64 {
+        match task.status {
+            TaskStatus::Completed | TaskStatus::Cancelled => self.retention,
+            _ => 0,
+        }
+    }
+
+    fn task_key(id: &str) -> String {
+        format!("{}{}", TASK_PREFIX_KEY, id)
     }
 }
 
 impl TaskStorage for RedisTaskStorage {
     fn push(&self, task: Task) -> Result<(), Box<dyn Error>> {
-        let key = match task.status {
-            TaskStatus::Queued => QUEUED_TASKS_QUEUE_KEY,
-            TaskStatus::Completed => COMPLETED_TASKS_QUEUE_KEY,
+        let mut invocation = self.push_script.prepare_invoke();
+        invocation
+            .key(Self::task_key(&task.id))
+            .key(TASKS_INDEX_KEY)
+            .key(LEASED_TASKS_KEY);
+
+        let delayed_until = match task.run_at {
+            Some(run_at) if task.status == TaskStatus::Queued && !task.is_due() => Some(run_at),
+            _ => None,
         };
 
-        redis::cmd("RPUSH")
-            .arg(key)
+        match (task.status, delayed_until) {
+            (TaskStatus::Queued, Some(_)) => invocation.key(DELAYED_TASKS_QUEUE_KEY),
+            (TaskStatus::Queued, None) => invocation.key(QUEUED_TASKS_QUEUE_KEY),
+            (TaskStatus::Failed, _) => invocation.key(DEAD_LETTER_QUEUE_KEY),
+            _ => &mut invocation,
+        };
+
+        invocation
             .arg(serde_json::to_string(&task)?)
-            .query(&mut self.conn.lock().unwrap())?;
+            .arg(task.created_at.timestamp_millis())
+            .arg(self.expiry(&task))
+            .arg(
+                delayed_until
+                    .map(|run_at| run_at.timestamp_millis().to_string())
+                    .unwrap_or_default(),
+            )
+            .arg(&task.id)
+            .invoke::<()>(&mut *self.conn.lock().unwrap())?;
 
         Ok(())
     }
 
-    fn pop_queued(&self) -> Result<Option<Task>, Box<dyn Error>> {
-        let result: Option<String> = redis::cmd("LPOP")
-            .arg(QUEUED_TASKS_QUEUE_KEY)
+    fn promote_due(&self, now: DateTime<Utc>) -> Result<usize, Box<dyn Error>> {
+        let promoted: usize = self
+            .promote_due_script
+            .key(DELAYED_TASKS_QUEUE_KEY)
+            .key(QUEUED_TASKS_QUEUE_KEY)
+            .arg(now.timestamp_millis())
+            .invoke(&mut *self.conn.lock().unwrap())?;
+
+        Ok(promoted)
+    }
+
+    fn save(&self, task: &Task) -> Result<(), Box<dyn Error>> {
+        let mut conn = self.conn.lock().unwrap();
+
+        let mut set = redis::cmd("SET");
+        set.arg(Self::task_key(&task.id))
+            .arg(serde_json::to_string(task)?);
+        let expiry = self.expiry(task);
+        if expiry > 0 {
+            set.arg("EX").arg(expiry);
+        }
+        set.query::<()>(&mut *conn)?;
+
+        redis::cmd("ZADD")
+            .arg(TASKS_INDEX_KEY)
+            .arg(task.created_at.timestamp_millis())
+            .arg(&task.id)
+            .query::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Task>, Box<dyn Error>> {
+        let result: Option<String> = redis::cmd("GET")
+            .arg(Self::task_key(id))
             .query(&mut self.conn.lock().unwrap())?;
 
         match result {
-            Some(json) => {
-                let task: Task = serde_json::from_str(&json)?;
-                Ok(Some(task))
-            }
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
             None => Ok(None),
         }
     }
+
+    fn list(&self, filter: &TaskFilter, limit: usize) -> Result<Vec<Task>, Box<dyn Error>> {
+        let mut tasks = Vec::new();
+        let mut start: isize = 0;
+
+        while tasks.len() < limit {
+            let mut conn = self.conn.lock().unwrap();
+
+            let ids: Vec<String> = redis::cmd("ZREVRANGE")
+                .arg(TASKS_INDEX_KEY)
+                .arg(start)
+                .arg(start + LIST_PAGE_SIZE - 1)
+                .query(&mut *conn)?;
+            if ids.is_empty() {
+                break;
+            }
+
+            let keys: Vec<String> = ids.iter().map(|id| Self::task_key(id)).collect();
+            let results: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query(&mut *conn)?;
+
+            let mut expired = Vec::new();
+            for (id, result) in ids.iter().zip(results) {
+                match result {
+                    Some(json) => {
+                        let task: Task = serde_json::from_str(&json)?;
+                        if filter.matches(&task) && tasks.len() < limit {
+                            tasks.push(task);
+                        }
+                    }
+                    None => expired.push(id),
+                }
+            }
+
+            // Finished tasks expire without leaving the index.
+            if !expired.is_empty() {
+                redis::cmd("ZREM")
+                    .arg(TASKS_INDEX_KEY)
+                    .arg(&expired)
+                    .query::<()>(&mut *conn)?;
+            }
+
+            start += LIST_PAGE_SIZE - expired.len() as isize;
+        }
+
+        Ok(tasks)
+    }
+
+    fn pop_queued(&self, lease: i64) -> Result<Option<Task>, Box<dyn Error>> {
+        loop {
+            let result: Option<String> = self
+                .pop_and_lease_script
+                .key(QUEUED_TASKS_QUEUE_KEY)
+                .key(LEASED_TASKS_KEY)
+                .arg(Self::lease_expiry(lease))
+                .invoke(&mut *self.conn.lock().unwrap())?;
+
+            let id = match result {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+
+            // Queued whole by an instance predating the task keys.
+            if is_legacy_entry(&id) {
+                self.release(&id)?;
+                match serde_json::from_str::<Task>(&id) {
+                    Ok(task) => self.push(task)?,
+                    Err(e) => error!("Error: skipping a legacy queued task: {}", e),
+                }
+                continue;
+            }
+
+            match self.get(&id)? {
+                Some(task) if task.status == TaskStatus::Queued => return Ok(Some(task)),
+                // Cancelled while queued.
+                _ => self.release(&id)?,
+            }
+        }
+    }
+
+    fn extend_lease(&self, id: &str, lease: i64) -> Result<bool, Box<dyn Error>> {
+        let extended: u8 = self
+            .extend_lease_script
+            .key(LEASED_TASKS_KEY)
+            .arg(id)
+            .arg(Self::lease_expiry(lease))
+            .invoke(&mut *self.conn.lock().unwrap())?;
+
+        Ok(extended == 1)
+    }
+
+    fn release(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        redis::cmd("ZREM")
+            .arg(LEASED_TASKS_KEY)
+            .arg(id)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        Ok(())
+    }
+
+    fn claim_expired_leases(
+        &self,
+        now: DateTime<Utc>,
+        lease: i64,
+    ) -> Result<Vec<Task>, Box<dyn Error>> {
+        let ids: Vec<String> = self
+            .claim_expired_leases_script
+            .key(LEASED_TASKS_KEY)
+            .arg(now.timestamp_millis())
+            .arg(Self::lease_expiry(lease))
+            .invoke(&mut *self.conn.lock().unwrap())?;
+
+        let mut tasks = Vec::new();
+        for id in ids {
+            match self.get(&id)? {
+                Some(task) => tasks.push(task),
+                // Expired after the retention period.
+                None => self.release(&id)?,
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    fn list_dead_letters(
+        &self,
+        filter: &TaskFilter,
+        limit: usize,
+    ) -> Result<Vec<Task>, Box<dyn Error>> {
+        let ids: Vec<String> = redis::cmd("LRANGE")
+            .arg(DEAD_LETTER_QUEUE_KEY)
+            .arg(0)
+            .arg(-1)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        let mut tasks = Vec::new();
+        for id in ids {
+            if tasks.len() >= limit {
+                break;
+            }
+
+            if let Some(task) = self.get(&id)? {
+                if filter.matches(&task) {
+                    tasks.push(task);
+                }
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    fn requeue(&self, task: Task) -> Result<(), Box<dyn Error>> {
+        redis::cmd("LREM")
+            .arg(DEAD_LETTER_QUEUE_KEY)
+            .arg(0)
+            .arg(&task.id)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        self.push(task)
+    }
+}
+
+/// Tasks were queued as JSON documents before they had their own keys.
+fn is_legacy_entry(entry: &str) -> bool {
+    entry.starts_with('{')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::DEFAULT_PROJECT;
+
+    #[test]
+    fn test_legacy_entries_parse_as_tasks() {
+        let entry = r#"{"id":"0b1c1f4e-4c1e-4a55-9a5c-4bd0e5b6c0a1","status":"Queued","details":{"ClearCache":{"before_date":"2024-01-01T00:00:00Z"}},"kind":"ClearCache","error":null}"#;
+
+        assert!(is_legacy_entry(entry));
+        assert!(!is_legacy_entry("0b1c1f4e-4c1e-4a55-9a5c-4bd0e5b6c0a1"));
+
+        let task: Task = serde_json::from_str(entry).unwrap();
+        assert_eq!(task.project, DEFAULT_PROJECT);
+        assert_eq!(task.status, TaskStatus::Queued);
+        assert!(task.is_due());
+    }
 }
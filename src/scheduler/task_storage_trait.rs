@@ -1,8 +1,36 @@
+use chrono::{DateTime, Utc};
 use std::error::Error;
 
-use super::Task;
+use super::{Task, TaskFilter};
 
 pub trait TaskStorage: Send + Sync {
+    /// Saves the task, and queues it again if its status is queued or moves it
+    /// to the dead-letter queue if it failed. A queued task with a `run_at` in
+    /// the future waits in the delayed queue.
     fn push(&self, task: Task) -> Result<(), Box<dyn Error>>;
-    fn pop_queued(&self) -> Result<Option<Task>, Box<dyn Error>>;
+    /// Moves the delayed tasks due at `now` to the queue, returning how many.
+    fn promote_due(&self, now: DateTime<Utc>) -> Result<usize, Box<dyn Error>>;
+    /// Saves the task without queuing it.
+    fn save(&self, task: &Task) -> Result<(), Box<dyn Error>>;
+    fn get(&self, id: &str) -> Result<Option<Task>, Box<dyn Error>>;
+    /// The most recent tasks matching `filter`, at most `limit` of them.
+    fn list(&self, filter: &TaskFilter, limit: usize) -> Result<Vec<Task>, Box<dyn Error>>;
+    /// Pops the next task still queued, skipping the cancelled ones, and leases
+    /// it for `lease` seconds. Pushing the task releases the lease.
+    fn pop_queued(&self, lease: i64) -> Result<Option<Task>, Box<dyn Error>>;
+    /// Heartbeat of a running task. Returns false if the lease was lost.
+    fn extend_lease(&self, id: &str, lease: i64) -> Result<bool, Box<dyn Error>>;
+    fn release(&self, id: &str) -> Result<(), Box<dyn Error>>;
+    /// The tasks whose lease expired at `now`, leased again for `lease` seconds
+    /// so that a single caller reclaims each of them.
+    fn claim_expired_leases(
+        &self,
+        now: DateTime<Utc>,
+        lease: i64,
+    ) -> Result<Vec<Task>, Box<dyn Error>>;
+    /// The oldest failed tasks matching `filter`, at most `limit` of them.
+    fn list_dead_letters(&self, filter: &TaskFilter, limit: usize)
+        -> Result<Vec<Task>, Box<dyn Error>>;
+    /// Takes the task out of the dead-letter queue and pushes it.
+    fn requeue(&self, task: Task) -> Result<(), Box<dyn Error>>;
 }
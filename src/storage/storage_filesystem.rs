@@ -1,6 +1,7 @@
 use bytes::Bytes;
 use std::error::Error;
 use std::fs;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 use async_trait::async_trait;
 
@@ -44,12 +45,50 @@ impl FileStorage for FilesystemStorage {
         }
     }
 
+    async fn download_range(
+        &self,
+        path: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<Bytes>, Box<dyn Error>> {
+        let path = path.strip_prefix("/").unwrap_or(path);
+        let full_path = Path::new(&self.mount_dir).join(path);
+
+        let mut file = match fs::File::open(&full_path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut data = vec![0; (end - start + 1) as usize];
+        file.read_exact(&mut data)?;
+
+        Ok(Some(Bytes::from(data)))
+    }
+
+    async fn size(&self, path: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        let path = path.strip_prefix("/").unwrap_or(path);
+        let full_path = Path::new(&self.mount_dir).join(path);
+
+        match fs::metadata(&full_path) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     async fn move_(&self, src: &str, dst: &str) -> Result<(), Box<dyn Error>> {
         let src = src.strip_prefix("/").unwrap_or(src);
         let dst = dst.strip_prefix("/").unwrap_or(dst);
         let src_full_path = Path::new(&self.mount_dir).join(src);
         let dst_full_path = Path::new(&self.mount_dir).join(dst);
 
+        if let Some(parent) = dst_full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
         fs::rename(&src_full_path, &dst_full_path)?;
 
         Ok(())
@@ -61,6 +100,10 @@ impl FileStorage for FilesystemStorage {
         let src_full_path = Path::new(&self.mount_dir).join(src);
         let dst_full_path = Path::new(&self.mount_dir).join(dst);
 
+        if let Some(parent) = dst_full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
         fs::copy(&src_full_path, &dst_full_path)?;
 
         Ok(())
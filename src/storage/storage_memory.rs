@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::ErrorKind;
+use std::sync::RwLock;
+
+use crate::storage::storage_trait::FileStorage;
+
+/// Keeps the files in memory, for development and tests. They are lost when the
+/// server stops.
+#[derive(Default)]
+pub struct MemoryStorage {
+    files: RwLock<HashMap<String, Bytes>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn not_found(path: &str) -> Box<dyn Error> {
+        Box::new(std::io::Error::new(
+            ErrorKind::NotFound,
+            format!("File {} not found", path),
+        ))
+    }
+}
+
+#[async_trait]
+impl FileStorage for MemoryStorage {
+    async fn upload(&self, path: &str, data: Bytes) -> Result<(), Box<dyn Error>> {
+        self.files.write().unwrap().insert(path.to_string(), data);
+
+        Ok(())
+    }
+
+    async fn download(&self, path: &str) -> Result<Option<Bytes>, Box<dyn Error>> {
+        Ok(self.files.read().unwrap().get(path).cloned())
+    }
+
+    async fn download_range(
+        &self,
+        path: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<Bytes>, Box<dyn Error>> {
+        let files = self.files.read().unwrap();
+        let data = match files.get(path) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+
+        if start > end || end >= data.len() as u64 {
+            return Err(Box::new(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Range exceeds the file",
+            )));
+        }
+
+        Ok(Some(data.slice(start as usize..=end as usize)))
+    }
+
+    async fn size(&self, path: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        Ok(self
+            .files
+            .read()
+            .unwrap()
+            .get(path)
+            .map(|data| data.len() as u64))
+    }
+
+    async fn move_(&self, src: &str, dst: &str) -> Result<(), Box<dyn Error>> {
+        let mut files = self.files.write().unwrap();
+        let data = files.remove(src).ok_or_else(|| Self::not_found(src))?;
+        files.insert(dst.to_string(), data);
+
+        Ok(())
+    }
+
+    async fn copy(&self, src: &str, dst: &str) -> Result<(), Box<dyn Error>> {
+        let mut files = self.files.write().unwrap();
+        let data = files
+            .get(src)
+            .cloned()
+            .ok_or_else(|| Self::not_found(src))?;
+        files.insert(dst.to_string(), data);
+
+        Ok(())
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.files
+            .write()
+            .unwrap()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| Self::not_found(path))
+    }
+}
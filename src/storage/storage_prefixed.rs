@@ -0,0 +1,72 @@
+use bytes::Bytes;
+use std::error::Error;
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::storage::storage_trait::FileStorage;
+
+/// Keeps the files of another storage under a folder of its own, the way each
+/// project gets its part of the shared file and cache storages.
+pub struct PrefixedFileStorage {
+    inner: Arc<Mutex<dyn FileStorage>>,
+    prefix: String,
+}
+
+impl PrefixedFileStorage {
+    pub fn new(inner: Arc<Mutex<dyn FileStorage>>, prefix: String) -> Self {
+        Self { inner, prefix }
+    }
+
+    fn path(&self, path: &str) -> String {
+        format!("{}{}", self.prefix, path)
+    }
+}
+
+#[async_trait]
+impl FileStorage for PrefixedFileStorage {
+    async fn upload(&self, path: &str, data: Bytes) -> Result<(), Box<dyn Error>> {
+        self.inner.lock().await.upload(&self.path(path), data).await
+    }
+
+    async fn download(&self, path: &str) -> Result<Option<Bytes>, Box<dyn Error>> {
+        self.inner.lock().await.download(&self.path(path)).await
+    }
+
+    async fn download_range(
+        &self,
+        path: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<Bytes>, Box<dyn Error>> {
+        self.inner
+            .lock()
+            .await
+            .download_range(&self.path(path), start, end)
+            .await
+    }
+
+    async fn size(&self, path: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        self.inner.lock().await.size(&self.path(path)).await
+    }
+
+    async fn move_(&self, src: &str, dst: &str) -> Result<(), Box<dyn Error>> {
+        self.inner
+            .lock()
+            .await
+            .move_(&self.path(src), &self.path(dst))
+            .await
+    }
+
+    async fn copy(&self, src: &str, dst: &str) -> Result<(), Box<dyn Error>> {
+        self.inner
+            .lock()
+            .await
+            .copy(&self.path(src), &self.path(dst))
+            .await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.inner.lock().await.delete(&self.path(path)).await
+    }
+}
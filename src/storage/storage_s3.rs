@@ -29,6 +29,21 @@ impl FileStorage for S3Storage {
 
     }
 
+    async fn download_range(
+        &self,
+        path: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<Bytes>, Box<dyn Error>> {
+        let s3_object = self.s3.download_object_range(path, start, end).await?;
+        Ok(Some(s3_object.body))
+    }
+
+    async fn size(&self, path: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        let content_length = self.s3.head_object(path).await?;
+        Ok(content_length.map(|content_length| content_length as u64))
+    }
+
     async fn move_(&self, src: &str, dst: &str) -> Result<(), Box<dyn Error>> {
         self.s3.move_object(src, dst).await
     }
@@ -5,6 +5,7 @@ use std::time::Duration;
 use bytes::BytesMut;
 use std::sync::Arc;
 use serde::{Deserialize};
+use tokio::sync::Mutex;
 use tokio::time::sleep;
 use crate::media::Path;
 use crate::storage::FileStorage;
@@ -39,12 +40,12 @@ struct PredictionResult {
 }
 
 pub struct Colorizer {
-    file_storage: Arc<dyn FileStorage>,
+    file_storage: Arc<Mutex<dyn FileStorage>>,
 }
 
 impl Colorizer {
     pub fn new(
-        file_storage: Arc<dyn FileStorage>,
+        file_storage: Arc<Mutex<dyn FileStorage>>,
     ) -> Self {
         Self {
             file_storage,
@@ -0,0 +1,85 @@
+use bytes::BytesMut;
+use image::io::Reader as ImageReader;
+use image::DynamicImage;
+use std::{error::Error, io::Cursor};
+
+use super::OutputFormat;
+use crate::media::Path;
+
+#[derive(Default)]
+pub struct FormatConverter {
+    output_format: OutputFormat,
+}
+
+impl FormatConverter {
+    pub fn new(output_format: OutputFormat) -> Self {
+        Self { output_format }
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    /// Re-encodes the body and returns whether the source image had an alpha channel.
+    pub fn transform(
+        &self,
+        path: Path,
+        body: BytesMut,
+    ) -> Result<(Path, BytesMut, bool), Box<dyn Error>> {
+        let img = ImageReader::new(Cursor::new(&body))
+            .with_guessed_format()?
+            .decode()?;
+
+        self.encode(path, img)
+    }
+
+    /// Like `transform`, decoding the image straight from the file so that only
+    /// the decoded image is held in memory.
+    pub fn transform_file(
+        &self,
+        path: Path,
+        file: &std::path::Path,
+    ) -> Result<(Path, BytesMut, bool), Box<dyn Error>> {
+        let img = ImageReader::open(file)?.with_guessed_format()?.decode()?;
+
+        self.encode(path, img)
+    }
+
+    fn encode(
+        &self,
+        mut path: Path,
+        img: DynamicImage,
+    ) -> Result<(Path, BytesMut, bool), Box<dyn Error>> {
+        let has_alpha = img.color().has_alpha();
+        let body = self.output_format.encode(&img)?;
+
+        path.set_extension(self.output_format.extension());
+
+        Ok((path, body, has_alpha))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::TempFile;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_transform_file_decodes_from_disk() {
+        let file = TempFile::new(None).unwrap();
+        RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 128]))
+            .save_with_format(file.path(), image::ImageFormat::Png)
+            .unwrap();
+        let path = Path::new("/bbd2fa99-f35e-4062-92eb-9d26caa943ae.png").unwrap();
+
+        let (path, body, has_alpha) = FormatConverter::new(OutputFormat::Png)
+            .transform_file(path, file.path())
+            .unwrap();
+
+        assert!(has_alpha);
+        assert_eq!(path.extension(), "png");
+        let img = image::load_from_memory(&body).unwrap();
+        assert_eq!((img.width(), img.height()), (4, 2));
+    }
+}
@@ -1,4 +1,6 @@
+pub mod format_converter;
 pub mod named_transformation;
+pub mod output_format;
 pub mod path_generator;
 pub mod scaler;
 pub mod transformation_descriptor;
@@ -6,13 +8,14 @@ pub mod transformation_descriptor_chain;
 pub mod transformation_template;
 pub mod transformation_template_registry;
 pub mod watermarker;
-pub mod webp_converter;
 pub mod colorizer;
 
+pub use format_converter::FormatConverter;
 pub use named_transformation::{
-    NamedTransformation, NamedTransformationStorage,
+    MemoryNamedTransformationStorage, NamedTransformation, NamedTransformationStorage,
     RedisNamedTransformationStorage,
 };
+pub use output_format::{FormatPreference, OutputFormat};
 pub use path_generator::PathGenerator;
 pub use scaler::{CropStrategy, Scaler};
 pub use transformation_descriptor::TransformationDescriptor;
@@ -20,5 +23,4 @@ pub use transformation_descriptor_chain::TransformationDescriptorChain;
 pub use transformation_template::TransformationTemplate;
 pub use transformation_template_registry::{TransformationName, TransformationTemplateRegistry};
 pub use watermarker::Watermarker;
-pub use webp_converter::WebpConverter;
 pub use colorizer::Colorizer;
\ No newline at end of file
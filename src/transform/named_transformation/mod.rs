@@ -1,7 +1,9 @@
 pub mod named_transformation;
+pub mod named_transformation_storage_memory;
 pub mod named_transformation_storage_redis;
 pub mod named_transformation_storage_trait;
 
 pub use named_transformation::{NamedTransformation, NamedTransformationMap};
+pub use named_transformation_storage_memory::MemoryNamedTransformationStorage;
 pub use named_transformation_storage_redis::RedisNamedTransformationStorage;
 pub use named_transformation_storage_trait::NamedTransformationStorage;
\ No newline at end of file
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use super::{NamedTransformation, NamedTransformationMap, NamedTransformationStorage};
+use crate::project::DEFAULT_PROJECT;
+
+/// Keeps the named transformations in memory, for development and tests. They are
+/// lost when the server stops.
+pub struct MemoryNamedTransformationStorage {
+    projects: Arc<RwLock<HashMap<String, NamedTransformationMap>>>,
+    project: String,
+}
+
+impl MemoryNamedTransformationStorage {
+    pub fn new() -> Self {
+        Self {
+            projects: Arc::default(),
+            project: DEFAULT_PROJECT.to_string(),
+        }
+    }
+}
+
+impl Default for MemoryNamedTransformationStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NamedTransformationStorage for MemoryNamedTransformationStorage {
+    fn get_all(&self) -> Result<NamedTransformationMap, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+
+        Ok(projects.get(&self.project).cloned().unwrap_or_default())
+    }
+
+    fn get_by_name(&self, name: &str) -> Result<Option<NamedTransformation>, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+
+        Ok(projects
+            .get(&self.project)
+            .and_then(|named_transformations| named_transformations.get(name))
+            .cloned())
+    }
+
+    fn save(&self, named_transformation: NamedTransformation) -> Result<(), Box<dyn Error>> {
+        self.projects
+            .write()
+            .unwrap()
+            .entry(self.project.clone())
+            .or_default()
+            .insert(named_transformation.name.clone(), named_transformation);
+
+        Ok(())
+    }
+
+    fn delete(&self, named_transformation_name: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(named_transformations) = self.projects.write().unwrap().get_mut(&self.project) {
+            named_transformations.remove(named_transformation_name);
+        }
+
+        Ok(())
+    }
+
+    fn for_project(
+        &self,
+        project: &str,
+    ) -> Result<Arc<dyn NamedTransformationStorage>, Box<dyn Error>> {
+        Ok(Arc::new(Self {
+            projects: self.projects.clone(),
+            project: project.to_string(),
+        }))
+    }
+}
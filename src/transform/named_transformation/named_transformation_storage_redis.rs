@@ -5,15 +5,22 @@ use std::sync::{Arc, Mutex};
 const NAMED_TRANSFORMATIONS_KEY: &str = "internal:configuration:named_transformations";
 
 use super::{NamedTransformation, NamedTransformationMap, NamedTransformationStorage};
+use crate::project::{namespaced_key, DEFAULT_PROJECT};
 
 pub struct RedisNamedTransformationStorage {
     conn: Arc<Mutex<Connection>>,
+    key: String,
 }
 
 impl RedisNamedTransformationStorage {
     pub fn new(conn: Connection) -> Result<Self, Box<dyn Error>> {
+        Self::with_project(Arc::new(Mutex::new(conn)), DEFAULT_PROJECT)
+    }
+
+    fn with_project(conn: Arc<Mutex<Connection>>, project: &str) -> Result<Self, Box<dyn Error>> {
         let storage = RedisNamedTransformationStorage {
-            conn: Arc::new(Mutex::new(conn)),
+            conn,
+            key: namespaced_key(project, NAMED_TRANSFORMATIONS_KEY),
         };
         storage.init()?;
         Ok(storage)
@@ -21,12 +28,12 @@ impl RedisNamedTransformationStorage {
 
     fn init(&self) -> Result<(), Box<dyn Error>> {
         let exists: bool = redis::cmd("EXISTS")
-            .arg(NAMED_TRANSFORMATIONS_KEY)
+            .arg(&self.key)
             .query(&mut self.conn.lock().unwrap())?;
 
         if !exists {
             redis::cmd("JSON.SET")
-                .arg(NAMED_TRANSFORMATIONS_KEY)
+                .arg(&self.key)
                 .arg(".")
                 .arg("{}")
                 .query(&mut self.conn.lock().unwrap())?;
@@ -39,7 +46,7 @@ impl RedisNamedTransformationStorage {
 impl NamedTransformationStorage for RedisNamedTransformationStorage {
     fn get_all(&self) -> Result<NamedTransformationMap, Box<dyn Error>> {
         let result: String = redis::cmd("JSON.GET")
-            .arg(NAMED_TRANSFORMATIONS_KEY)
+            .arg(&self.key)
             .query(&mut self.conn.lock().unwrap())?;
 
         serde_json::from_str(&result).map_err(|e| e.into())
@@ -47,7 +54,7 @@ impl NamedTransformationStorage for RedisNamedTransformationStorage {
 
     fn get_by_name(&self, name: &str) -> Result<Option<NamedTransformation>, Box<dyn Error>> {
         let result: Option<String> = redis::cmd("JSON.GET")
-            .arg(NAMED_TRANSFORMATIONS_KEY)
+            .arg(&self.key)
             .arg(name)
             .query(&mut self.conn.lock().unwrap())?;
 
@@ -61,7 +68,7 @@ impl NamedTransformationStorage for RedisNamedTransformationStorage {
         let transformation_json = serde_json::to_string(&named_transformation)?;
 
         redis::cmd("JSON.SET")
-            .arg(NAMED_TRANSFORMATIONS_KEY)
+            .arg(&self.key)
             .arg(named_transformation.name)
             .arg(transformation_json)
             .query(&mut self.conn.lock().unwrap())?;
@@ -71,10 +78,17 @@ impl NamedTransformationStorage for RedisNamedTransformationStorage {
 
     fn delete(&self, named_transformation_name: &str) -> Result<(), Box<dyn Error>> {
         redis::cmd("JSON.DEL")
-            .arg(NAMED_TRANSFORMATIONS_KEY)
+            .arg(&self.key)
             .arg(named_transformation_name)
             .query(&mut self.conn.lock().unwrap())?;
 
         Ok(())
     }
+
+    fn for_project(
+        &self,
+        project: &str,
+    ) -> Result<Arc<dyn NamedTransformationStorage>, Box<dyn Error>> {
+        Ok(Arc::new(Self::with_project(self.conn.clone(), project)?))
+    }
 }
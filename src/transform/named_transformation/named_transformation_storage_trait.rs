@@ -2,6 +2,7 @@
 use mockall::automock;
 
 use std::error::Error;
+use std::sync::Arc;
 
 use super::{NamedTransformation, NamedTransformationMap};
 
@@ -10,4 +11,9 @@ pub trait NamedTransformationStorage: Send + Sync {
     fn get_by_name(&self, name: &str) -> Result<Option<NamedTransformation>, Box<dyn Error>>;
     fn save(&self, named_transformation: NamedTransformation) -> Result<(), Box<dyn Error>>;
     fn delete(&self, named_transformation: &str) -> Result<(), Box<dyn Error>>;
+    /// The storage of the named transformations of `project`, kept apart from this one.
+    fn for_project(
+        &self,
+        project: &str,
+    ) -> Result<Arc<dyn NamedTransformationStorage>, Box<dyn Error>>;
 }
@@ -0,0 +1,167 @@
+use bytes::BytesMut;
+use image::{DynamicImage, ImageOutputFormat};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, io::Cursor};
+use webp::Encoder;
+
+pub const DEFAULT_QUALITY: f32 = 65.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OutputFormat {
+    Avif,
+    Webp,
+    Jpeg,
+    Png,
+}
+
+/// The output format requested in a transformation chain, either fixed (`f_webp`)
+/// or negotiated from the `Accept` header (`f_auto`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FormatPreference {
+    Auto,
+    Fixed(OutputFormat),
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "avif" => Some(OutputFormat::Avif),
+            "webp" => Some(OutputFormat::Webp),
+            "jpeg" | "jpg" => Some(OutputFormat::Jpeg),
+            "png" => Some(OutputFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Avif => "avif",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Jpeg => "jpeg",
+            OutputFormat::Png => "png",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Avif => "avif",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Avif => "image/avif",
+            OutputFormat::Webp => "image/webp",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::Png => "image/png",
+        }
+    }
+
+    /// AVIF encoding pulls in rav1e and is only available with the `avif` feature.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            OutputFormat::Avif => cfg!(feature = "avif"),
+            _ => true,
+        }
+    }
+
+    /// Picks the best supported format the client explicitly accepts: AVIF, then WebP,
+    /// falling back to PNG for images with transparency and JPEG otherwise.
+    pub fn negotiate(accept: &str, has_alpha: bool) -> Self {
+        let accepted: Vec<&str> = accept
+            .split(',')
+            .filter_map(|media_range| {
+                let mut params = media_range.split(';').map(str::trim);
+                let media_type = params.next()?;
+                let rejected = params.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .map_or(false, |q| q <= 0.0)
+                });
+
+                if rejected {
+                    None
+                } else {
+                    Some(media_type)
+                }
+            })
+            .collect();
+
+        for format in [OutputFormat::Avif, OutputFormat::Webp] {
+            if format.is_supported() && accepted.contains(&format.content_type()) {
+                return format;
+            }
+        }
+
+        if has_alpha {
+            OutputFormat::Png
+        } else {
+            OutputFormat::Jpeg
+        }
+    }
+
+    pub fn encode(&self, img: &DynamicImage) -> Result<BytesMut, Box<dyn Error>> {
+        let mut body = BytesMut::new();
+
+        match self {
+            OutputFormat::Webp => {
+                let encoder: Encoder = Encoder::from_image(img)?;
+                body.extend_from_slice(&encoder.encode(DEFAULT_QUALITY));
+            }
+            OutputFormat::Jpeg => {
+                let mut cursor = Cursor::new(Vec::new());
+                DynamicImage::ImageRgb8(img.to_rgb8())
+                    .write_to(&mut cursor, ImageOutputFormat::Jpeg(DEFAULT_QUALITY as u8))?;
+                body.extend_from_slice(cursor.get_ref());
+            }
+            OutputFormat::Png => {
+                let mut cursor = Cursor::new(Vec::new());
+                img.write_to(&mut cursor, ImageOutputFormat::Png)?;
+                body.extend_from_slice(cursor.get_ref());
+            }
+            #[cfg(feature = "avif")]
+            OutputFormat::Avif => {
+                let mut cursor = Cursor::new(Vec::new());
+                img.write_to(&mut cursor, ImageOutputFormat::Avif)?;
+                body.extend_from_slice(cursor.get_ref());
+            }
+            #[cfg(not(feature = "avif"))]
+            OutputFormat::Avif => return Err("AVIF encoding is not enabled".into()),
+        }
+
+        Ok(body)
+    }
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Webp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputFormat;
+
+    #[test]
+    fn test_negotiate() {
+        let chrome = "image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8";
+        let expected = if cfg!(feature = "avif") {
+            OutputFormat::Avif
+        } else {
+            OutputFormat::Webp
+        };
+        assert_eq!(OutputFormat::negotiate(chrome, false), expected);
+
+        assert_eq!(
+            OutputFormat::negotiate("image/webp;q=0,image/*", false),
+            OutputFormat::Jpeg
+        );
+        assert_eq!(OutputFormat::negotiate("*/*", true), OutputFormat::Png);
+        assert_eq!(OutputFormat::negotiate("", false), OutputFormat::Jpeg);
+    }
+}
@@ -1,7 +1,6 @@
 use std::error::Error;
-use aws_sdk_s3::primitives::event_stream::HeaderValue::Uuid;
 
-use super::TransformationDescriptorChain;
+use super::{FormatPreference, TransformationDescriptorChain};
 use crate::media::Path;
 
 #[derive(Default)]
@@ -16,13 +15,23 @@ impl PathGenerator {
         let mut path = path.clone();
 
         if !transformation_descriptor_chain.is_empty() {
-            let suffix = transformation_descriptor_chain
+            let mut suffixes = transformation_descriptor_chain
                 .iter()
                 .map(|transformation_descriptor| transformation_descriptor.as_str())
-                .collect::<Vec<_>>()
-                .join(",");
+                .collect::<Vec<_>>();
 
-            path = path.add_suffix_to_filename(&suffix)?;
+            match transformation_descriptor_chain.format() {
+                Some(FormatPreference::Fixed(output_format)) => {
+                    suffixes.push(format!("f-{}", output_format.as_str()));
+                    path.set_extension(output_format.extension());
+                }
+                Some(FormatPreference::Auto) => {
+                    return Err("Output format must be negotiated before generating a path".into());
+                }
+                None => {}
+            }
+
+            path = path.add_suffix_to_filename(&suffixes.join(","))?;
         }
 
         Ok(path)
@@ -1,9 +1,8 @@
 use bytes::BytesMut;
 use image::io::Reader as ImageReader;
-use image::{EncodableLayout, GenericImageView, ImageBuffer, Rgba};
+use image::{GenericImageView, ImageBuffer, Rgba};
 use std::{error::Error, io::Cursor};
-use webp::{Encoder, WebPMemory};
-use crate::media::Path;
+use crate::transform::OutputFormat;
 use crate::types::{Position, Size};
 
 
@@ -28,7 +27,11 @@ impl Scaler {
         }
     }
 
-    pub fn transform(&self, mut path: Path, mut dst: BytesMut) -> Result<BytesMut, Box<dyn Error>> {
+    pub fn transform(
+        &self,
+        dst: BytesMut,
+        output_format: OutputFormat,
+    ) -> Result<BytesMut, Box<dyn Error>> {
         let img = ImageReader::new(Cursor::new(&dst))
             .with_guessed_format()?
             .decode()?;
@@ -63,14 +66,6 @@ impl Scaler {
             CropStrategy::ForcedCrop => {}
         }
 
-        let encoder: Encoder = Encoder::from_image(&img)?;
-        let encoded_webp: WebPMemory = encoder.encode(65f32);
-
-        dst.clear();
-        dst.extend_from_slice(&encoded_webp.as_bytes());
-
-        path.set_extension("webp");
-
-        Ok(dst)
+        output_format.encode(&img)
     }
 }
@@ -29,7 +29,10 @@ impl TransformationDescriptor {
         let mut s = String::new();
         s.push_str(&self.transformation_template.name.as_str());
 
-        for key in self.transformation_template.args.keys() {
+        let mut keys: Vec<&String> = self.transformation_template.args.keys().collect();
+        keys.sort();
+
+        for key in keys {
             let value = self.arg_values.get(key);
             match value {
                 Some(v) => s.push_str(&format!(",{}-{}", key, v.replace("/", "%"))),
@@ -1,14 +1,18 @@
-use super::TransformationDescriptor;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default, Debug, Clone, PartialEq)]
+use super::{FormatPreference, OutputFormat, TransformationDescriptor};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TransformationDescriptorChain {
     transformation_descriptors: Vec<TransformationDescriptor>,
+    format: Option<FormatPreference>,
 }
 
 impl TransformationDescriptorChain {
     pub fn new() -> Self {
         Self {
             transformation_descriptors: Vec::new(),
+            format: None,
         }
     }
 
@@ -25,8 +29,33 @@ impl TransformationDescriptorChain {
         &self.transformation_descriptors
     }
 
+    pub fn format(&self) -> Option<FormatPreference> {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: FormatPreference) {
+        self.format = Some(format);
+    }
+
+    /// Replaces an `Auto` format preference with the format negotiated for the client.
+    pub fn resolve_format(&mut self, accept: &str, has_alpha: bool) {
+        if self.format == Some(FormatPreference::Auto) {
+            self.format = Some(FormatPreference::Fixed(OutputFormat::negotiate(
+                accept, has_alpha,
+            )));
+        }
+    }
+
+    /// The format derived medias are encoded to, WebP unless a fixed format was requested.
+    pub fn output_format(&self) -> OutputFormat {
+        match self.format {
+            Some(FormatPreference::Fixed(output_format)) => output_format,
+            _ => OutputFormat::default(),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.transformation_descriptors.is_empty()
+        self.transformation_descriptors.is_empty() && self.format.is_none()
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &TransformationDescriptor> {
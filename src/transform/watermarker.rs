@@ -1,12 +1,13 @@
 use bytes::BytesMut;
-use image::{EncodableLayout, GenericImage, GenericImageView, ImageBuffer, Rgba};
+use image::{GenericImage, GenericImageView, ImageBuffer, Rgba};
 use imageproc::{drawing::draw_filled_rect_mut, rect::Rect};
 use std::{error::Error, str::FromStr};
 use std::sync::Arc;
-use webp::{Encoder};
+use tokio::sync::Mutex;
+use crate::error::MindiaError;
 use crate::media::Path;
 use crate::storage::FileStorage;
-use crate::transform::{CropStrategy, Scaler};
+use crate::transform::{CropStrategy, OutputFormat, Scaler};
 use crate::transform::TransformationName::Scale;
 use crate::types::Size;
 
@@ -16,11 +17,11 @@ pub struct Watermarker {
     padding: u32,
     size: Size,
     overlay_path: Path,
-    file_storage: Arc<dyn FileStorage>,
+    file_storage: Arc<Mutex<dyn FileStorage>>,
 }
 
 impl Watermarker {
-    pub fn new(anchor: Anchor, padding: u32, size: Size, overlay_path: Path, file_storage: Arc<dyn FileStorage>) -> Self {
+    pub fn new(anchor: Anchor, padding: u32, size: Size, overlay_path: Path, file_storage: Arc<Mutex<dyn FileStorage>>) -> Self {
         Self {
             anchor,
             padding,
@@ -30,8 +31,19 @@ impl Watermarker {
         }
     }
 
-    pub async fn transform(&self, bytes: BytesMut) -> Result<BytesMut, Box<dyn Error>> {
-        match self.file_storage.download(self.overlay_path.as_str()).await? {
+    pub async fn transform(
+        &self,
+        bytes: BytesMut,
+        output_format: OutputFormat,
+    ) -> Result<BytesMut, Box<dyn Error>> {
+        let overlay_bytes = self
+            .file_storage
+            .lock()
+            .await
+            .download(self.overlay_path.as_str())
+            .await?;
+
+        match overlay_bytes {
             Some(overlay_bytes) => {
                 let mut img = image::load_from_memory(&bytes)?;
                 let (img_w, img_h) = img.dimensions();
@@ -54,7 +66,7 @@ impl Watermarker {
                 overlay_bytes_mut.extend_from_slice(&overlay_bytes);
 
                 let scaler = Scaler::new(Size::new(overlay_w, overlay_h), CropStrategy::ForcedCrop, Rgba([0, 0, 0, 0]));
-                let scaled_overlay = scaler.transform(self.overlay_path.clone(), overlay_bytes_mut)?;
+                let scaled_overlay = scaler.transform(overlay_bytes_mut, OutputFormat::Png)?;
 
                 let mut overlay = image::load_from_memory(&scaled_overlay)?;
 
@@ -76,13 +88,7 @@ impl Watermarker {
 
                 img.copy_from(&overlay_buffer, x, y)?;
 
-                let encoder = Encoder::from_image(&img)?;
-                let encoded_webp = encoder.encode(65f32);
-
-                let mut dst = BytesMut::new();
-                dst.extend_from_slice(&encoded_webp.as_bytes());
-
-                Ok(dst)
+                output_format.encode(&img)
             },
             None => Ok(bytes)
         }
@@ -107,7 +113,7 @@ fn find_watermark_size_that_fit_image(image_size: (u32, u32), padding: u32, wate
         width = (height as f32 * watermark_aspect_ratio).round() as u32;
 
         if width <= 0 || height <= 0 {
-            return Err("Watermark is too big".into());
+            return Err(MindiaError::transformation("Watermark is too big").into());
         }
     }
 
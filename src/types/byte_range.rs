@@ -0,0 +1,78 @@
+const BYTES_UNIT_PREFIX: &str = "bytes=";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteRange {
+    FromTo(u64, u64),
+    From(u64),
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Parses a single-range `Range` header value. Multiple ranges and malformed
+    /// values yield `None` so that callers fall back to serving the whole body.
+    pub fn parse(header: &str) -> Option<Self> {
+        let spec = header.trim().strip_prefix(BYTES_UNIT_PREFIX)?.trim();
+
+        if spec.contains(',') {
+            return None;
+        }
+
+        let (start, end) = spec.split_once('-')?;
+
+        match (start.trim(), end.trim()) {
+            ("", "") => None,
+            ("", suffix) => suffix.parse().ok().map(ByteRange::Suffix),
+            (start, "") => start.parse().ok().map(ByteRange::From),
+            (start, end) => {
+                let start: u64 = start.parse().ok()?;
+                let end: u64 = end.parse().ok()?;
+
+                if start > end {
+                    return None;
+                }
+
+                Some(ByteRange::FromTo(start, end))
+            }
+        }
+    }
+
+    /// Resolves the range against the full size into inclusive `(start, end)` offsets,
+    /// or `None` if the range is not satisfiable.
+    pub fn resolve(&self, size: u64) -> Option<(u64, u64)> {
+        if size == 0 {
+            return None;
+        }
+
+        match *self {
+            ByteRange::FromTo(start, end) if start < size => Some((start, end.min(size - 1))),
+            ByteRange::From(start) if start < size => Some((start, size - 1)),
+            ByteRange::Suffix(length) if length > 0 => Some((size - length.min(size), size - 1)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteRange;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ByteRange::parse("bytes=0-99"), Some(ByteRange::FromTo(0, 99)));
+        assert_eq!(ByteRange::parse("bytes=100-"), Some(ByteRange::From(100)));
+        assert_eq!(ByteRange::parse("bytes=-500"), Some(ByteRange::Suffix(500)));
+        assert_eq!(ByteRange::parse("bytes=10-5"), None);
+        assert_eq!(ByteRange::parse("bytes=0-1,4-5"), None);
+        assert_eq!(ByteRange::parse("items=0-1"), None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(ByteRange::FromTo(0, 99).resolve(50), Some((0, 49)));
+        assert_eq!(ByteRange::From(10).resolve(50), Some((10, 49)));
+        assert_eq!(ByteRange::Suffix(10).resolve(50), Some((40, 49)));
+        assert_eq!(ByteRange::Suffix(100).resolve(50), Some((0, 49)));
+        assert_eq!(ByteRange::From(50).resolve(50), None);
+        assert_eq!(ByteRange::Suffix(0).resolve(50), None);
+    }
+}
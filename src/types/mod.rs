@@ -1,5 +1,7 @@
+pub mod byte_range;
 pub mod position;
 pub mod size;
 
+pub use byte_range::ByteRange;
 pub use position::Position;
 pub use size::Size;
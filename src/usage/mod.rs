@@ -0,0 +1,11 @@
+pub mod usage;
+pub mod usage_storage_memory;
+pub mod usage_storage_redis;
+pub mod usage_storage_trait;
+pub mod usage_tracker;
+
+pub use usage::{DailyUsage, UsageMetric};
+pub use usage_storage_memory::MemoryUsageStorage;
+pub use usage_storage_redis::RedisUsageStorage;
+pub use usage_storage_trait::UsageStorage;
+pub use usage_tracker::{UsageReservation, UsageTracker};
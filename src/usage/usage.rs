@@ -0,0 +1,32 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UsageMetric {
+    BytesUploaded,
+    /// A running total rather than a daily sum: media deleted lower it.
+    BytesStored,
+    DerivativesGenerated,
+    BytesServed,
+}
+
+impl UsageMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageMetric::BytesUploaded => "bytes_uploaded",
+            UsageMetric::BytesStored => "bytes_stored",
+            UsageMetric::DerivativesGenerated => "derivatives_generated",
+            UsageMetric::BytesServed => "bytes_served",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub bytes_uploaded: u64,
+    /// Bytes stored at the end of the day.
+    pub bytes_stored: u64,
+    pub derivatives_generated: u64,
+    pub bytes_served: u64,
+}
@@ -0,0 +1,155 @@
+use chrono::{Duration, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+
+use crate::usage::{DailyUsage, UsageMetric, UsageStorage};
+
+#[derive(Default)]
+struct Counters {
+    /// The counters of each account and day, by metric.
+    daily: HashMap<(String, NaiveDate), HashMap<&'static str, i64>>,
+    bytes_stored: HashMap<String, i64>,
+}
+
+/// Keeps the usage counters in memory, for development and tests. They are lost
+/// when the server stops.
+pub struct MemoryUsageStorage {
+    counters: Mutex<Counters>,
+    /// Days a daily counter is kept.
+    retention_days: i64,
+}
+
+impl MemoryUsageStorage {
+    pub fn new(retention_days: i64) -> Self {
+        Self {
+            counters: Mutex::default(),
+            retention_days,
+        }
+    }
+}
+
+impl MemoryUsageStorage {
+    fn add(
+        &self,
+        counters: &mut Counters,
+        account: &str,
+        date: NaiveDate,
+        metric: UsageMetric,
+        amount: i64,
+    ) {
+        let oldest = Utc::now().date_naive() - Duration::days(self.retention_days);
+        counters.daily.retain(|(_, date), _| *date >= oldest);
+
+        match metric {
+            UsageMetric::BytesStored => {
+                let bytes_stored = counters
+                    .bytes_stored
+                    .entry(account.to_string())
+                    .or_default();
+                *bytes_stored += amount;
+                let bytes_stored = *bytes_stored;
+
+                // Snapshot of the running total, so past days keep their value.
+                counters
+                    .daily
+                    .entry((account.to_string(), date))
+                    .or_default()
+                    .insert(metric.as_str(), bytes_stored.max(0));
+            }
+            _ => {
+                *counters
+                    .daily
+                    .entry((account.to_string(), date))
+                    .or_default()
+                    .entry(metric.as_str())
+                    .or_default() += amount;
+            }
+        }
+    }
+}
+
+impl UsageStorage for MemoryUsageStorage {
+    fn record(
+        &self,
+        account: &str,
+        date: NaiveDate,
+        metric: UsageMetric,
+        amount: i64,
+    ) -> Result<(), Box<dyn Error>> {
+        self.add(&mut self.counters.lock().unwrap(), account, date, metric, amount);
+
+        Ok(())
+    }
+
+    fn reserve(
+        &self,
+        account: &str,
+        date: NaiveDate,
+        metric: UsageMetric,
+        amount: u64,
+        limit: u64,
+    ) -> Result<Option<u64>, Box<dyn Error>> {
+        let mut counters = self.counters.lock().unwrap();
+
+        let used = match metric {
+            UsageMetric::BytesStored => counters.bytes_stored.get(account).copied(),
+            _ => counters
+                .daily
+                .get(&(account.to_string(), date))
+                .and_then(|day| day.get(metric.as_str()))
+                .copied(),
+        };
+        let used = used.unwrap_or(0).max(0) as u64;
+
+        if used + amount > limit {
+            return Ok(Some(used));
+        }
+        self.add(&mut counters, account, date, metric, amount as i64);
+
+        Ok(None)
+    }
+
+    fn get_daily(
+        &self,
+        account: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyUsage>, Box<dyn Error>> {
+        let counters = self.counters.lock().unwrap();
+        let mut daily_usages = Vec::new();
+        let mut bytes_stored: Option<u64> = None;
+
+        for date in from.iter_days().take_while(|date| *date <= to) {
+            let day = counters.daily.get(&(account.to_string(), date));
+            let counter = |metric: UsageMetric| {
+                day.and_then(|day| day.get(metric.as_str()))
+                    .map(|value| (*value).max(0) as u64)
+            };
+
+            // Days without a change keep the total of the previous one.
+            bytes_stored = counter(UsageMetric::BytesStored).or(bytes_stored);
+
+            daily_usages.push(DailyUsage {
+                date,
+                bytes_uploaded: counter(UsageMetric::BytesUploaded).unwrap_or(0),
+                bytes_stored: bytes_stored.unwrap_or(0),
+                derivatives_generated: counter(UsageMetric::DerivativesGenerated).unwrap_or(0),
+                bytes_served: counter(UsageMetric::BytesServed).unwrap_or(0),
+            });
+        }
+
+        Ok(daily_usages)
+    }
+
+    fn get_bytes_stored(&self, account: &str) -> Result<u64, Box<dyn Error>> {
+        let counters = self.counters.lock().unwrap();
+
+        Ok(counters
+            .bytes_stored
+            .get(account)
+            .copied()
+            .unwrap_or(0)
+            .max(0) as u64)
+    }
+}
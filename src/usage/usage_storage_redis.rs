@@ -0,0 +1,176 @@
+use chrono::NaiveDate;
+use redis::{Connection, Script};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use crate::usage::{DailyUsage, UsageMetric, UsageStorage};
+
+const USAGE_PREFIX_KEY: &str = "internal:usage:";
+
+/// Adds to the running total and snapshots it into the daily counters, unless
+/// it would go past the limit. Returns the total that left no room, or -1.
+const RESERVE_STORED_SCRIPT: &str = r"
+local used = math.max(tonumber(redis.call('GET', KEYS[1]) or '0'), 0)
+if used + tonumber(ARGV[2]) > tonumber(ARGV[3]) then
+    return used
+end
+local total = redis.call('INCRBY', KEYS[1], ARGV[2])
+redis.call('HSET', KEYS[2], ARGV[1], math.max(total, 0))
+redis.call('EXPIRE', KEYS[2], ARGV[4])
+return -1
+";
+
+/// Adds to a daily counter, unless it would go past the limit. Returns the
+/// counter that left no room, or -1.
+const RESERVE_DAILY_SCRIPT: &str = r"
+local used = math.max(tonumber(redis.call('HGET', KEYS[1], ARGV[1]) or '0'), 0)
+if used + tonumber(ARGV[2]) > tonumber(ARGV[3]) then
+    return used
+end
+redis.call('HINCRBY', KEYS[1], ARGV[1], ARGV[2])
+redis.call('EXPIRE', KEYS[1], ARGV[4])
+return -1
+";
+
+pub struct RedisUsageStorage {
+    conn: Arc<Mutex<Connection>>,
+    /// Days a daily counter is kept.
+    retention_days: i64,
+    reserve_stored_script: Script,
+    reserve_daily_script: Script,
+}
+
+impl RedisUsageStorage {
+    pub fn new(conn: Connection, retention_days: i64) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+            retention_days,
+            reserve_stored_script: Script::new(RESERVE_STORED_SCRIPT),
+            reserve_daily_script: Script::new(RESERVE_DAILY_SCRIPT),
+        }
+    }
+
+    fn daily_key(account: &str, date: NaiveDate) -> String {
+        format!("{}{}:{}", USAGE_PREFIX_KEY, account, date.format("%Y-%m-%d"))
+    }
+
+    fn bytes_stored_key(account: &str) -> String {
+        format!("{}{}:bytes_stored", USAGE_PREFIX_KEY, account)
+    }
+}
+
+impl UsageStorage for RedisUsageStorage {
+    fn record(
+        &self,
+        account: &str,
+        date: NaiveDate,
+        metric: UsageMetric,
+        amount: i64,
+    ) -> Result<(), Box<dyn Error>> {
+        let daily_key = Self::daily_key(account, date);
+        let mut conn = self.conn.lock().unwrap();
+
+        match metric {
+            UsageMetric::BytesStored => {
+                let bytes_stored: i64 = redis::cmd("INCRBY")
+                    .arg(Self::bytes_stored_key(account))
+                    .arg(amount)
+                    .query(&mut *conn)?;
+
+                // Snapshot of the running total, so past days keep their value.
+                redis::cmd("HSET")
+                    .arg(&daily_key)
+                    .arg(metric.as_str())
+                    .arg(bytes_stored.max(0))
+                    .query::<()>(&mut *conn)?;
+            }
+            _ => {
+                redis::cmd("HINCRBY")
+                    .arg(&daily_key)
+                    .arg(metric.as_str())
+                    .arg(amount)
+                    .query::<()>(&mut *conn)?;
+            }
+        }
+
+        redis::cmd("EXPIRE")
+            .arg(&daily_key)
+            .arg(self.retention_days * 24 * 60 * 60)
+            .query::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    fn reserve(
+        &self,
+        account: &str,
+        date: NaiveDate,
+        metric: UsageMetric,
+        amount: u64,
+        limit: u64,
+    ) -> Result<Option<u64>, Box<dyn Error>> {
+        let daily_key = Self::daily_key(account, date);
+
+        let mut invocation = match metric {
+            UsageMetric::BytesStored => {
+                let mut invocation = self.reserve_stored_script.prepare_invoke();
+                invocation.key(Self::bytes_stored_key(account)).key(&daily_key);
+                invocation
+            }
+            _ => {
+                let mut invocation = self.reserve_daily_script.prepare_invoke();
+                invocation.key(&daily_key);
+                invocation
+            }
+        };
+
+        let used: i64 = invocation
+            .arg(metric.as_str())
+            .arg(amount)
+            .arg(limit)
+            .arg(self.retention_days * 24 * 60 * 60)
+            .invoke(&mut *self.conn.lock().unwrap())?;
+
+        Ok((used >= 0).then_some(used as u64))
+    }
+
+    fn get_daily(
+        &self,
+        account: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyUsage>, Box<dyn Error>> {
+        let mut daily_usages = Vec::new();
+        let mut bytes_stored: Option<u64> = None;
+
+        for date in from.iter_days().take_while(|date| *date <= to) {
+            let counters: HashMap<String, u64> = redis::cmd("HGETALL")
+                .arg(Self::daily_key(account, date))
+                .query(&mut self.conn.lock().unwrap())?;
+
+            let counter = |metric: UsageMetric| counters.get(metric.as_str()).copied();
+
+            // Days without a change keep the total of the previous one.
+            bytes_stored = counter(UsageMetric::BytesStored).or(bytes_stored);
+
+            daily_usages.push(DailyUsage {
+                date,
+                bytes_uploaded: counter(UsageMetric::BytesUploaded).unwrap_or(0),
+                bytes_stored: bytes_stored.unwrap_or(0),
+                derivatives_generated: counter(UsageMetric::DerivativesGenerated).unwrap_or(0),
+                bytes_served: counter(UsageMetric::BytesServed).unwrap_or(0),
+            });
+        }
+
+        Ok(daily_usages)
+    }
+
+    fn get_bytes_stored(&self, account: &str) -> Result<u64, Box<dyn Error>> {
+        let bytes_stored: Option<i64> = redis::cmd("GET")
+            .arg(Self::bytes_stored_key(account))
+            .query(&mut self.conn.lock().unwrap())?;
+
+        Ok(bytes_stored.unwrap_or(0).max(0) as u64)
+    }
+}
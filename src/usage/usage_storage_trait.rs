@@ -0,0 +1,34 @@
+use std::error::Error;
+
+use chrono::NaiveDate;
+
+use crate::usage::{DailyUsage, UsageMetric};
+
+pub trait UsageStorage: Send + Sync {
+    /// Adds `amount` to the counter of `metric` for `account` on `date`.
+    fn record(
+        &self,
+        account: &str,
+        date: NaiveDate,
+        metric: UsageMetric,
+        amount: i64,
+    ) -> Result<(), Box<dyn Error>>;
+    /// Adds `amount` like `record`, unless the counter would go past `limit`, in
+    /// which case it is left unchanged and its value is returned.
+    fn reserve(
+        &self,
+        account: &str,
+        date: NaiveDate,
+        metric: UsageMetric,
+        amount: u64,
+        limit: u64,
+    ) -> Result<Option<u64>, Box<dyn Error>>;
+    /// One entry per day from `from` to `to`, both included.
+    fn get_daily(
+        &self,
+        account: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyUsage>, Box<dyn Error>>;
+    fn get_bytes_stored(&self, account: &str) -> Result<u64, Box<dyn Error>>;
+}
@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use chrono::{NaiveDate, Utc};
+use log::error;
+
+use crate::config::{QuotaConfig, UsageConfig};
+use crate::error::MindiaError;
+use crate::usage::{UsageMetric, UsageStorage};
+
+/// Records usage and enforces the quotas of the accounts.
+#[derive(Clone)]
+pub struct UsageTracker {
+    storage: Arc<dyn UsageStorage>,
+    config: UsageConfig,
+}
+
+impl UsageTracker {
+    pub fn new(storage: Arc<dyn UsageStorage>, config: UsageConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Fails with a quota error if `account` cannot store `bytes` more and
+    /// generate `derivatives` more today.
+    pub fn check_upload_quota(
+        &self,
+        account: &str,
+        bytes: u64,
+        derivatives: u64,
+    ) -> Result<(), MindiaError> {
+        let quota = self.config.quota_for(account);
+
+        if let Some(limit) = quota.max_bytes_stored {
+            let used = self.storage.get_bytes_stored(account)?;
+            if used + bytes > limit {
+                return Err(MindiaError::quota_exceeded("max_bytes_stored", limit, used));
+            }
+        }
+
+        if quota.max_bytes_uploaded_per_day.is_none() && quota.max_derivatives_per_day.is_none() {
+            return Ok(());
+        }
+
+        let today = Utc::now().date_naive();
+        let usage = self.storage.get_daily(account, today, today)?;
+        let usage = usage.first();
+
+        if let Some(limit) = quota.max_bytes_uploaded_per_day {
+            let used = usage.map_or(0, |usage| usage.bytes_uploaded);
+            if used + bytes > limit {
+                return Err(MindiaError::quota_exceeded(
+                    "max_bytes_uploaded_per_day",
+                    limit,
+                    used,
+                ));
+            }
+        }
+
+        if let Some(limit) = quota.max_derivatives_per_day {
+            let used = usage.map_or(0, |usage| usage.derivatives_generated);
+            if used + derivatives > limit {
+                return Err(MindiaError::quota_exceeded(
+                    "max_derivatives_per_day",
+                    limit,
+                    used,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reserves the usage of an upload, failing with a quota error if it does not
+    /// fit. Unlike `check_upload_quota`, concurrent uploads cannot get past a quota
+    /// together.
+    pub fn reserve_upload(
+        &self,
+        account: &str,
+        bytes: u64,
+        derivatives: u64,
+    ) -> Result<UsageReservation, MindiaError> {
+        let quota = self.config.quota_for(account);
+        let mut reservation = UsageReservation::new(self.storage.clone(), account);
+
+        reservation.reserve(
+            UsageMetric::BytesStored,
+            bytes,
+            quota.max_bytes_stored,
+            "max_bytes_stored",
+        )?;
+        reservation.reserve(
+            UsageMetric::BytesUploaded,
+            bytes,
+            quota.max_bytes_uploaded_per_day,
+            "max_bytes_uploaded_per_day",
+        )?;
+        reservation.reserve(
+            UsageMetric::DerivativesGenerated,
+            derivatives,
+            quota.max_derivatives_per_day,
+            "max_derivatives_per_day",
+        )?;
+
+        Ok(reservation)
+    }
+
+    /// Reserves the bytes of a copy, which only add to the storage.
+    pub fn reserve_storage(
+        &self,
+        account: &str,
+        bytes: u64,
+    ) -> Result<UsageReservation, MindiaError> {
+        let quota = self.config.quota_for(account);
+        let mut reservation = UsageReservation::new(self.storage.clone(), account);
+
+        reservation.reserve(
+            UsageMetric::BytesStored,
+            bytes,
+            quota.max_bytes_stored,
+            "max_bytes_stored",
+        )?;
+
+        Ok(reservation)
+    }
+
+    /// Reserves a derivative generated on download.
+    pub fn reserve_derivative(&self, account: &str) -> Result<UsageReservation, MindiaError> {
+        let quota = self.config.quota_for(account);
+        let mut reservation = UsageReservation::new(self.storage.clone(), account);
+
+        reservation.reserve(
+            UsageMetric::DerivativesGenerated,
+            1,
+            quota.max_derivatives_per_day,
+            "max_derivatives_per_day",
+        )?;
+
+        Ok(reservation)
+    }
+
+    /// Accounting never fails the request it is made for, errors are logged.
+    pub fn record(&self, account: &str, metric: UsageMetric, amount: i64) {
+        if amount == 0 {
+            return;
+        }
+
+        let today = Utc::now().date_naive();
+        if let Err(e) = self.storage.record(account, today, metric, amount) {
+            error!("Error: {}", e);
+        }
+    }
+
+    pub fn quota_for(&self, account: &str) -> &QuotaConfig {
+        self.config.quota_for(account)
+    }
+
+    pub fn storage(&self) -> &Arc<dyn UsageStorage> {
+        &self.storage
+    }
+}
+
+/// Usage counted for a request before it is done. It is given back when dropped,
+/// unless the request commits it.
+pub struct UsageReservation {
+    storage: Arc<dyn UsageStorage>,
+    account: String,
+    date: NaiveDate,
+    reserved: Vec<(UsageMetric, u64)>,
+}
+
+impl UsageReservation {
+    fn new(storage: Arc<dyn UsageStorage>, account: &str) -> Self {
+        Self {
+            storage,
+            account: account.to_string(),
+            date: Utc::now().date_naive(),
+            reserved: Vec::new(),
+        }
+    }
+
+    fn reserve(
+        &mut self,
+        metric: UsageMetric,
+        amount: u64,
+        limit: Option<u64>,
+        quota: &str,
+    ) -> Result<(), MindiaError> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        match limit {
+            Some(limit) => {
+                let refused =
+                    self.storage
+                        .reserve(&self.account, self.date, metric, amount, limit)?;
+                if let Some(used) = refused {
+                    return Err(MindiaError::quota_exceeded(quota, limit, used));
+                }
+            }
+            None => {
+                self.storage
+                    .record(&self.account, self.date, metric, amount as i64)?;
+            }
+        }
+
+        self.reserved.push((metric, amount));
+
+        Ok(())
+    }
+
+    /// Corrects the reservation of `metric` to the `amount` actually used.
+    pub fn settle(&mut self, metric: UsageMetric, amount: u64) {
+        let reserved = self
+            .reserved
+            .iter()
+            .filter(|(reserved_metric, _)| *reserved_metric == metric)
+            .map(|(_, reserved)| *reserved)
+            .sum::<u64>();
+
+        if amount != reserved {
+            if let Err(e) = self.storage.record(
+                &self.account,
+                self.date,
+                metric,
+                amount as i64 - reserved as i64,
+            ) {
+                error!("Error: {}", e);
+                return;
+            }
+        }
+
+        self.reserved.retain(|(reserved_metric, _)| *reserved_metric != metric);
+        self.reserved.push((metric, amount));
+    }
+
+    pub fn commit(mut self) {
+        self.reserved.clear();
+    }
+}
+
+impl Drop for UsageReservation {
+    fn drop(&mut self) {
+        for (metric, amount) in &self.reserved {
+            let given_back =
+                self.storage
+                    .record(&self.account, self.date, *metric, -(*amount as i64));
+            if let Err(e) = given_back {
+                error!("Error: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usage::MemoryUsageStorage;
+
+    #[test]
+    fn test_reservations_are_given_back_unless_committed() {
+        let storage = Arc::new(MemoryUsageStorage::new(30));
+        let mut config = UsageConfig::default();
+        config.default_quota.max_bytes_stored = Some(100);
+        config.default_quota.max_derivatives_per_day = Some(1);
+        let tracker = UsageTracker::new(storage.clone(), config);
+
+        let reservation = tracker.reserve_upload("key", 60, 0).unwrap();
+        // The bytes reserved count against a concurrent upload.
+        assert!(tracker.reserve_upload("key", 60, 0).is_err());
+        drop(reservation);
+        assert_eq!(storage.get_bytes_stored("key").unwrap(), 0);
+
+        let mut reservation = tracker.reserve_upload("key", 60, 1).unwrap();
+        reservation.settle(UsageMetric::BytesStored, 40);
+        reservation.commit();
+        assert_eq!(storage.get_bytes_stored("key").unwrap(), 40);
+
+        // The derivative reserved by the upload leaves none to generate on download.
+        assert!(tracker.reserve_derivative("key").is_err());
+        assert_eq!(storage.get_bytes_stored("key").unwrap(), 40);
+
+        // Copies count against the same storage quota.
+        assert!(tracker.reserve_storage("key", 70).is_err());
+        tracker.reserve_storage("key", 60).unwrap().commit();
+        assert_eq!(storage.get_bytes_stored("key").unwrap(), 100);
+    }
+}
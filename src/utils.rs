@@ -1,8 +1,13 @@
 use hex;
 use rand::Rng;
+use sha2::{Digest, Sha256};
 
 pub fn generate_apikey() -> String {
     let mut rng = rand::thread_rng();
     let b: [u8; 16] = rng.gen();
     hex::encode(b)
 }
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
@@ -0,0 +1,14 @@
+pub mod webhook;
+pub mod webhook_dispatcher;
+pub mod webhook_storage_memory;
+pub mod webhook_storage_redis;
+pub mod webhook_storage_trait;
+
+pub use webhook::{
+    resolve_webhook_url, sign_payload, validate_name, DeliveryAttempt, Webhook, WebhookEvent, WebhookMap,
+    WebhookPayload,
+};
+pub use webhook_dispatcher::WebhookDispatcher;
+pub use webhook_storage_memory::MemoryWebhookStorage;
+pub use webhook_storage_redis::RedisWebhookStorage;
+pub use webhook_storage_trait::WebhookStorage;
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+use reqwest::Url;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const MAX_NAME_LENGTH: usize = 64;
+
+pub type WebhookMap = HashMap<String, Webhook>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "media.uploaded")]
+    MediaUploaded,
+    #[serde(rename = "media.deleted")]
+    MediaDeleted,
+    #[serde(rename = "media.moved")]
+    MediaMoved,
+    #[serde(rename = "media.copied")]
+    MediaCopied,
+    #[serde(rename = "media.derivative_created")]
+    DerivativeCreated,
+    #[serde(rename = "task.completed")]
+    TaskCompleted,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::MediaUploaded => "media.uploaded",
+            WebhookEvent::MediaDeleted => "media.deleted",
+            WebhookEvent::MediaMoved => "media.moved",
+            WebhookEvent::MediaCopied => "media.copied",
+            WebhookEvent::DerivativeCreated => "media.derivative_created",
+            WebhookEvent::TaskCompleted => "task.completed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub name: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    /// Key of the HMAC signature sent along every payload.
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn is_subscribed(&self, event: WebhookEvent) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+/// The JSON body posted to the webhooks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    /// Identifies the delivery across its attempts.
+    pub id: String,
+    pub event: WebhookEvent,
+    pub project: String,
+    pub created_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+/// One attempt at delivering a payload, kept in the delivery log of its webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    pub delivery_id: String,
+    pub event: WebhookEvent,
+    pub attempt: u32,
+    pub attempted_at: DateTime<Utc>,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub success: bool,
+}
+
+/// Names are keys of the stored document and of the deliveries, so they are
+/// limited to letters, digits, `-` and `_`.
+pub fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err("Webhook name must have between 1 and 64 characters");
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err("Webhook name may only contain letters, digits, - and _");
+    }
+
+    Ok(())
+}
+
+/// Signature of `payload` sent at `timestamp`, as found in the
+/// `Mindia-Signature: t=<timestamp>,v1=<signature>` header.
+pub fn sign_payload(secret: &str, timestamp: i64, payload: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.{}", timestamp, payload).as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Whether deliveries may reach `ip`. Loopback, private, link-local and the other
+/// non-routable addresses would let a webhook probe the internal network.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space, 100.64.0.0/10.
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_address(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local, fc00::/7, and link-local, fe80::/10.
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Resolves the host of a webhook URL to the address its deliveries are sent to.
+/// Every address of the host must be public, unless the host is one of
+/// `allowed_hosts`.
+pub async fn resolve_webhook_url(url: &Url, allowed_hosts: &[String]) -> Result<SocketAddr, String> {
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| "Webhook URL has no port".to_string())?;
+
+    let name = url
+        .host_str()
+        .ok_or_else(|| "Webhook URL has no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+
+    let addresses: Vec<SocketAddr> = match name.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((name.as_str(), port))
+            .await
+            .map_err(|e| format!("Cannot resolve {}: {}", name, e))?
+            .collect(),
+    };
+
+    let allowed = allowed_hosts
+        .iter()
+        .any(|host| host.eq_ignore_ascii_case(&name));
+    if !allowed && addresses.iter().any(|address| !is_public_address(address.ip())) {
+        return Err(format!("{} resolves to a non-public address", name));
+    }
+
+    addresses
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public_address() {
+        for ip in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_webhook_url_rejects_internal_hosts() {
+        let url = Url::parse("http://127.0.0.1:8080/hook").unwrap();
+        assert!(resolve_webhook_url(&url, &[]).await.is_err());
+
+        let allowed = vec!["127.0.0.1".to_string()];
+        let address = resolve_webhook_url(&url, &allowed).await.unwrap();
+        assert_eq!(address, "127.0.0.1:8080".parse().unwrap());
+
+        let url = Url::parse("http://[::1]/hook").unwrap();
+        assert!(resolve_webhook_url(&url, &[]).await.is_err());
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("media-sync_2").is_ok());
+
+        for name in ["", ".", "$", "a.b", "a b", "a:b", &"a".repeat(65)] {
+            assert!(validate_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        let signature = sign_payload("secret", 1700000000, r#"{"event":"media.uploaded"}"#);
+
+        assert_eq!(signature.len(), 64);
+        assert_eq!(
+            signature,
+            sign_payload("secret", 1700000000, r#"{"event":"media.uploaded"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign_payload("secret", 1700000001, r#"{"event":"media.uploaded"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign_payload("other", 1700000000, r#"{"event":"media.uploaded"}"#)
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use chrono::Utc;
+use log::error;
+use serde_json::Value;
+
+use crate::scheduler::{Details, Task, TaskKind, TaskStorage};
+use crate::webhook::{WebhookEvent, WebhookPayload, WebhookStorage};
+
+/// Queues a delivery task for every webhook of a project subscribed to an event.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    project: String,
+    webhook_storage: Arc<dyn WebhookStorage>,
+    task_storage: Arc<dyn TaskStorage>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(
+        project: String,
+        webhook_storage: Arc<dyn WebhookStorage>,
+        task_storage: Arc<dyn TaskStorage>,
+    ) -> Self {
+        Self {
+            project,
+            webhook_storage,
+            task_storage,
+        }
+    }
+
+    /// Notifying never fails the operation it is made for, errors are logged.
+    pub fn dispatch(&self, event: WebhookEvent, data: Value) {
+        if let Err(e) = self.queue_deliveries(event, data) {
+            error!("Error: {}", e);
+        }
+    }
+
+    fn queue_deliveries(&self, event: WebhookEvent, data: Value) -> Result<(), Box<dyn Error>> {
+        let webhooks: Vec<String> = self
+            .webhook_storage
+            .get_all()?
+            .into_values()
+            .filter(|webhook| webhook.is_subscribed(event))
+            .map(|webhook| webhook.name)
+            .collect();
+
+        if webhooks.is_empty() {
+            return Ok(());
+        }
+
+        let payload = WebhookPayload {
+            id: uuid::Uuid::new_v4().to_string(),
+            event,
+            project: self.project.clone(),
+            created_at: Utc::now(),
+            data,
+        };
+        // Every attempt sends the same bytes.
+        let body = serde_json::to_string(&payload)?;
+
+        for webhook in webhooks {
+            self.task_storage.push(Task::new(
+                self.project.clone(),
+                TaskKind::WebhookDelivery,
+                Details::WebhookDelivery {
+                    webhook,
+                    delivery_id: payload.id.clone(),
+                    event,
+                    payload: body.clone(),
+                    attempt: 0,
+                },
+            ))?;
+        }
+
+        Ok(())
+    }
+}
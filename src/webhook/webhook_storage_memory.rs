@@ -0,0 +1,113 @@
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use super::{DeliveryAttempt, Webhook, WebhookMap, WebhookStorage};
+use crate::project::DEFAULT_PROJECT;
+
+#[derive(Default)]
+struct ProjectWebhooks {
+    webhooks: WebhookMap,
+    /// The delivery log of each webhook, most recent attempt first.
+    deliveries: HashMap<String, VecDeque<DeliveryAttempt>>,
+}
+
+/// Keeps the webhooks and their delivery logs in memory, for development and
+/// tests. They are lost when the server stops.
+pub struct MemoryWebhookStorage {
+    projects: Arc<RwLock<HashMap<String, ProjectWebhooks>>>,
+    project: String,
+}
+
+impl MemoryWebhookStorage {
+    pub fn new() -> Self {
+        Self {
+            projects: Arc::default(),
+            project: DEFAULT_PROJECT.to_string(),
+        }
+    }
+}
+
+impl Default for MemoryWebhookStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookStorage for MemoryWebhookStorage {
+    fn get_all(&self) -> Result<WebhookMap, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+
+        Ok(projects
+            .get(&self.project)
+            .map(|project| project.webhooks.clone())
+            .unwrap_or_default())
+    }
+
+    fn get_by_name(&self, name: &str) -> Result<Option<Webhook>, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+
+        Ok(projects
+            .get(&self.project)
+            .and_then(|project| project.webhooks.get(name))
+            .cloned())
+    }
+
+    fn save(&self, webhook: Webhook) -> Result<(), Box<dyn Error>> {
+        self.projects
+            .write()
+            .unwrap()
+            .entry(self.project.clone())
+            .or_default()
+            .webhooks
+            .insert(webhook.name.clone(), webhook);
+
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(project) = self.projects.write().unwrap().get_mut(&self.project) {
+            project.webhooks.remove(name);
+            project.deliveries.remove(name);
+        }
+
+        Ok(())
+    }
+
+    fn log_attempt(
+        &self,
+        webhook: &str,
+        attempt: &DeliveryAttempt,
+        keep: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut projects = self.projects.write().unwrap();
+        let deliveries = projects
+            .entry(self.project.clone())
+            .or_default()
+            .deliveries
+            .entry(webhook.to_string())
+            .or_default();
+
+        deliveries.push_front(attempt.clone());
+        deliveries.truncate(keep.max(1));
+
+        Ok(())
+    }
+
+    fn get_attempts(&self, webhook: &str) -> Result<Vec<DeliveryAttempt>, Box<dyn Error>> {
+        let projects = self.projects.read().unwrap();
+
+        Ok(projects
+            .get(&self.project)
+            .and_then(|project| project.deliveries.get(webhook))
+            .map(|deliveries| deliveries.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn for_project(&self, project: &str) -> Result<Arc<dyn WebhookStorage>, Box<dyn Error>> {
+        Ok(Arc::new(Self {
+            projects: self.projects.clone(),
+            project: project.to_string(),
+        }))
+    }
+}
@@ -0,0 +1,154 @@
+use redis::{Connection, ErrorKind, RedisError};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+const WEBHOOKS_KEY: &str = "internal:configuration:webhooks";
+const DELIVERIES_PREFIX_KEY: &str = "internal:webhook_deliveries:";
+
+use super::{validate_name, DeliveryAttempt, Webhook, WebhookMap, WebhookStorage};
+use crate::project::{namespaced_key, DEFAULT_PROJECT};
+
+pub struct RedisWebhookStorage {
+    conn: Arc<Mutex<Connection>>,
+    key: String,
+    deliveries_prefix_key: String,
+}
+
+impl RedisWebhookStorage {
+    pub fn new(conn: Connection) -> Result<Self, Box<dyn Error>> {
+        Self::with_project(Arc::new(Mutex::new(conn)), DEFAULT_PROJECT)
+    }
+
+    fn with_project(conn: Arc<Mutex<Connection>>, project: &str) -> Result<Self, Box<dyn Error>> {
+        let storage = RedisWebhookStorage {
+            conn,
+            key: namespaced_key(project, WEBHOOKS_KEY),
+            deliveries_prefix_key: namespaced_key(project, DELIVERIES_PREFIX_KEY),
+        };
+        storage.init()?;
+        Ok(storage)
+    }
+
+    fn init(&self) -> Result<(), Box<dyn Error>> {
+        let exists: bool = redis::cmd("EXISTS")
+            .arg(&self.key)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        if !exists {
+            redis::cmd("JSON.SET")
+                .arg(&self.key)
+                .arg(".")
+                .arg("{}")
+                .query::<()>(&mut self.conn.lock().unwrap())?;
+        }
+
+        Ok(())
+    }
+
+    /// Names are validated before they reach a path, which they would otherwise
+    /// be able to escape.
+    fn webhook_path(name: &str) -> Result<String, Box<dyn Error>> {
+        validate_name(name)?;
+
+        Ok(format!(".{}", name))
+    }
+
+    fn deliveries_key(&self, webhook: &str) -> String {
+        format!("{}{}", self.deliveries_prefix_key, webhook)
+    }
+}
+
+impl WebhookStorage for RedisWebhookStorage {
+    fn get_all(&self) -> Result<WebhookMap, Box<dyn Error>> {
+        let result: String = redis::cmd("JSON.GET")
+            .arg(&self.key)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        serde_json::from_str(&result).map_err(|e| e.into())
+    }
+
+    fn get_by_name(&self, name: &str) -> Result<Option<Webhook>, Box<dyn Error>> {
+        // No webhook can be saved under an invalid name.
+        let path = match Self::webhook_path(name) {
+            Ok(path) => path,
+            Err(_) => return Ok(None),
+        };
+
+        let result: Result<String, RedisError> = redis::cmd("JSON.GET")
+            .arg(&self.key)
+            .arg(path)
+            .query(&mut self.conn.lock().unwrap());
+
+        match result {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            // RedisJSON answers an error for a path that does not exist.
+            Err(e) if e.kind() == ErrorKind::ResponseError => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, webhook: Webhook) -> Result<(), Box<dyn Error>> {
+        let path = Self::webhook_path(&webhook.name)?;
+        let webhook_json = serde_json::to_string(&webhook)?;
+
+        redis::cmd("JSON.SET")
+            .arg(&self.key)
+            .arg(path)
+            .arg(webhook_json)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        redis::cmd("JSON.DEL")
+            .arg(&self.key)
+            .arg(Self::webhook_path(name)?)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        redis::cmd("DEL")
+            .arg(self.deliveries_key(name))
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        Ok(())
+    }
+
+    fn log_attempt(
+        &self,
+        webhook: &str,
+        attempt: &DeliveryAttempt,
+        keep: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let key = self.deliveries_key(webhook);
+
+        redis::cmd("LPUSH")
+            .arg(&key)
+            .arg(serde_json::to_string(attempt)?)
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        redis::cmd("LTRIM")
+            .arg(&key)
+            .arg(0)
+            .arg(keep.saturating_sub(1))
+            .query::<()>(&mut self.conn.lock().unwrap())?;
+
+        Ok(())
+    }
+
+    fn get_attempts(&self, webhook: &str) -> Result<Vec<DeliveryAttempt>, Box<dyn Error>> {
+        let attempts: Vec<String> = redis::cmd("LRANGE")
+            .arg(self.deliveries_key(webhook))
+            .arg(0)
+            .arg(-1)
+            .query(&mut self.conn.lock().unwrap())?;
+
+        attempts
+            .iter()
+            .map(|attempt| serde_json::from_str(attempt).map_err(|e| e.into()))
+            .collect()
+    }
+
+    fn for_project(&self, project: &str) -> Result<Arc<dyn WebhookStorage>, Box<dyn Error>> {
+        Ok(Arc::new(Self::with_project(self.conn.clone(), project)?))
+    }
+}
@@ -0,0 +1,22 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use super::{DeliveryAttempt, Webhook, WebhookMap};
+
+pub trait WebhookStorage: Send + Sync {
+    fn get_all(&self) -> Result<WebhookMap, Box<dyn Error>>;
+    fn get_by_name(&self, name: &str) -> Result<Option<Webhook>, Box<dyn Error>>;
+    fn save(&self, webhook: Webhook) -> Result<(), Box<dyn Error>>;
+    fn delete(&self, name: &str) -> Result<(), Box<dyn Error>>;
+    /// Appends to the delivery log of `webhook`, which keeps the last `keep` attempts.
+    fn log_attempt(
+        &self,
+        webhook: &str,
+        attempt: &DeliveryAttempt,
+        keep: usize,
+    ) -> Result<(), Box<dyn Error>>;
+    /// The delivery log of `webhook`, most recent attempt first.
+    fn get_attempts(&self, webhook: &str) -> Result<Vec<DeliveryAttempt>, Box<dyn Error>>;
+    /// The storage of the webhooks of `project`, kept apart from this one.
+    fn for_project(&self, project: &str) -> Result<Arc<dyn WebhookStorage>, Box<dyn Error>>;
+}